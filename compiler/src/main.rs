@@ -57,6 +57,7 @@ fn main() -> anyhow::Result<()> {
                 .code_model(code_model.into())
                 .optimize_level(optimize_level.into())
                 .reloc_mode(reloc_mode.into())
+                .debug_assertions(!release)
                 .build();
 
             let emit_options = EmitOptions::builder()
@@ -71,13 +72,20 @@ fn main() -> anyhow::Result<()> {
             let file_table = FileTable::new();
             let file_id = file_table.register_file(input_file.clone())?;
 
-            let ast = ParseContext::new(&file_table).parse(&CaraParser, file_id)?;
+            let ast = match ParseContext::new(&file_table).parse(&CaraParser, file_id) {
+                Ok(ast) => ast,
+                Err(errors) => {
+                    errors.dump(&file_table);
+                    exit(-1);
+                }
+            };
             let ast = simplify(crate_name.clone(), ast);
 
             let query_ctx = QueryContext::builder()
                 .crate_name(crate_name)
                 .ast(Arc::new(ast))
                 .target(target)
+                .source_map(file_table.source_map())
                 .build();
 
             let main_fn = query_ctx.main_fn_id();
@@ -0,0 +1,74 @@
+use ast::{Exp, ExpId, visitor::ExpVisitor};
+
+use crate::{
+    AnalyzerContext, Binding, Error, Symbol, Type, Value, number_pattern, queries::check_required,
+};
+
+impl AnalyzerContext {
+    /// Checks `[value; len]`, which is the array type `[T; len]` if `value`
+    /// is a type and an array of `len` copies of `value` otherwise. Since the
+    /// value of a type is the type itself, both are `[T; len]` here.
+    pub(crate) fn check_array_template(&mut self, value: ExpId, len: ExpId) -> Value {
+        let element = self.visit_right_value(value).into_type();
+        let len = self.array_len(len).unwrap_or_default();
+        Value::new(element.array(len))
+    }
+
+    /// Evaluates the length of an array, which has to be an integer known at
+    /// compile time. Array types with the same element type are equal if
+    /// their lengths evaluate to the same number, however they are written.
+    fn array_len(&mut self, len: ExpId) -> Option<u32> {
+        let len_ty = self.visit_right_value(len).into_type();
+        if !matches!(len_ty, number_pattern!()) {
+            self.error_at(Error::NonIntegerArrayLength(len_ty), len.span());
+            return None;
+        }
+        if !self.is_const_exp(len) {
+            self.error_at(Error::ExpectedConst, len.span());
+            return None;
+        }
+        // The length is evaluated right away, so the types of its literals
+        // and of those in the functions it calls have to be known by now.
+        self.infer_literals(false);
+        check_required(&self.ctx, &self.required);
+        let value = match const_eval::try_eval_exp(self.ctx.clone(), len, &self.bindings) {
+            Ok(value) => value.as_int(),
+            Err((error, span)) => {
+                self.const_eval_error_at(error, span);
+                return None;
+            }
+        };
+        match u32::try_from(value) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                self.error_at(Error::ArrayLengthOutOfRange(value), len.span());
+                None
+            }
+        }
+    }
+
+    pub(crate) fn is_const_exp(&self, exp: ExpId) -> bool {
+        let ast_ctx = self.ctx.ast_ctx();
+        match ast_ctx.exp(exp) {
+            Exp::Number(_) | Exp::Bool(..) | Exp::Builtin(_) => true,
+            Exp::Exp(inner, _) | Exp::Unary(_, inner, _) => self.is_const_exp(*inner),
+            Exp::Binary(_, lhs, rhs, _) => self.is_const_exp(*lhs) && self.is_const_exp(*rhs),
+            Exp::TypeCast(type_cast) => self.is_const_exp(type_cast.exp),
+            Exp::FieldAccess(field_access) => self.is_const_exp(field_access.lhs),
+            Exp::Index(index) => self.is_const_exp(index.exp) && self.is_const_exp(index.index),
+            Exp::Call(call) => {
+                self.is_const_exp(call.func) && call.args.iter().all(|&arg| self.is_const_exp(arg))
+            }
+            // `comptime` parameters are bound to their arguments.
+            Exp::Var(var) => {
+                let name = var.path.path.join("::");
+                match self.symbols.lookup(&name) {
+                    Some(Symbol::Var(_, Binding::Param(_), _)) => self.bindings.contains_key(&name),
+                    Some(Symbol::Var(..)) => false,
+                    _ => true,
+                }
+            }
+            _ => false,
+        }
+    }
+}
@@ -1,8 +1,9 @@
+use ast::Span;
 use thiserror::Error;
 
 use crate::Type;
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum Error {
     #[error("Invalid field {0}.")]
     InvalidField(String),
@@ -26,10 +27,91 @@ pub enum Error {
     BreakOutsideLoop,
     #[error("Continue statement outside of loop")]
     ContinueOutsideLoop,
+    #[error("No enclosing loop is labeled '{0}.")]
+    UndeclaredLabel(String),
+    #[error("Only `loop` can be left with a value.")]
+    BreakValueOutsideLoop,
+    #[error("Invalid variant {0} of {1}.")]
+    InvalidVariant(String, Type),
+    #[error("Pattern can't match value with type {0}.")]
+    PatternMismatch(Type),
+    #[error("Variant {0} doesn't carry a payload.")]
+    UnexpectedBinding(String),
+    #[error("Non-exhaustive match, missing {0}.")]
+    NonExhaustiveMatch(String),
+    #[error("Expected a type, found value of type {0}.")]
+    ExpectedType(Type),
+    #[error("Expected {0} arguments, found {1}.")]
+    ArgumentCount(usize, usize),
+    #[error("Alignment {0} is not a power of two.")]
+    InvalidAlignment(u64),
+    #[error("No field or method {0} on {1}.")]
+    UnknownMethod(String, Type),
+    #[error("{0} has no self parameter and can't be called as a method.")]
+    NotAMethod(String),
+    #[error("Method {0} takes self as {1}, which can't be passed from {2}.")]
+    ReceiverMismatch(String, Type, Type),
+    #[error("Can't destructure value of type {1} into {0} variables.")]
+    DestructureMismatch(usize, Type),
+    #[error("Expected an interface, found {0}.")]
+    ExpectedInterface(Type),
+    #[error("Interface method {0} must take self: *Self first.")]
+    InvalidInterfaceMethod(String),
+    #[error("Missing method {1} of interface {0}.")]
+    MissingInterfaceMethod(String, String),
+    #[error("Method {0} should have type {1} to implement the interface, found {2}.")]
+    InterfaceMethodMismatch(String, Type, Type),
+    #[error("{0} doesn't implement {1}.")]
+    DoesNotImplement(Type, Type),
+    #[error("Slicing {0} needs an end, since its length isn't known.")]
+    UnboundedSlice(Type),
+    #[error("Expected a const expression.")]
+    ExpectedConst,
+    #[error(transparent)]
+    ConstEval(const_eval::EvalError),
+    #[error("Array lengths must be integers, found {0}.")]
+    NonIntegerArrayLength(Type),
+    #[error("Array length {0} is out of range, it must be between 0 and {max}.", max = u32::MAX)]
+    ArrayLengthOutOfRange(i128),
+    #[error("Literal {0} is out of range for {1}.")]
+    LiteralOutOfRange(String, Type),
+    #[error("Can't assign to immutable variable {0}.")]
+    AssignToImmutable(String, Span),
+    #[error("Can't assign to parameter {0}.")]
+    AssignToParam(String, Span),
+    #[error("Can't assign to const {0}.")]
+    AssignToConst(String, Span),
+    #[error("Can't assign to a value that isn't a variable, dereference, element or field.")]
+    AssignToValue,
+    #[error("Can't assign twice to immutable variable {0}.")]
+    AssignTwice(String, Span),
+    #[error("Variable {0} is used before it is assigned.")]
+    Uninitialized(String),
+    #[error("Not every path returns a value of type {0}.")]
+    MissingReturn(Type),
 }
 
-#[derive(Debug, Error)]
+impl Error {
+    /// Where what the error is about was declared, and what to say there.
+    pub fn related(&self) -> Option<(String, Span)> {
+        match self {
+            Error::AssignToImmutable(name, span)
+            | Error::AssignToParam(name, span)
+            | Error::AssignToConst(name, span)
+            | Error::AssignTwice(name, span) => Some((format!("{} is declared here", name), *span)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Error)]
 pub enum Warning {
     #[error("{0}")]
     Custom(String),
+    #[error("Unreachable pattern.")]
+    UnreachablePattern,
+    #[error("Variable {0} is declared mut but never mutated.")]
+    UnusedMut(String),
+    #[error("Unreachable code.")]
+    UnreachableCode,
 }
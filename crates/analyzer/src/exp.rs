@@ -6,13 +6,13 @@ use std::{
 };
 
 use ast::{
-    Array, BinaryOp, ConstInitialValue, Span, TypeCast, TypeEnum, UnaryOp,
+    Array, BinaryOp, Builtin, ConstInitialValue, Exp, Span, TypeCast, TypeEnum, UnaryOp,
     visitor::{BlockVisitor, ExpVisitor},
 };
 use query::DefId;
 
 use crate::{
-    AnalyzerContext, Error, Symbol, Type, Value, number_pattern,
+    AnalyzerContext, Error, Place, Symbol, Type, Value, number_pattern,
     queries::{AnalyzeResult, CHECK_CONST_DEF},
 };
 
@@ -22,7 +22,7 @@ impl ExpVisitor<Value> for AnalyzerContext {
     }
 
     fn get_right_value(&self, left_value: Value) -> Value {
-        left_value
+        Value::new(self.resolve(left_value.type_()))
     }
 
     fn pass_left_value_as_right_value(&self, left_value: Value) -> Value {
@@ -30,8 +30,9 @@ impl ExpVisitor<Value> for AnalyzerContext {
     }
 
     fn visit_get_addr(&mut self, get_addr: &ast::GetAddr) -> Value {
-        let ty = self.visit_right_value(get_addr.exp).into_type();
-        Value::new(ty.pointer())
+        let value = self.visit_left_value(get_addr.exp);
+        self.note_mutation(&value);
+        Value::new(self.resolve(value.type_()).pointer())
     }
 
     fn visit_array(&mut self, array: &ast::Array) -> Value {
@@ -43,7 +44,7 @@ impl ExpVisitor<Value> for AnalyzerContext {
                     .collect::<Vec<_>>();
                 let should_be_type = types[0].clone();
                 for type_ in types.iter().skip(1) {
-                    if *type_ != should_be_type {
+                    if !self.unify(should_be_type.type_(), type_.type_()) {
                         self.error_at(
                             Error::TypeMismatch(
                                 should_be_type.clone().into_type(),
@@ -53,13 +54,28 @@ impl ExpVisitor<Value> for AnalyzerContext {
                         );
                     }
                 }
-                Value::new(should_be_type.type_().array(values.len() as u32))
+                Value::new(
+                    self.resolve(should_be_type.type_())
+                        .array(values.len() as u32),
+                )
             }
-            _ => unimplemented!(),
+            Array::Template(value, len, _) => self.check_array_template(*value, len.exp),
         }
     }
 
     fn visit_binary(&mut self, op: &BinaryOp, lhs: Value, rhs: Value, span: &Span) -> Value {
+        // An unsuffixed literal takes the type of the other operand.
+        let (lhs, rhs) = if !matches!(op, BinaryOp::And | BinaryOp::Or)
+            && (matches!(lhs.type_(), Type::IntVar(_)) || matches!(rhs.type_(), Type::IntVar(_)))
+            && self.unify(lhs.type_(), rhs.type_())
+        {
+            (
+                Value::new(self.resolve(lhs.type_())),
+                Value::new(self.resolve(rhs.type_())),
+            )
+        } else {
+            (lhs, rhs)
+        };
         let result = match op {
             BinaryOp::Add => lhs.check_add(&rhs),
             BinaryOp::Sub => lhs.check_sub(&rhs),
@@ -76,6 +92,9 @@ impl ExpVisitor<Value> for AnalyzerContext {
             BinaryOp::Ge => lhs.check_ge(&rhs),
             BinaryOp::And => lhs.check_and(&rhs),
             BinaryOp::Or => lhs.check_or(&rhs),
+            BinaryOp::BitAnd => lhs.check_bitand(&rhs),
+            BinaryOp::BitOr => lhs.check_bitor(&rhs),
+            BinaryOp::BitXor => lhs.check_bitxor(&rhs),
         };
         match result {
             Ok(value) => value,
@@ -87,18 +106,27 @@ impl ExpVisitor<Value> for AnalyzerContext {
     }
 
     fn visit_block(&mut self, block: &ast::Block) -> Value {
-        <Self as BlockVisitor<_>>::visit_block(self, block).unwrap_or(Value::new(Type::Unit))
+        let value =
+            <Self as BlockVisitor<_>>::visit_block(self, block).unwrap_or(Value::new(Type::Unit));
+        if self.flow.diverges(block.span) {
+            Value::new(Type::Never)
+        } else {
+            value
+        }
     }
 
     fn visit_call(&mut self, call: &ast::Call) -> Value {
+        if let Exp::FieldAccess(field_access) = self.ctx.ast_ctx().exp(call.func)
+            && let Some(value) = self.check_method_call(field_access, call)
+        {
+            return value;
+        }
         let func = self.visit_right_value(call.func);
-        if let Type::Function(ret_ty, param_types) = func.type_() {
+        if let Type::Generic(func) = func.type_() {
+            self.check_generic_call(*func, call)
+        } else if let Type::Function(ret_ty, param_types) = func.type_() {
             for (arg, param_ty) in zip(call.args.iter(), param_types.iter()) {
-                let param_ty = param_ty.clone();
-                let arg_ty = self.visit_right_value(*arg).into_type();
-                if arg_ty != param_ty {
-                    self.error_at(Error::TypeMismatch(param_ty, arg_ty), arg.span());
-                }
+                self.check_coercion(*arg, param_ty);
             }
 
             Value::new(ret_ty.deref().clone())
@@ -112,7 +140,7 @@ impl ExpVisitor<Value> for AnalyzerContext {
         let value = self.visit_right_value(deref.exp);
         let type_ = value.type_();
         if let Type::Ptr(target) = type_ {
-            Value::new(target.deref().clone())
+            Value::new(target.deref().clone()).with_place(Some(Place::Deref))
         } else {
             self.error_at(Error::WrongDeref(type_.clone()), deref.exp.span());
             Value::new(Type::Unit)
@@ -127,16 +155,27 @@ impl ExpVisitor<Value> for AnalyzerContext {
         let array = self.visit_left_value(index.exp);
         let index_value = self.visit_right_value(index.index);
 
-        if !matches!(index_value.type_(), Type::Signed(_) | Type::Unsigned(_)) {
+        if !matches!(index_value.type_(), number_pattern!()) {
             self.error_at(
-                Error::TypeMismatch(Type::Unsigned(64), index_value.into_type()),
+                Error::TypeMismatch(Type::Usize, index_value.into_type()),
                 index.index.span(),
             );
         }
 
+        // Elements of an array are stored where the array is, those of a
+        // slice or behind a pointer are reached through it.
         match array.type_() {
-            Type::Array(target, _) => Value::new(target.deref().clone()),
-            Type::Ptr(target) => Value::new(target.deref().clone()),
+            Type::Array(target, _) => Value::new(target.deref().clone())
+                .with_place(array.place().cloned().map(Place::project)),
+            Type::Slice(target) => {
+                Value::new(target.deref().clone()).with_place(Some(Place::Deref))
+            }
+            Type::Ptr(target) => match target.as_ref() {
+                Type::Array(element, _) => {
+                    Value::new(element.deref().clone()).with_place(Some(Place::Deref))
+                }
+                target => Value::new(target.clone()).with_place(Some(Place::Deref)),
+            },
             _ => {
                 self.error_at(Error::WrongDeref(array.into_type()), index.exp.span());
                 Value::new(Type::Unit)
@@ -144,17 +183,50 @@ impl ExpVisitor<Value> for AnalyzerContext {
         }
     }
 
-    fn visit_number(&mut self, number: &ast::Number) -> Value {
-        if let Some(ty) = &number.ty {
-            match ty {
-                TypeEnum::Signed(width) => Value::new(Type::Signed(*width)),
-                TypeEnum::Unsigned(width) => Value::new(Type::Unsigned(*width)),
-                TypeEnum::Isize => Value::new(Type::Isize),
-                TypeEnum::Usize => Value::new(Type::Usize),
-                _ => unreachable!(),
+    fn visit_slice(&mut self, slice: &ast::Slice) -> Value {
+        let value = self.visit_left_value(slice.exp);
+        if value.type_().is_array() {
+            self.note_mutation(&value);
+        }
+        for bound in slice.start.iter().chain(&slice.end) {
+            let bound_ty = self.visit_right_value(*bound).into_type();
+            if !matches!(bound_ty, number_pattern!()) {
+                self.error_at(Error::TypeMismatch(Type::Usize, bound_ty), bound.span());
             }
-        } else {
-            Value::new(Type::Signed(32))
+        }
+
+        match value.type_() {
+            Type::Ptr(target) => match target.as_ref() {
+                Type::Array(element, _) => Value::new(element.slice()),
+                element => {
+                    if slice.end.is_none() {
+                        self.error_at(Error::UnboundedSlice(value.type_().clone()), slice.span);
+                    }
+                    Value::new(element.slice())
+                }
+            },
+            Type::Array(element, _) | Type::Slice(element) => Value::new(element.slice()),
+            _ => {
+                self.error_at(Error::WrongDeref(value.into_type()), slice.exp.span());
+                Value::new(Type::Unit)
+            }
+        }
+    }
+
+    fn visit_number(&mut self, number: &ast::Number) -> Value {
+        Value::new(self.new_literal(number, false, number.span))
+    }
+
+    fn visit_negative_number(&mut self, number: &ast::Number, span: &Span) -> Value {
+        let value = Value::new(self.new_literal(number, true, *span));
+        self.visit_unary(&UnaryOp::Neg, value, span)
+    }
+
+    fn visit_float(&mut self, float: &ast::Float) -> Value {
+        match &float.ty {
+            Some(TypeEnum::Float(width)) => Value::new(Type::Float(*width)),
+            Some(_) => unreachable!(),
+            None => Value::new(Type::Float(64)),
         }
     }
 
@@ -162,10 +234,18 @@ impl ExpVisitor<Value> for AnalyzerContext {
         unreachable!()
     }
 
-    fn visit_str(&mut self, _string: &str) -> Value {
+    fn visit_str(&mut self, _string: &[u8]) -> Value {
         Value::new(Type::Signed(8).pointer())
     }
 
+    fn visit_byte_str(&mut self, _bytes: &[u8]) -> Value {
+        Value::new(Type::Unsigned(8).pointer())
+    }
+
+    fn visit_bool(&mut self, _value: bool) -> Value {
+        Value::new(Type::Bool)
+    }
+
     fn visit_type_cast(&mut self, type_cast: &TypeCast) -> Value {
         let TypeCast { exp, ty, span } = type_cast;
         let value = self.visit_right_value(*exp);
@@ -176,9 +256,26 @@ impl ExpVisitor<Value> for AnalyzerContext {
             return value;
         }
 
+        if let Type::Dyn(interface) = &target {
+            let value_type = value_type.clone();
+            let implemented = match &value_type {
+                Type::Ptr(structure) => self.implements(structure, interface),
+                _ => false,
+            };
+            if !implemented {
+                self.error_at(
+                    Error::DoesNotImplement(value_type, interface.as_ref().clone()),
+                    *span,
+                );
+            }
+            return Value::new(target);
+        }
+
         if !matches!(
             (value_type, &target),
             (number_pattern!(), number_pattern!())
+                | (number_pattern!() | Type::Float(_), Type::Float(_))
+                | (Type::Float(_), number_pattern!())
                 | (Type::Bool, number_pattern!())
                 | (number_pattern!(), Type::Ptr(_))
                 | (Type::Ptr(_), number_pattern!())
@@ -198,6 +295,7 @@ impl ExpVisitor<Value> for AnalyzerContext {
             UnaryOp::Pos => value.check_pos(),
             UnaryOp::Neg => value.check_neg(),
             UnaryOp::Not => value.check_not(),
+            UnaryOp::BitNot => value.check_bitnot(),
             UnaryOp::Ptr => return Value::new(value.into_type().pointer()),
         };
         match result {
@@ -217,83 +315,91 @@ impl ExpVisitor<Value> for AnalyzerContext {
         let name = var.path.path.join("::");
         if let Some(symbol) = self.symbols.lookup(&name) {
             match symbol {
-                Symbol::Var(_, _, value) => value.clone(),
+                Symbol::Var(name, binding, value) => value
+                    .clone()
+                    .with_place(Some(Place::Var(name.clone(), *binding))),
+                Symbol::Type(_, value) => value.clone(),
             }
         } else {
-            static CHECKED: LazyLock<RwLock<HashMap<DefId, Value>>> =
-                LazyLock::new(|| RwLock::new(HashMap::new()));
-
             let Some(def_id) = self.ctx.lookup_def_id(&name) else {
                 self.error_at(Error::Unknown(name), var.span);
                 return Value::default();
             };
-
-            if let Some(value) = CHECKED.read().unwrap().get(&def_id) {
-                value.clone()
-            } else if let Some(ty) = {
-                let ctx = self.ctx.clone();
-                let const_def = ctx.get_def(def_id).unwrap();
-                match &const_def.initial_value {
-                    ConstInitialValue::Exp(exp) => self.try_infer(exp.exp),
-                }
-            } {
-                self.required.push(def_id);
-                Value::new(ty)
-            } else {
-                let result = self.ctx.query(&CHECK_CONST_DEF, def_id).unwrap();
-
-                let AnalyzeResult {
-                    value,
-                    errors,
-                    warnings,
-                    required,
-                } = result;
-
-                self.errors.extend(errors);
-                self.warnings.extend(warnings);
-                self.required.extend(required);
-
-                CHECKED.write().unwrap().insert(def_id, value.clone());
-
-                value
-            }
+            let declared = self.ctx.get_def(def_id).unwrap().span;
+            let const_name = var.path.path.last().cloned().unwrap_or(name);
+            self.visit_global(def_id)
+                .with_place(Some(Place::Const(const_name, declared)))
         }
     }
 
     fn visit_structure(&mut self, structure: &ast::Structure) -> Value {
         let ty = self.visit_right_value(structure.ty).into_type();
-        let Type::Structure(field_types) = &ty else {
+        let Type::Structure(field_types, _) = &ty else {
             self.error_at(Error::ExpectedStructType(ty), structure.ty.span());
             return Value::default();
         };
 
         for (field_name, &field_exp) in structure.fields.iter() {
-            let field_value = self.visit_right_value(field_exp);
-            let field_type = field_value.into_type();
-            let Some(should_be_type) = field_types.get(field_name) else {
+            let Some((_, should_be_type)) = field_types.iter().find(|(name, _)| name == field_name)
+            else {
+                self.visit_right_value(field_exp);
                 self.error_at(Error::InvalidField(field_name.clone()), field_exp.span());
                 continue;
             };
-            if field_type != *should_be_type {
-                self.error_at(
-                    Error::TypeMismatch(field_type, should_be_type.clone()),
-                    field_exp.span(),
-                );
-            }
+            self.check_coercion(field_exp, should_be_type);
         }
 
         Value::new(ty)
     }
 
+    fn visit_tuple(&mut self, tuple: &ast::Tuple) -> Value {
+        let elements = tuple
+            .elements
+            .iter()
+            .map(|element| self.visit_right_value(*element).into_type())
+            .collect();
+        Value::new(Type::Tuple(elements))
+    }
+
     fn visit_field_access(&mut self, field_access: &ast::FieldAccess) -> Value {
-        let ty = self.visit_right_value(field_access.lhs).into_type();
-        let Type::Structure(field_types) = &ty else {
+        // Fields are stored where the structure or tuple is.
+        let lhs = self.visit_left_value(field_access.lhs);
+        let place = lhs.place().cloned().map(Place::project);
+        let ty = self.resolve(lhs.type_());
+        if let Type::Tuple(elements) = &ty {
+            return match field_access
+                .field
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| elements.get(index))
+            {
+                Some(element) => Value::new(element.clone()).with_place(place),
+                None => {
+                    self.error_at(
+                        Error::InvalidField(field_access.field.clone()),
+                        field_access.span,
+                    );
+                    Value::default()
+                }
+            };
+        }
+        if let Type::Slice(_) = &ty {
+            if field_access.field != "len" {
+                self.error_at(
+                    Error::InvalidField(field_access.field.clone()),
+                    field_access.span,
+                );
+            }
+            return Value::new(Type::Usize);
+        }
+        let Type::Structure(field_types, _) = &ty else {
             self.error_at(Error::ExpectedStructType(ty), field_access.lhs.span());
             return Value::default();
         };
         field_types
-            .get(&field_access.field)
-            .map(|t| Value::new(t.clone()))
+            .iter()
+            .find(|(name, _)| *name == field_access.field)
+            .map(|(_, t)| Value::new(t.clone()).with_place(place))
             .unwrap_or_else(|| {
                 self.error_at(
                     Error::InvalidField(field_access.field.clone()),
@@ -306,4 +412,85 @@ impl ExpVisitor<Value> for AnalyzerContext {
     fn visit_type(&mut self, type_: &ast::Type) -> Value {
         Value::new(self.convert_type(type_))
     }
+
+    fn visit_variant(&mut self, variant: &ast::EnumVariant) -> Value {
+        let ty = self.visit_right_value(variant.ty).into_type();
+        let Type::Enum(variants) = &ty else {
+            self.error_at(
+                Error::InvalidVariant(variant.name.clone(), ty),
+                variant.span,
+            );
+            return Value::default();
+        };
+        match variants.iter().find(|(name, _)| *name == variant.name) {
+            Some((_, Some(payload))) => {
+                let payload = payload.clone();
+                Value::new(Type::Function(Box::new(ty), vec![payload]))
+            }
+            Some((_, None)) => Value::new(ty),
+            None => {
+                self.error_at(
+                    Error::InvalidVariant(variant.name.clone(), ty),
+                    variant.span,
+                );
+                Value::default()
+            }
+        }
+    }
+
+    fn visit_builtin(&mut self, builtin: &ast::BuiltinCall) -> Value {
+        let ty_exp = builtin.builtin.ty();
+        let ty = self.visit_right_value(ty_exp).into_type();
+        if !self.is_type_exp(ty_exp) {
+            self.error_at(Error::ExpectedType(ty), ty_exp.span());
+            return Value::new(Type::Usize);
+        }
+        if let Builtin::OffsetOf(_, field) = &builtin.builtin {
+            match &ty {
+                Type::Structure(fields, _) if fields.iter().any(|(name, _)| name == field) => {}
+                Type::Structure(..) => {
+                    self.error_at(Error::InvalidField(field.clone()), builtin.span)
+                }
+                _ => self.error_at(Error::ExpectedStructType(ty), ty_exp.span()),
+            }
+        }
+        Value::new(Type::Usize)
+    }
+}
+
+impl AnalyzerContext {
+    pub(crate) fn visit_global(&mut self, def_id: DefId) -> Value {
+        static CHECKED: LazyLock<RwLock<HashMap<DefId, Value>>> =
+            LazyLock::new(|| RwLock::new(HashMap::new()));
+
+        if let Some(value) = CHECKED.read().unwrap().get(&def_id) {
+            value.clone()
+        } else if let Some(ty) = {
+            let ctx = self.ctx.clone();
+            let const_def = ctx.get_def(def_id).unwrap();
+            match &const_def.initial_value {
+                ConstInitialValue::Exp(exp) => self.try_infer(exp.exp),
+            }
+        } {
+            self.required.push(def_id);
+            Value::new(ty)
+        } else {
+            let result = self.ctx.query_cached(&CHECK_CONST_DEF, def_id).unwrap();
+
+            let AnalyzeResult {
+                value,
+                errors,
+                warnings,
+                required,
+            } = result;
+
+            self.errors.extend(errors);
+            self.warnings.extend(warnings);
+            self.required.extend(required);
+
+            CHECKED.write().unwrap().insert(def_id, value.clone());
+
+            value
+        }
+    }
 }
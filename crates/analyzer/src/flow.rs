@@ -0,0 +1,598 @@
+//! The control-flow graph of a function body: whether it reaches its end,
+//! which code is unreachable, and which deferred `let`s are read unassigned.
+
+use std::{collections::HashSet, sync::Arc};
+
+use ast::{
+    AstContext, Block, BlockItem, Exp, ExpId, FunctionDef, Span, Statement,
+    visitor::{BlockVisitor, ExpVisitor, StatementVisitor},
+};
+
+use crate::{Error, Warning};
+
+#[derive(Default)]
+pub(crate) struct Flow {
+    diverging: HashSet<Span>,
+    pub(crate) falls_through: bool,
+    pub(crate) errors: Vec<(Error, Span)>,
+    pub(crate) warnings: Vec<(Warning, Span)>,
+}
+
+impl Flow {
+    pub(crate) fn analyze(ast_ctx: Arc<AstContext>, func: &FunctionDef) -> Flow {
+        let mut builder = FlowBuilder {
+            ast_ctx,
+            nodes: vec![Node::default()],
+            current: 0,
+            loops: Vec::new(),
+            scopes: vec![Vec::new()],
+            deferred: Vec::new(),
+            blocks: Vec::new(),
+            loop_exits: Vec::new(),
+        };
+        <FlowBuilder as BlockVisitor<()>>::visit_block(&mut builder, &func.block);
+        builder.finish()
+    }
+
+    /// Whether the block or loop at `span` never ends, so it has no value.
+    pub(crate) fn diverges(&self, span: Span) -> bool {
+        self.diverging.contains(&span)
+    }
+}
+
+type NodeId = usize;
+
+#[derive(Default)]
+struct Node {
+    accesses: Vec<Access>,
+    successors: Vec<NodeId>,
+}
+
+enum Access {
+    Declare(usize),
+    Read(usize, Span),
+    Write(usize, Span),
+}
+
+struct Deferred {
+    name: String,
+    mutable: bool,
+    span: Span,
+}
+
+struct LoopTargets {
+    label: Option<String>,
+    head: NodeId,
+    exit: NodeId,
+}
+
+struct BlockNodes {
+    span: Span,
+    start: NodeId,
+    items: Vec<(Span, NodeId)>,
+    end: NodeId,
+}
+
+struct FlowBuilder {
+    ast_ctx: Arc<AstContext>,
+    nodes: Vec<Node>,
+    current: NodeId,
+    loops: Vec<LoopTargets>,
+    scopes: Vec<Vec<(String, Option<usize>)>>,
+    deferred: Vec<Deferred>,
+    blocks: Vec<BlockNodes>,
+    loop_exits: Vec<(Span, NodeId)>,
+}
+
+impl FlowBuilder {
+    fn new_node(&mut self) -> NodeId {
+        self.nodes.push(Node::default());
+        self.nodes.len() - 1
+    }
+
+    fn edge(&mut self, from: NodeId, to: NodeId) {
+        self.nodes[from].successors.push(to);
+    }
+
+    fn goto(&mut self, to: NodeId) {
+        self.edge(self.current, to);
+        self.current = to;
+    }
+
+    fn branch(&mut self) {
+        let next = self.new_node();
+        self.goto(next);
+    }
+
+    fn find_loop(&self, label: &Option<String>) -> Option<&LoopTargets> {
+        self.loops
+            .iter()
+            .rev()
+            .find(|targets| label.is_none() || targets.label == *label)
+    }
+
+    fn jump(&mut self, to: NodeId) {
+        self.edge(self.current, to);
+        self.current = self.new_node();
+    }
+
+    fn access(&mut self, access: Access) {
+        self.nodes[self.current].accesses.push(access);
+    }
+
+    fn declare(&mut self, name: &str, deferred: Option<usize>) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .push((name.to_string(), deferred));
+    }
+
+    fn lookup(&self, var: &ast::Var) -> Option<usize> {
+        let name = var.path.path.join("::");
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(declared, _)| *declared == name)
+            .and_then(|(_, deferred)| *deferred)
+    }
+
+    fn deferred_var(&self, exp: ExpId) -> Option<usize> {
+        match self.ast_ctx.exp(exp) {
+            Exp::Exp(exp, _) => self.deferred_var(*exp),
+            Exp::Var(var) => self.lookup(var),
+            _ => None,
+        }
+    }
+
+    fn reachable(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.nodes.len()];
+        let mut pending = vec![0];
+        while let Some(node) = pending.pop() {
+            if !std::mem::replace(&mut reachable[node], true) {
+                pending.extend(&self.nodes[node].successors);
+            }
+        }
+        reachable
+    }
+
+    fn finish(self) -> Flow {
+        let reachable = self.reachable();
+        let body = self.blocks.last().unwrap();
+        let mut flow = Flow {
+            falls_through: reachable[body.end],
+            ..Flow::default()
+        };
+
+        for block in &self.blocks {
+            if !reachable[block.end] {
+                flow.diverging.insert(block.span);
+            }
+            // Only the first item of a block control can't get to is
+            // reported, and only if the block itself can be reached.
+            if reachable[block.start]
+                && let Some((span, _)) = block.items.iter().find(|(_, node)| !reachable[*node])
+            {
+                flow.warnings.push((Warning::UnreachableCode, *span));
+            }
+        }
+        for (span, exit) in &self.loop_exits {
+            if !reachable[*exit] {
+                flow.diverging.insert(*span);
+            }
+        }
+
+        self.check_assignments(&reachable, &mut flow);
+        flow
+    }
+
+    fn check_assignments(&self, reachable: &[bool], flow: &mut Flow) {
+        if self.deferred.is_empty() {
+            return;
+        }
+        let nodes = || (0..self.nodes.len()).filter(|node| reachable[*node]);
+        let mut predecessors = vec![Vec::new(); self.nodes.len()];
+        for node in nodes() {
+            for &successor in &self.nodes[node].successors {
+                predecessors[successor].push(node);
+            }
+        }
+
+        // Everything counts as assigned on every path out of a node until
+        // shown otherwise, so the analysis converges in spite of loops.
+        let mut assigned_out = vec![
+            Assigned {
+                definitely: (0..self.deferred.len()).collect(),
+                maybe: HashSet::new(),
+            };
+            self.nodes.len()
+        ];
+        let assigned_in = |assigned_out: &[Assigned], node: NodeId| {
+            predecessors[node]
+                .iter()
+                .map(|&pred| assigned_out[pred].clone())
+                .reduce(Assigned::meet)
+                .unwrap_or_default()
+        };
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for node in nodes() {
+                let mut assigned = assigned_in(&assigned_out, node);
+                for access in &self.nodes[node].accesses {
+                    assigned.apply(access);
+                }
+                if assigned != assigned_out[node] {
+                    assigned_out[node] = assigned;
+                    changed = true;
+                }
+            }
+        }
+
+        for node in nodes() {
+            let mut assigned = assigned_in(&assigned_out, node);
+            for access in &self.nodes[node].accesses {
+                match *access {
+                    Access::Read(var, span) if !assigned.definitely.contains(&var) => {
+                        let name = self.deferred[var].name.clone();
+                        flow.errors.push((Error::Uninitialized(name), span));
+                    }
+                    Access::Write(var, span)
+                        if !self.deferred[var].mutable && assigned.maybe.contains(&var) =>
+                    {
+                        let Deferred {
+                            name,
+                            span: declared,
+                            ..
+                        } = &self.deferred[var];
+                        flow.errors
+                            .push((Error::AssignTwice(name.clone(), *declared), span));
+                    }
+                    _ => {}
+                }
+                assigned.apply(access);
+            }
+        }
+    }
+}
+
+#[derive(Default, Clone, PartialEq)]
+struct Assigned {
+    definitely: HashSet<usize>,
+    maybe: HashSet<usize>,
+}
+
+impl Assigned {
+    fn meet(mut self, other: Assigned) -> Assigned {
+        self.definitely.retain(|var| other.definitely.contains(var));
+        self.maybe.extend(other.maybe);
+        self
+    }
+
+    fn apply(&mut self, access: &Access) {
+        match *access {
+            Access::Declare(var) => {
+                self.definitely.remove(&var);
+                self.maybe.remove(&var);
+            }
+            Access::Write(var, _) => {
+                self.definitely.insert(var);
+                self.maybe.insert(var);
+            }
+            Access::Read(..) => {}
+        }
+    }
+}
+
+fn item_span(item: &BlockItem) -> Span {
+    match item {
+        BlockItem::Statement(Statement::Exp(exp)) => exp.span(),
+        BlockItem::Statement(Statement::InlineAsm(inline_asm)) => inline_asm.span,
+        BlockItem::VarDef(var_def) => var_def.span,
+    }
+}
+
+impl BlockVisitor<()> for FlowBuilder {
+    fn on_enter_block(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    fn on_leave_block(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn visit_block(&mut self, block: &Block) -> Option<()> {
+        self.on_enter_block();
+        let start = self.current;
+        let mut items = Vec::new();
+        for item in &block.items {
+            items.push((item_span(item), self.current));
+            match item {
+                BlockItem::Statement(stmt) => {
+                    self.visit_statement(stmt);
+                }
+                BlockItem::VarDef(var_def) => self.visit_var_def(var_def),
+            }
+        }
+        if let Some(value) = block.return_value {
+            items.push((value.span(), self.current));
+            self.visit_right_value(value);
+        }
+        self.blocks.push(BlockNodes {
+            span: block.span,
+            start,
+            items,
+            end: self.current,
+        });
+        self.on_leave_block();
+        None
+    }
+
+    fn visit_var_def(&mut self, var_def: &ast::VarDef) {
+        if let Some(value) = var_def.initial_value {
+            self.visit_right_value(value);
+        }
+        for name in var_def.binding.names() {
+            if var_def.initial_value.is_some() {
+                self.declare(name, None);
+                continue;
+            }
+            let var = self.deferred.len();
+            self.deferred.push(Deferred {
+                name: name.clone(),
+                mutable: var_def.mutable,
+                span: var_def.span,
+            });
+            self.access(Access::Declare(var));
+            self.declare(name, Some(var));
+        }
+    }
+
+    fn visit_inline_asm(&mut self, _inline_asm: &ast::InlineAsm) {}
+}
+
+impl StatementVisitor<()> for FlowBuilder {
+    fn visit_assign(&mut self, assign: &ast::Assign) {
+        match self.deferred_var(assign.lhs) {
+            Some(var) => {
+                if assign.op.is_some() {
+                    self.access(Access::Read(var, assign.lhs.span()));
+                }
+                self.visit_right_value(assign.rhs);
+                self.access(Access::Write(var, assign.lhs.span()));
+            }
+            None => {
+                self.visit_left_value(assign.lhs);
+                self.visit_right_value(assign.rhs);
+            }
+        }
+    }
+
+    fn visit_if_exp(&mut self, if_exp: &ast::IfExp) {
+        self.visit_right_value(if_exp.condition);
+        let condition = self.current;
+        let end = self.new_node();
+
+        self.current = self.new_node();
+        self.edge(condition, self.current);
+        <Self as BlockVisitor<()>>::visit_block(self, &if_exp.then_branch);
+        self.edge(self.current, end);
+
+        self.current = self.new_node();
+        self.edge(condition, self.current);
+        if let Some(else_branch) = &if_exp.else_branch {
+            <Self as BlockVisitor<()>>::visit_block(self, else_branch);
+        } else if let Some(else_if) = &if_exp.else_if {
+            self.visit_if_exp(else_if);
+        }
+        self.goto(end);
+    }
+
+    // The analyzer makes sure the arms cover every value, so control always
+    // goes into one of them.
+    fn visit_match(&mut self, match_: &ast::Match) {
+        self.visit_right_value(match_.exp);
+        let scrutinee = self.current;
+        let end = self.new_node();
+        for arm in &match_.arms {
+            self.current = self.new_node();
+            self.edge(scrutinee, self.current);
+            self.scopes.push(Vec::new());
+            if let ast::Pattern::Variant(_, Some(binding), _) = &arm.pattern {
+                self.declare(binding, None);
+            }
+            self.visit_right_value(arm.body);
+            self.scopes.pop();
+            self.edge(self.current, end);
+        }
+        self.current = end;
+    }
+
+    fn visit_return(&mut self, return_stmt: &ast::Return) {
+        if let Some(value) = return_stmt.value {
+            self.visit_right_value(value);
+        }
+        self.current = self.new_node();
+    }
+
+    fn visit_for(&mut self, for_: &ast::For) {
+        self.visit_right_value(for_.start);
+        self.visit_right_value(for_.end);
+        if let Some(step) = for_.step {
+            self.visit_right_value(step);
+        }
+        let head = self.new_node();
+        let exit = self.new_node();
+        self.goto(head);
+        self.edge(head, exit);
+        self.branch();
+
+        self.scopes.push(Vec::new());
+        self.declare(&for_.var, None);
+        self.loops.push(LoopTargets {
+            label: for_.label.clone(),
+            head,
+            exit,
+        });
+        <Self as BlockVisitor<()>>::visit_block(self, &for_.body);
+        self.loops.pop();
+        self.scopes.pop();
+
+        self.edge(self.current, head);
+        self.current = exit;
+    }
+
+    // A loop only ends through `break`, so without one it diverges.
+    fn visit_loop(&mut self, loop_: &ast::Loop) {
+        let head = self.new_node();
+        let exit = self.new_node();
+        self.goto(head);
+
+        self.loops.push(LoopTargets {
+            label: loop_.label.clone(),
+            head,
+            exit,
+        });
+        <Self as BlockVisitor<()>>::visit_block(self, &loop_.body);
+        self.loops.pop();
+
+        self.edge(self.current, head);
+        self.current = exit;
+        self.loop_exits.push((loop_.span, exit));
+    }
+
+    fn visit_while(&mut self, while_: &ast::While) {
+        let head = self.new_node();
+        let exit = self.new_node();
+        self.goto(head);
+        self.visit_right_value(while_.condition);
+        self.edge(self.current, exit);
+        self.branch();
+
+        self.loops.push(LoopTargets {
+            label: while_.label.clone(),
+            head,
+            exit,
+        });
+        <Self as BlockVisitor<()>>::visit_block(self, &while_.body);
+        self.loops.pop();
+
+        self.edge(self.current, head);
+        self.current = exit;
+    }
+
+    fn visit_break(&mut self, break_: &ast::Break) {
+        if let Some(value) = break_.value {
+            self.visit_right_value(value);
+        }
+        match self.find_loop(&break_.label).map(|targets| targets.exit) {
+            Some(exit) => self.jump(exit),
+            None => self.current = self.new_node(),
+        }
+    }
+
+    fn visit_continue(&mut self, continue_: &ast::Continue) {
+        match self.find_loop(&continue_.label).map(|targets| targets.head) {
+            Some(head) => self.jump(head),
+            None => self.current = self.new_node(),
+        }
+    }
+}
+
+impl ExpVisitor<()> for FlowBuilder {
+    fn ast_ctx(&self) -> Arc<AstContext> {
+        self.ast_ctx.clone()
+    }
+    fn get_right_value(&self, _left_value: ()) {}
+    fn pass_left_value_as_right_value(&self, _left_value: ()) {}
+
+    fn visit_logical(&mut self, _op: &ast::BinaryOp, lhs: ExpId, rhs: ExpId, _span: &Span) {
+        self.visit_right_value(lhs);
+        let end = self.new_node();
+        self.edge(self.current, end);
+        self.branch();
+        self.visit_right_value(rhs);
+        self.goto(end);
+    }
+
+    fn visit_array(&mut self, array: &ast::Array) {
+        match array {
+            ast::Array::List(elements, _) => {
+                for element in elements {
+                    self.visit_right_value(*element);
+                }
+            }
+            ast::Array::Template(value, _, _) => self.visit_right_value(*value),
+        }
+    }
+
+    fn visit_binary(&mut self, _op: &ast::BinaryOp, _lhs: (), _rhs: (), _span: &Span) {}
+    fn visit_unary(&mut self, _op: &ast::UnaryOp, _value: (), _span: &Span) {}
+
+    fn visit_call(&mut self, call: &ast::Call) {
+        self.visit_right_value(call.func);
+        for &arg in &call.args {
+            self.visit_right_value(arg);
+        }
+    }
+
+    fn visit_deref(&mut self, deref: &ast::Deref) {
+        self.visit_right_value(deref.exp);
+    }
+
+    fn visit_index(&mut self, index: &ast::Index) {
+        self.visit_left_value(index.exp);
+        self.visit_right_value(index.index);
+    }
+
+    fn visit_slice(&mut self, slice: &ast::Slice) {
+        self.visit_left_value(slice.exp);
+        for bound in slice.start.iter().chain(&slice.end) {
+            self.visit_right_value(*bound);
+        }
+    }
+
+    fn visit_var(&mut self, var: &ast::Var) {
+        if let Some(deferred) = self.lookup(var) {
+            self.access(Access::Read(deferred, var.span));
+        }
+    }
+
+    fn visit_number(&mut self, _number: &ast::Number) {}
+    fn visit_float(&mut self, _float: &ast::Float) {}
+    fn visit_str(&mut self, _string: &[u8]) {}
+    fn visit_bool(&mut self, _value: bool) {}
+
+    fn visit_block(&mut self, block: &Block) {
+        <Self as BlockVisitor<()>>::visit_block(self, block);
+    }
+
+    fn visit_proto(&mut self, _proto_def: &ast::ProtoDef) {}
+    fn visit_function(&mut self, _func: &FunctionDef) {}
+
+    fn visit_unit(&mut self) {}
+
+    fn visit_type_cast(&mut self, type_cast: &ast::TypeCast) {
+        self.visit_right_value(type_cast.exp);
+    }
+
+    fn visit_structure(&mut self, structure: &ast::Structure) {
+        for &value in structure.fields.values() {
+            self.visit_right_value(value);
+        }
+    }
+
+    fn visit_tuple(&mut self, tuple: &ast::Tuple) {
+        for &element in &tuple.elements {
+            self.visit_right_value(element);
+        }
+    }
+
+    fn visit_field_access(&mut self, field_access: &ast::FieldAccess) {
+        self.visit_left_value(field_access.lhs);
+    }
+
+    fn visit_type(&mut self, _type_: &ast::Type) {}
+    fn visit_variant(&mut self, _variant: &ast::EnumVariant) {}
+    fn visit_builtin(&mut self, _builtin: &ast::BuiltinCall) {}
+}
@@ -0,0 +1,204 @@
+use std::{
+    iter::zip,
+    sync::{LazyLock, RwLock},
+};
+
+use ast::{Array, Call, ConstInitialValue, Exp, ExpId, TypeEnum, UnaryOp, visitor::ExpVisitor};
+use const_eval::Bindings;
+
+use crate::{AnalyzerContext, Binding, Error, Symbol, Type, Value};
+
+/// A generic function, the type arguments and the printed values of the other
+/// `comptime` arguments of one instance, and its signature.
+type Instance = (ExpId, Vec<Type>, Vec<String>, Type);
+
+static INSTANCES: LazyLock<RwLock<Vec<Instance>>> = LazyLock::new(|| RwLock::new(Vec::new()));
+
+impl AnalyzerContext {
+    pub(crate) fn check_generic_call(&mut self, func_exp: ExpId, call: &Call) -> Value {
+        let ast_ctx = self.ctx.ast_ctx();
+        let Exp::Function(func) = ast_ctx.exp(func_exp) else {
+            unreachable!()
+        };
+
+        if func.params.len() != call.args.len() {
+            self.error_at(
+                Error::ArgumentCount(func.params.len(), call.args.len()),
+                call.span,
+            );
+            return Value::default();
+        }
+
+        let mut type_args = Vec::new();
+        let mut const_args = Vec::new();
+        let mut bindings = Bindings::new();
+        for (param, &arg) in zip(&func.params, &call.args) {
+            if !param.comptime {
+                continue;
+            }
+            let kind = self.visit_right_value(param.param_type).into_type();
+            let is_type = kind == Type::Type || matches!(kind, Type::Interface(..));
+            if is_type {
+                let value = self.visit_right_value(arg);
+                if !self.is_type_exp(arg) {
+                    self.error_at(Error::ExpectedType(value.into_type()), arg.span());
+                    return Value::default();
+                }
+                // `comptime W: Writer` takes any structure implementing
+                // `Writer`; calls on it are resolved against the concrete type.
+                if matches!(kind, Type::Interface(..)) && !self.implements(value.type_(), &kind) {
+                    self.error_at(Error::DoesNotImplement(value.into_type(), kind), arg.span());
+                    return Value::default();
+                }
+                type_args.push(value.into_type());
+            } else {
+                self.check_coercion(arg, &kind);
+                if !self.is_const_exp(arg) {
+                    self.error_at(Error::ExpectedConst, arg.span());
+                    return Value::default();
+                }
+            }
+            // Literals in the instance are inferred per instance, which
+            // codegen tells apart by the values of the `comptime` arguments.
+            match const_eval::try_eval_exp(self.ctx.clone(), arg, &self.bindings) {
+                Ok(value) => {
+                    if !is_type {
+                        const_args.push(value.to_string());
+                    }
+                    bindings.insert(param.name.clone(), value);
+                }
+                Err(_) if !is_type => {
+                    self.error_at(Error::ExpectedConst, arg.span());
+                    return Value::default();
+                }
+                Err(_) => {}
+            }
+        }
+
+        let cached = INSTANCES
+            .read()
+            .unwrap()
+            .iter()
+            .find(|(exp, types, values, _)| {
+                *exp == func_exp && *types == type_args && *values == const_args
+            })
+            .map(|(_, _, _, ty)| ty.clone());
+
+        let fn_ty = match cached {
+            Some(ty) => ty,
+            None => {
+                let mut instance = AnalyzerContext::new(self.ctx.clone(), None);
+                instance.bindings = bindings;
+                instance.symbols.push_scope();
+                let mut types = type_args.iter();
+                for param in func.params.iter().filter(|p| p.comptime) {
+                    let kind = instance.visit_right_value(param.param_type).into_type();
+                    let symbol = if kind == Type::Type || matches!(kind, Type::Interface(..)) {
+                        let ty = types.next().unwrap().clone();
+                        Symbol::Type(param.name.clone(), Value::new(ty))
+                    } else {
+                        Symbol::Var(
+                            param.name.clone(),
+                            Binding::Param(param.span),
+                            Value::new(kind),
+                        )
+                    };
+                    instance.symbols.push(symbol);
+                }
+
+                let ty = instance.function_type(func);
+                let ty = if let Type::Function(ret_ty, param_types) = &ty
+                    && **ret_ty == Type::Type
+                {
+                    // Calling a type-returning function gives the type its
+                    // body produces.
+                    let produced = instance.check_function_body(func, &ty).unwrap_or_default();
+                    let ty = Type::Function(Box::new(produced), param_types.clone());
+                    INSTANCES
+                        .write()
+                        .unwrap()
+                        .push((func_exp, type_args, const_args, ty.clone()));
+                    ty
+                } else {
+                    // Registered before the body is checked so recursive
+                    // calls with the same type arguments find it.
+                    INSTANCES
+                        .write()
+                        .unwrap()
+                        .push((func_exp, type_args, const_args, ty.clone()));
+                    let _ = instance.check_function_body(func, &ty);
+                    ty
+                };
+
+                instance.finish();
+                self.errors.extend(instance.errors);
+                self.warnings.extend(instance.warnings);
+                self.required.extend(instance.required);
+                ty
+            }
+        };
+
+        let Type::Function(ret_ty, param_types) = fn_ty else {
+            unreachable!()
+        };
+        let runtime_args = zip(&func.params, &call.args)
+            .filter(|(param, _)| !param.comptime)
+            .map(|(_, arg)| *arg);
+        for (arg, param_ty) in zip(runtime_args, &param_types) {
+            self.check_coercion(arg, param_ty);
+        }
+
+        Value::new(*ret_ty)
+    }
+
+    pub(crate) fn is_type_exp(&self, exp: ExpId) -> bool {
+        let ast_ctx = self.ctx.ast_ctx();
+        match ast_ctx.exp(exp) {
+            Exp::Type(_) => true,
+            Exp::Unary(UnaryOp::Ptr, inner, _) => self.is_type_exp(*inner),
+            Exp::Array(Array::Template(element, _, _)) => self.is_type_exp(*element),
+            Exp::Tuple(tuple) => tuple
+                .elements
+                .iter()
+                .all(|element| self.is_type_exp(*element)),
+            Exp::Var(var) => {
+                let name = var.path.path.join("::");
+                if let Some(symbol) = self.symbols.lookup(&name) {
+                    return matches!(symbol, Symbol::Type(_, _));
+                }
+                self.ctx
+                    .lookup_def_id(&name)
+                    .and_then(|def_id| self.ctx.get_def(def_id))
+                    .is_some_and(|const_def| match &const_def.initial_value {
+                        ConstInitialValue::Exp(exp) => self.is_type_exp(exp.exp),
+                    })
+            }
+            Exp::Call(call) => {
+                let Exp::Var(var) = ast_ctx.exp(call.func) else {
+                    return false;
+                };
+                let Some(const_def) = self
+                    .ctx
+                    .lookup_def_id(var.path.path.join("::"))
+                    .and_then(|def_id| self.ctx.get_def(def_id))
+                else {
+                    return false;
+                };
+                let ConstInitialValue::Exp(exp) = &const_def.initial_value;
+                match ast_ctx.exp(exp.exp) {
+                    Exp::Function(func) => func.return_type.is_some_and(|ty| {
+                        matches!(
+                            ast_ctx.exp(ty),
+                            Exp::Type(ast::Type {
+                                kind: TypeEnum::Type,
+                                ..
+                            })
+                        )
+                    }),
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+}
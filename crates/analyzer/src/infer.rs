@@ -0,0 +1,147 @@
+use ast::{Number, Span, TypeEnum};
+
+use crate::{AnalyzerContext, Error, Type, number_pattern};
+
+pub(crate) struct Literal {
+    span: Span,
+    /// Where errors about the literal point, which includes a `-`.
+    at: Span,
+    negative: bool,
+    magnitude: u128,
+    ty: Type,
+}
+
+impl AnalyzerContext {
+    /// Adds `number`, negated at `at` if `negative`, to the literals whose
+    /// type is inferred.
+    pub(crate) fn new_literal(&mut self, number: &Number, negative: bool, at: Span) -> Type {
+        let ty = match &number.ty {
+            Some(TypeEnum::Signed(width)) => Type::Signed(*width),
+            Some(TypeEnum::Unsigned(width)) => Type::Unsigned(*width),
+            Some(TypeEnum::Isize) => Type::Isize,
+            Some(TypeEnum::Usize) => Type::Usize,
+            Some(_) => unreachable!(),
+            None => Type::IntVar(self.infer.new_var()),
+        };
+        self.literals.push(Literal {
+            span: number.span,
+            at,
+            negative,
+            magnitude: number.num,
+            ty: ty.clone(),
+        });
+        ty
+    }
+
+    /// Unifies `found` with `expected`, binding the type variables of
+    /// literals in either to the integer type on the other side. Types
+    /// without variables are unified if they are equal.
+    pub(crate) fn unify(&mut self, expected: &Type, found: &Type) -> bool {
+        match (self.resolve(expected), self.resolve(found)) {
+            (Type::Never, _) | (_, Type::Never) => true,
+            (Type::IntVar(a), Type::IntVar(b)) => self.infer.union(a, b),
+            (Type::IntVar(var), ty) | (ty, Type::IntVar(var))
+                if matches!(ty, number_pattern!()) =>
+            {
+                self.infer.bind(var, ty);
+                true
+            }
+            (Type::Ptr(expected), Type::Ptr(found))
+            | (Type::Slice(expected), Type::Slice(found)) => self.unify(&expected, &found),
+            (Type::Array(expected, a), Type::Array(found, b)) => {
+                a == b && self.unify(&expected, &found)
+            }
+            (Type::Tuple(expected), Type::Tuple(found)) => {
+                expected.len() == found.len()
+                    && std::iter::zip(&expected, &found).all(|(a, b)| self.unify(a, b))
+            }
+            (expected, found) => expected == found,
+        }
+    }
+
+    pub(crate) fn resolve(&self, ty: &Type) -> Type {
+        match ty {
+            Type::IntVar(var) => match self.infer.probe(*var) {
+                Some(ty) => ty.clone(),
+                None => Type::IntVar(self.infer.find(*var)),
+            },
+            Type::Ptr(target) => self.resolve(target).pointer(),
+            Type::Array(element, len) => self.resolve(element).array(*len),
+            Type::Slice(element) => self.resolve(element).slice(),
+            Type::Tuple(elements) => {
+                Type::Tuple(elements.iter().map(|ty| self.resolve(ty)).collect())
+            }
+            ty => ty.clone(),
+        }
+    }
+
+    /// Records the types of the literals checked so far and checks their
+    /// values fit. Literals whose type is still unknown are given the
+    /// default `i32` if `default` is set, and are left for later otherwise.
+    pub(crate) fn infer_literals(&mut self, default: bool) {
+        let scope = const_eval::literal_scope(&self.bindings);
+        for literal in std::mem::take(&mut self.literals) {
+            let ty = match self.resolve(&literal.ty) {
+                Type::IntVar(var) if default => {
+                    self.infer.bind(var, Type::Signed(32));
+                    Type::Signed(32)
+                }
+                Type::IntVar(_) => {
+                    self.literals.push(literal);
+                    continue;
+                }
+                ty => ty,
+            };
+            if !self.fits(&literal, &ty) {
+                let sign = if literal.negative { "-" } else { "" };
+                self.error_at(
+                    Error::LiteralOutOfRange(format!("{sign}{}", literal.magnitude), ty.clone()),
+                    literal.at,
+                );
+            }
+            if let Type::IntVar(_) = literal.ty {
+                self.ctx
+                    .literal_types()
+                    .record(literal.span, scope.clone(), int_type_enum(&ty));
+            }
+        }
+    }
+
+    fn fits(&self, literal: &Literal, ty: &Type) -> bool {
+        let pointer_width = self.ctx.target().pointer_width();
+        let (width, signed) = match ty {
+            Type::Signed(width) => (*width, true),
+            Type::Unsigned(width) => (*width, false),
+            Type::Isize => (pointer_width, true),
+            Type::Usize => (pointer_width, false),
+            _ => unreachable!(),
+        };
+        let bits = if signed {
+            width.saturating_sub(1)
+        } else {
+            width
+        };
+        let max = if bits >= 128 {
+            u128::MAX
+        } else {
+            (1u128 << bits) - 1
+        };
+        match (literal.negative, signed) {
+            (false, _) => literal.magnitude <= max,
+            // The most negative value is one further from zero than the
+            // largest.
+            (true, true) => literal.magnitude.saturating_sub(1) <= max,
+            (true, false) => literal.magnitude == 0,
+        }
+    }
+}
+
+fn int_type_enum(ty: &Type) -> TypeEnum {
+    match ty {
+        Type::Signed(width) => TypeEnum::Signed(*width),
+        Type::Unsigned(width) => TypeEnum::Unsigned(*width),
+        Type::Isize => TypeEnum::Isize,
+        Type::Usize => TypeEnum::Usize,
+        _ => unreachable!(),
+    }
+}
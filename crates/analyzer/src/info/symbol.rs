@@ -1,7 +1,19 @@
+use ast::Span;
+
 use crate::Value;
 
 pub enum Symbol {
-    Var(String, bool, Value),
+    Var(String, Binding, Value),
+    Type(String, Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    Let(bool, Span),
+    /// A `let` without `mut` or an initial value, assigned once later.
+    Deferred(Span),
+    Param(Span),
+    Pattern(Span),
 }
 
 impl symbol_table::Symbol for Symbol {
@@ -15,7 +27,7 @@ impl symbol_table::Symbol for Symbol {
 impl Symbol {
     pub fn name(&self) -> &String {
         match self {
-            Symbol::Var(name, _, _) => name,
+            Symbol::Var(name, _, _) | Symbol::Type(name, _) => name,
         }
     }
 }
@@ -1,9 +1,12 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{fmt::Display, sync::Arc};
+
+use ast::ExpId;
+use type_infer::TypeVar;
 
 #[macro_export]
 macro_rules! number_pattern {
     () => {
-        Type::Signed(_) | Type::Unsigned(_) | Type::Usize | Type::Isize
+        Type::Signed(_) | Type::Unsigned(_) | Type::Usize | Type::Isize | Type::IntVar(_)
     };
 }
 
@@ -13,6 +16,7 @@ pub enum Type {
     Unsigned(u32),
     Usize,
     Isize,
+    Float(u32),
 
     #[default]
     Unit,
@@ -20,8 +24,24 @@ pub enum Type {
 
     Ptr(Box<Self>),
     Array(Box<Self>, u32),
+    Slice(Box<Self>),
     Function(Box<Self>, Vec<Self>),
-    Structure(HashMap<String, Type>),
+    Structure(Vec<(String, Type)>, Option<String>),
+    Enum(Vec<(String, Option<Type>)>),
+    Tuple(Vec<Type>),
+    Type,
+    /// A function with `comptime` parameters; each call is checked on its own.
+    Generic(ExpId),
+    /// The name of an interface and the signatures of its methods, which take
+    /// `self: *Self` first. Shared so `Type` stays small.
+    Interface(String, Arc<Vec<(String, Type)>>),
+    SelfType,
+    /// The type of an unsuffixed integer literal, until it is inferred.
+    IntVar(TypeVar),
+    /// The type of what control never gets past, like `return` or a `loop`
+    /// without `break`, which fits wherever a value is expected.
+    Never,
+    Dyn(Box<Self>),
 }
 
 impl Type {
@@ -29,6 +49,10 @@ impl Type {
         matches!(self, Self::Signed(_)) || matches!(self, Self::Unsigned(_))
     }
 
+    pub fn is_float(&self) -> bool {
+        matches!(self, Self::Float(_))
+    }
+
     pub fn is_bool(&self) -> bool {
         matches!(self, Self::Bool)
     }
@@ -50,7 +74,11 @@ impl Type {
     }
 
     pub fn is_structure(&self) -> bool {
-        matches!(self, Self::Structure(_))
+        matches!(self, Self::Structure(..))
+    }
+
+    pub fn is_enum(&self) -> bool {
+        matches!(self, Self::Enum(_))
     }
 }
 
@@ -61,11 +89,13 @@ impl Display for Type {
             Self::Unsigned(bits) => write!(f, "u{}", bits),
             Self::Usize => write!(f, "usize"),
             Self::Isize => write!(f, "isize"),
+            Self::Float(bits) => write!(f, "f{}", bits),
             Self::Bool => write!(f, "bool"),
             Self::Unit => write!(f, "()"),
 
             Self::Ptr(ty) => write!(f, "*{}", ty),
             Self::Array(ty, len) => write!(f, "[{}; {}]", ty, len),
+            Self::Slice(ty) => write!(f, "[]{}", ty),
             Self::Function(ret_ty, param_types) => {
                 write!(f, "fn(")?;
                 for ty in param_types {
@@ -74,13 +104,37 @@ impl Display for Type {
                 write!(f, ") -> ")?;
                 write!(f, "{}", ret_ty)
             }
-            Self::Structure(fields) => {
+            Self::Structure(fields, _) => {
                 write!(f, "{{")?;
                 for (name, ty) in fields {
                     write!(f, "{}: {}, ", name, ty)?;
                 }
                 write!(f, "}}")
             }
+            Self::Enum(variants) => {
+                write!(f, "enum {{")?;
+                for (name, payload) in variants {
+                    match payload {
+                        Some(ty) => write!(f, "{}: {}, ", name, ty)?,
+                        None => write!(f, "{}, ", name)?,
+                    }
+                }
+                write!(f, "}}")
+            }
+            Self::Tuple(elements) => {
+                write!(f, "(")?;
+                for ty in elements {
+                    write!(f, "{}, ", ty)?;
+                }
+                write!(f, ")")
+            }
+            Self::Type => write!(f, "type"),
+            Self::Generic(_) => write!(f, "generic fn"),
+            Self::Interface(name, _) => write!(f, "{}", name),
+            Self::SelfType => write!(f, "Self"),
+            Self::IntVar(_) => write!(f, "{{integer}}"),
+            Self::Never => write!(f, "!"),
+            Self::Dyn(interface) => write!(f, "dyn {}", interface),
         }
     }
 }
@@ -93,4 +147,25 @@ impl Type {
     pub fn array(&self, len: u32) -> Self {
         Self::Array(Box::new(self.clone()), len)
     }
+
+    pub fn slice(&self) -> Self {
+        Self::Slice(Box::new(self.clone()))
+    }
+
+    pub fn replace_self(&self, ty: &Self) -> Self {
+        match self {
+            Self::SelfType => ty.clone(),
+            Self::Ptr(target) => target.replace_self(ty).pointer(),
+            Self::Array(element, len) => element.replace_self(ty).array(*len),
+            Self::Slice(element) => element.replace_self(ty).slice(),
+            Self::Function(ret_ty, param_types) => Self::Function(
+                Box::new(ret_ty.replace_self(ty)),
+                param_types
+                    .iter()
+                    .map(|param| param.replace_self(ty))
+                    .collect(),
+            ),
+            _ => self.clone(),
+        }
+    }
 }
@@ -1,19 +1,51 @@
-use crate::{Error, Type};
+use ast::Span;
+
+use crate::{Binding, Error, Type};
 
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct Value {
     type_: Type,
+    place: Option<Place>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Place {
+    Var(String, Binding),
+    Deref,
+    Const(String, Span),
+}
+
+impl Place {
+    /// The place of an element or field of what is stored here. Only whole
+    /// variables declared without a value can be assigned.
+    pub fn project(self) -> Place {
+        match self {
+            Place::Var(name, Binding::Deferred(span)) => {
+                Place::Var(name, Binding::Let(false, span))
+            }
+            place => place,
+        }
+    }
 }
 
 impl Value {
     pub fn new(type_: Type) -> Self {
-        Value { type_ }
+        Value { type_, place: None }
+    }
+
+    pub fn with_place(mut self, place: Option<Place>) -> Self {
+        self.place = place;
+        self
     }
 
     pub fn type_(&self) -> &Type {
         &self.type_
     }
 
+    pub fn place(&self) -> Option<&Place> {
+        self.place.as_ref()
+    }
+
     pub fn into_type(self) -> Type {
         self.type_
     }
@@ -57,6 +89,21 @@ impl Value {
             [check_mul, "*"],
             [check_div, "/"],
             [check_mod, "%"],
+        }
+        {
+            (Type::Signed(a), Type::Signed(b)) => Type::Signed(std::cmp::max(a, b)),
+            (Type::Unsigned(a), Type::Unsigned(b)) => Type::Unsigned(std::cmp::max(a, b)),
+            (Type::Isize, Type::Usize) => Type::Isize,
+            (Type::Usize, Type::Isize) => Type::Usize,
+            (Type::Isize, Type::Isize) => Type::Isize,
+            (Type::Usize, Type::Usize) => Type::Usize,
+            (Type::Float(32), Type::Float(32)) => Type::Float(32),
+            (Type::Float(64), Type::Float(64)) => Type::Float(64),
+            (Type::IntVar(a), Type::IntVar(_)) => Type::IntVar(a),
+        }
+    );
+    check_op_impl!(
+        {
             [check_lshift, "<<"],
             [check_rshift, ">>"],
         }
@@ -65,6 +112,9 @@ impl Value {
             (Type::Unsigned(a), Type::Unsigned(b)) => Type::Unsigned(std::cmp::max(a, b)),
             (Type::Isize, Type::Usize) => Type::Isize,
             (Type::Usize, Type::Isize) => Type::Usize,
+            (Type::Isize, Type::Isize) => Type::Isize,
+            (Type::Usize, Type::Usize) => Type::Usize,
+            (Type::IntVar(a), Type::IntVar(_)) => Type::IntVar(a),
         }
     );
     check_op_impl!(
@@ -81,7 +131,30 @@ impl Value {
             (Type::Unsigned(_), Type::Unsigned(_)) => Type::Bool,
             (Type::Isize, Type::Usize) => Type::Bool,
             (Type::Usize, Type::Isize) => Type::Bool,
+            (Type::Isize, Type::Isize) => Type::Bool,
+            (Type::Usize, Type::Usize) => Type::Bool,
+            (Type::Float(32), Type::Float(32)) => Type::Bool,
+            (Type::Float(64), Type::Float(64)) => Type::Bool,
             (Type::Bool, Type::Bool) => Type::Bool,
+            (Type::IntVar(_), Type::IntVar(_)) => Type::Bool,
+        }
+    );
+
+    check_op_impl!(
+        {
+            [check_bitand, "&"],
+            [check_bitor, "|"],
+            [check_bitxor, "^"],
+        }
+        {
+            (Type::Signed(a), Type::Signed(b)) => Type::Signed(std::cmp::max(a, b)),
+            (Type::Unsigned(a), Type::Unsigned(b)) => Type::Unsigned(std::cmp::max(a, b)),
+            (Type::Isize, Type::Usize) => Type::Isize,
+            (Type::Usize, Type::Isize) => Type::Usize,
+            (Type::Isize, Type::Isize) => Type::Isize,
+            (Type::Usize, Type::Usize) => Type::Usize,
+            (Type::Bool, Type::Bool) => Type::Bool,
+            (Type::IntVar(a), Type::IntVar(_)) => Type::IntVar(a),
         }
     );
 
@@ -105,6 +178,20 @@ impl Value {
             Type::Unsigned(a) => Type::Unsigned(a),
             Type::Isize => Type::Isize,
             Type::Usize => Type::Usize,
+            Type::Float(a) => Type::Float(a),
+            Type::IntVar(a) => Type::IntVar(a),
+        }
+    );
+    check_op_impl!(#unary
+        {
+            [check_bitnot, "~"],
+        }
+        {
+            Type::Signed(a) => Type::Signed(a),
+            Type::Unsigned(a) => Type::Unsigned(a),
+            Type::Isize => Type::Isize,
+            Type::Usize => Type::Usize,
+            Type::IntVar(a) => Type::IntVar(a),
         }
     );
     check_op_impl!(#unary
@@ -0,0 +1,109 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, LazyLock, RwLock},
+};
+
+use ast::{
+    ConstInitialValue, Exp, ExpId, InterfaceType, StructType, Type as AstType, TypeEnum,
+    visitor::ExpVisitor,
+};
+
+use crate::{AnalyzerContext, Error, Type};
+
+impl AnalyzerContext {
+    pub(crate) fn interface_type(&mut self, interface: &InterfaceType) -> Type {
+        let ast_ctx = self.ctx.ast_ctx();
+        let mut methods = Vec::new();
+        for (name, proto) in &interface.methods {
+            let Exp::ProtoDef(proto_def) = ast_ctx.exp(*proto) else {
+                unreachable!()
+            };
+            let ty = self.proto_type(proto_def);
+            let Type::Function(_, param_types) = &ty else {
+                unreachable!()
+            };
+            if param_types.first() != Some(&Type::SelfType.pointer()) {
+                self.error_at(Error::InvalidInterfaceMethod(name.clone()), proto_def.span);
+            }
+            methods.push((name.clone(), ty));
+        }
+        Type::Interface(
+            interface.name.clone().unwrap_or_default(),
+            Arc::new(methods),
+        )
+    }
+
+    /// Checks that the structure has every method of the interfaces listed in
+    /// its `impl(...)`, with `Self` standing for the structure. Each structure
+    /// is checked once, before its methods are looked at, since their
+    /// signatures refer back to it.
+    pub(crate) fn check_conformance(&mut self, structure: &Type, implements: &[ExpId]) {
+        static CHECKED: LazyLock<RwLock<HashSet<String>>> =
+            LazyLock::new(|| RwLock::new(HashSet::new()));
+
+        let Type::Structure(_, Some(namespace)) = structure else {
+            return;
+        };
+        if implements.is_empty() || !CHECKED.write().unwrap().insert(namespace.clone()) {
+            return;
+        }
+
+        for &interface in implements {
+            let interface_ty = self.visit_right_value(interface).into_type();
+            let Type::Interface(interface_name, methods) = &interface_ty else {
+                self.error_at(Error::ExpectedInterface(interface_ty), interface.span());
+                continue;
+            };
+            for (name, expected) in methods.iter() {
+                let method = format!("{}::{}", namespace, name);
+                let Some(def_id) = self.ctx.lookup_def_id(&method) else {
+                    self.error_at(
+                        Error::MissingInterfaceMethod(interface_name.clone(), name.clone()),
+                        interface.span(),
+                    );
+                    continue;
+                };
+                let expected = expected.replace_self(structure);
+                let found = self.visit_global(def_id).into_type();
+                if found != expected {
+                    self.error_at(
+                        Error::InterfaceMethodMismatch(method, expected, found),
+                        interface.span(),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Whether `structure` lists `interface` in its `impl(...)`. Whether it
+    /// really provides the methods is checked with the structure itself.
+    pub(crate) fn implements(&mut self, structure: &Type, interface: &Type) -> bool {
+        let (Type::Structure(_, Some(namespace)), Type::Interface(name, _)) =
+            (structure, interface)
+        else {
+            return false;
+        };
+        let Some(const_def) = self
+            .ctx
+            .lookup_def_id(namespace)
+            .and_then(|def_id| self.ctx.get_def(def_id))
+        else {
+            return false;
+        };
+        let ConstInitialValue::Exp(exp) = &const_def.initial_value;
+        let ast_ctx = self.ctx.ast_ctx();
+        let Exp::Type(AstType {
+            kind: TypeEnum::Structure(StructType { implements, .. }),
+            ..
+        }) = ast_ctx.exp(exp.exp)
+        else {
+            return false;
+        };
+        implements.iter().any(|&implemented| {
+            matches!(
+                self.visit_right_value(implemented).into_type(),
+                Type::Interface(implemented, _) if implemented == *name
+            )
+        })
+    }
+}
@@ -1,17 +1,33 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashSet, ops::Deref, sync::Arc};
 
-use ast::{Exp, ExpId, Span, StructType, TypeEnum, visitor::ExpVisitor};
+use ast::{
+    EnumType, Exp, ExpId, FunctionDef, ProtoDef, Span, StructType, TypeEnum,
+    visitor::{BlockVisitor, ExpVisitor},
+};
+use const_eval::{Bindings, EvalError};
 pub use diagnostic::*;
+use flow::Flow;
+use infer::Literal;
 pub use info::*;
 use query::{DefId, QueryContext};
 use symbol_table::SymbolTable;
+use type_infer::InferTable;
 
+mod array;
 mod diagnostic;
 mod exp;
+mod flow;
+mod generic;
+mod infer;
 mod info;
+mod interface;
+mod method;
+mod mutability;
 mod program;
 pub mod queries;
 mod stmt;
+#[cfg(test)]
+mod tests;
 
 struct AnalyzerContext {
     symbols: SymbolTable<Symbol>,
@@ -20,7 +36,21 @@ struct AnalyzerContext {
     warnings: Vec<(Warning, Span)>,
     required: Vec<DefId>,
     ret_ty: Option<Type>,
-    in_loop: bool,
+    loops: Vec<LoopScope>,
+    infer: InferTable<Type>,
+    literals: Vec<Literal>,
+    bindings: Bindings,
+    mutable_vars: Vec<(String, Span)>,
+    mutated: HashSet<(String, Span)>,
+    flow: Flow,
+    erroneous_consts: HashSet<String>,
+}
+
+struct LoopScope {
+    label: Option<String>,
+    /// Only `loop` can be left with `break value`.
+    takes_value: bool,
+    break_ty: Option<Type>,
 }
 
 impl AnalyzerContext {
@@ -32,7 +62,14 @@ impl AnalyzerContext {
             warnings: Vec::new(),
             required: Vec::new(),
             ret_ty,
-            in_loop: false,
+            loops: Vec::new(),
+            infer: InferTable::new(),
+            literals: Vec::new(),
+            bindings: Bindings::new(),
+            mutable_vars: Vec::new(),
+            mutated: HashSet::new(),
+            flow: Flow::default(),
+            erroneous_consts: HashSet::new(),
         }
     }
 
@@ -40,13 +77,36 @@ impl AnalyzerContext {
         self.errors.push((e, s));
     }
 
-    #[allow(unused)]
+    /// Reports that an evaluation failed. A const that failed is reported
+    /// where it is first used only.
+    fn const_eval_error_at(&mut self, e: EvalError, s: Span) {
+        if let EvalError::ErroneousConst(name) = &e
+            && !self.erroneous_consts.insert(name.clone())
+        {
+            return;
+        }
+        self.error_at(Error::ConstEval(e), s);
+    }
+
     fn warning_at(&mut self, w: Warning, s: Span) {
         self.warnings.push((w, s));
     }
 
-    fn toggle_in_loop(&mut self) {
-        self.in_loop = !self.in_loop;
+    fn push_loop(&mut self, label: &Option<String>, takes_value: bool) {
+        self.loops.push(LoopScope {
+            label: label.clone(),
+            takes_value,
+            break_ty: None,
+        });
+    }
+
+    fn pop_loop(&mut self) -> Option<Type> {
+        self.loops.pop().and_then(|scope| scope.break_ty)
+    }
+
+    fn finish(&mut self) {
+        self.infer_literals(true);
+        self.warn_unused_mut();
     }
 }
 
@@ -55,53 +115,183 @@ impl AnalyzerContext {
         let ast_ctx = self.ctx.ast_ctx();
         let exp_body = ast_ctx.exp(exp);
         match exp_body {
-            Exp::ProtoDef(proto) => {
-                let ret_ty = proto
-                    .return_type
-                    .as_ref()
-                    .map(|t| self.visit_right_value(*t).into_type())
-                    .unwrap_or(Type::Unit);
-                let param_types = proto
-                    .params
-                    .iter()
-                    .map(|p| self.visit_right_value(p.param_type).into_type())
-                    .collect::<Vec<_>>();
-                Some(Type::Function(Box::new(ret_ty), param_types))
-            }
-            Exp::Function(func) => {
-                let ret_ty = func
-                    .return_type
-                    .as_ref()
-                    .map(|t| self.visit_right_value(*t).into_type())
-                    .unwrap_or(Type::Unit);
-                let param_types = func
-                    .params
-                    .iter()
-                    .map(|p| self.visit_right_value(p.param_type).into_type())
-                    .collect::<Vec<_>>();
-                Some(Type::Function(Box::new(ret_ty), param_types))
-            }
+            Exp::ProtoDef(proto) => Some(self.proto_type(proto)),
+            Exp::Function(func) if func.is_generic() => Some(Type::Generic(exp)),
+            Exp::Function(func) => Some(self.function_type(func)),
             _ => None,
         }
     }
+
+    fn proto_type(&mut self, proto: &ProtoDef) -> Type {
+        let ret_ty = proto
+            .return_type
+            .as_ref()
+            .map(|t| self.visit_right_value(*t).into_type())
+            .unwrap_or(Type::Unit);
+        let param_types = proto
+            .params
+            .iter()
+            .map(|p| self.visit_right_value(p.param_type).into_type())
+            .collect::<Vec<_>>();
+        Type::Function(Box::new(ret_ty), param_types)
+    }
+
+    fn function_type(&mut self, func: &FunctionDef) -> Type {
+        let ret_ty = func
+            .return_type
+            .as_ref()
+            .map(|t| self.visit_right_value(*t).into_type())
+            .unwrap_or(Type::Unit);
+        let param_types = func
+            .runtime_params()
+            .map(|p| self.visit_right_value(p.param_type).into_type())
+            .collect::<Vec<_>>();
+        Type::Function(Box::new(ret_ty), param_types)
+    }
+
+    fn check_function(&mut self, func: &FunctionDef) -> Type {
+        let ty = self.function_type(func);
+        let _ = self.check_function_body(func, &ty);
+        ty
+    }
+
+    /// Checks the body against the signature `ty` and returns the type of the
+    /// value it evaluates to. The body of a function returning `type` must be
+    /// a type, which is returned as is.
+    fn check_function_body(&mut self, func: &FunctionDef, ty: &Type) -> Option<Type> {
+        let Type::Function(ret_ty, param_types) = ty else {
+            unreachable!()
+        };
+        self.ret_ty = Some(ret_ty.deref().clone());
+        for (param, ty) in func.runtime_params().zip(param_types) {
+            self.symbols.pre_push(Symbol::Var(
+                param.name.clone(),
+                Binding::Param(param.span),
+                Value::new(ty.clone()),
+            ));
+        }
+        let outer_flow = std::mem::replace(&mut self.flow, Flow::analyze(self.ctx.ast_ctx(), func));
+        let body = <AnalyzerContext as BlockVisitor<_>>::visit_block(self, &func.block);
+        let flow = std::mem::replace(&mut self.flow, outer_flow);
+        self.errors.extend(flow.errors);
+        self.warnings.extend(flow.warnings);
+
+        // The body needs a value of its own unless every path returns.
+        if flow.falls_through
+            && body.as_ref().is_none_or(|value| value.type_().is_unit())
+            && !ret_ty.is_unit()
+            && **ret_ty != Type::Type
+        {
+            self.error_at(
+                Error::MissingReturn(ret_ty.deref().clone()),
+                func.block.span,
+            );
+            return body.map(Value::into_type);
+        }
+        let got_ret_ty = body?.into_type();
+        if **ret_ty == Type::Type {
+            if !func
+                .block
+                .return_value
+                .is_some_and(|value| self.is_type_exp(value))
+            {
+                self.error_at(Error::ExpectedType(got_ret_ty.clone()), func.block.span);
+            }
+        } else if !self.unify(ret_ty, &got_ret_ty) {
+            self.error_at(
+                Error::TypeMismatch(ret_ty.deref().clone(), got_ret_ty.clone()),
+                func.block.span,
+            );
+        }
+        Some(got_ret_ty)
+    }
 }
 
 impl AnalyzerContext {
+    /// Checks a value passed where `expected` is wanted, such as an argument
+    /// or the value of a variable. Besides values of that very type,
+    /// `*[T; N]` and string literals are accepted for `[]T`.
+    fn check_coercion(&mut self, exp: ExpId, expected: &Type) {
+        let found = self.visit_right_value(exp).into_type();
+        if self.unify(expected, &found) {
+            return;
+        }
+        let coerces = match (&found, expected) {
+            (Type::Ptr(array), Type::Slice(element)) => match array.as_ref() {
+                Type::Array(found, _) => self.unify(element, found),
+                Type::Signed(8) => {
+                    **element == Type::Signed(8)
+                        && matches!(self.ctx.ast_ctx().exp(exp), Exp::Str(..))
+                }
+                Type::Unsigned(8) => {
+                    **element == Type::Unsigned(8)
+                        && matches!(self.ctx.ast_ctx().exp(exp), Exp::ByteStr(..))
+                }
+                _ => false,
+            },
+            _ => false,
+        };
+        if !coerces {
+            self.error_at(Error::TypeMismatch(expected.clone(), found), exp.span());
+        }
+    }
+
     fn convert_type(&mut self, ty: &ast::Type) -> Type {
         match &ty.kind {
             TypeEnum::Signed(width) => Type::Signed(*width),
             TypeEnum::Unsigned(width) => Type::Unsigned(*width),
-            TypeEnum::Array(base, len) => self.visit_right_value(*base).into_type().array(*len),
+            TypeEnum::Slice(element) => self.visit_right_value(*element).into_type().slice(),
             TypeEnum::Unit => Type::Unit,
-            TypeEnum::Structure(StructType { fields, .. }) => {
-                let mut new_fields = HashMap::new();
-                for (name, &ty) in fields.iter() {
-                    new_fields.insert(name.clone(), self.visit_right_value(ty).into_type());
+            TypeEnum::Structure(StructType {
+                fields,
+                namespace,
+                implements,
+                layout,
+                ..
+            }) => {
+                if let Some(align) = layout.align
+                    && !align.is_power_of_two()
+                {
+                    self.error_at(Error::InvalidAlignment(align), ty.span);
+                }
+                let structure = Type::Structure(
+                    fields
+                        .iter()
+                        .map(|(name, ty)| (name.clone(), self.visit_right_value(*ty).into_type()))
+                        .collect(),
+                    namespace.clone(),
+                );
+                self.check_conformance(&structure, implements);
+                structure
+            }
+            TypeEnum::Interface(interface) => self.interface_type(interface),
+            TypeEnum::Dyn(interface) => {
+                let interface_ty = self.visit_right_value(*interface).into_type();
+                if !matches!(interface_ty, Type::Interface(..)) {
+                    self.error_at(
+                        Error::ExpectedInterface(interface_ty.clone()),
+                        interface.span(),
+                    );
                 }
-                Type::Structure(new_fields)
+                Type::Dyn(Box::new(interface_ty))
             }
+            TypeEnum::SelfType => Type::SelfType,
+            TypeEnum::Enum(EnumType { variants, .. }) => Type::Enum(
+                variants
+                    .iter()
+                    .map(|variant| {
+                        let payload = variant
+                            .payload
+                            .map(|ty| self.visit_right_value(ty).into_type());
+                        (variant.name.clone(), payload)
+                    })
+                    .collect(),
+            ),
             TypeEnum::Isize => Type::Isize,
             TypeEnum::Usize => Type::Usize,
+            TypeEnum::Float(width) => Type::Float(*width),
+            TypeEnum::Bool => Type::Bool,
+            TypeEnum::Type => Type::Type,
         }
     }
 }
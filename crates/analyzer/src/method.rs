@@ -0,0 +1,117 @@
+use std::{iter::zip, ops::Deref};
+
+use ast::{Call, ConstInitialValue, Exp, FieldAccess, visitor::ExpVisitor};
+
+use crate::{AnalyzerContext, Error, Type, Value};
+
+impl AnalyzerContext {
+    /// Checks `receiver.name(args)` as a call of the method `name` of the
+    /// receiver's structure, or returns `None` if `name` is a field, which is
+    /// then called like any other value.
+    pub(crate) fn check_method_call(
+        &mut self,
+        field_access: &FieldAccess,
+        call: &Call,
+    ) -> Option<Value> {
+        let receiver_value = self.visit_left_value(field_access.lhs);
+        let receiver = self.resolve(receiver_value.type_());
+        let structure = match &receiver {
+            Type::Ptr(target) if target.is_structure() => target.deref().clone(),
+            ty if ty.is_structure() => ty.clone(),
+            Type::Dyn(interface) => {
+                let interface = interface.deref().clone();
+                return Some(self.check_dyn_call(field_access, call, interface));
+            }
+            _ => return None,
+        };
+        let Type::Structure(fields, namespace) = &structure else {
+            unreachable!()
+        };
+        if fields.iter().any(|(name, _)| *name == field_access.field) {
+            return None;
+        }
+
+        let method = format!(
+            "{}::{}",
+            namespace.as_deref().unwrap_or_default(),
+            field_access.field
+        );
+        let Some(def_id) = namespace
+            .as_ref()
+            .and_then(|_| self.ctx.lookup_def_id(&method))
+        else {
+            self.error_at(
+                Error::UnknownMethod(field_access.field.clone(), structure),
+                field_access.span,
+            );
+            return Some(Value::default());
+        };
+
+        let ast_ctx = self.ctx.ast_ctx();
+        let ConstInitialValue::Exp(exp) = &self.ctx.get_def(def_id).unwrap().initial_value;
+        let Exp::Function(func) = ast_ctx.exp(exp.exp) else {
+            self.error_at(Error::NotAMethod(method), field_access.span);
+            return Some(Value::default());
+        };
+        if func.params.first().is_none_or(|param| param.name != "self") {
+            self.error_at(Error::NotAMethod(method), field_access.span);
+            return Some(Value::default());
+        }
+
+        let func = self.visit_global(def_id).into_type();
+        let Type::Function(ret_ty, param_types) = func else {
+            self.error_at(Error::WrongCall(func), call.span);
+            return Some(Value::default());
+        };
+        let self_ty = &param_types[0];
+        if *self_ty != structure && *self_ty != structure.pointer() {
+            self.error_at(
+                Error::ReceiverMismatch(method, self_ty.clone(), receiver),
+                field_access.lhs.span(),
+            );
+            return Some(Value::new(ret_ty.deref().clone()));
+        }
+
+        // Passing `self` by pointer takes the address of the receiver.
+        if receiver.is_structure() && self_ty.is_ptr() {
+            self.note_mutation(&receiver_value);
+        }
+        self.check_method_args(call, &param_types[1..]);
+
+        Some(Value::new(ret_ty.deref().clone()))
+    }
+
+    fn check_dyn_call(
+        &mut self,
+        field_access: &FieldAccess,
+        call: &Call,
+        interface: Type,
+    ) -> Value {
+        let Type::Interface(_, methods) = &interface else {
+            return Value::default();
+        };
+        let Some((_, Type::Function(ret_ty, param_types))) =
+            methods.iter().find(|(name, _)| *name == field_access.field)
+        else {
+            self.error_at(
+                Error::UnknownMethod(field_access.field.clone(), Type::Dyn(Box::new(interface))),
+                field_access.span,
+            );
+            return Value::default();
+        };
+        self.check_method_args(call, &param_types[1..]);
+        Value::new(ret_ty.deref().clone())
+    }
+
+    fn check_method_args(&mut self, call: &Call, param_types: &[Type]) {
+        if param_types.len() != call.args.len() {
+            self.error_at(
+                Error::ArgumentCount(param_types.len(), call.args.len()),
+                call.span,
+            );
+        }
+        for (arg, param_ty) in zip(&call.args, param_types) {
+            self.check_coercion(*arg, param_ty);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use ast::Span;
+
+use crate::{AnalyzerContext, Binding, Error, Place, Value, Warning};
+
+impl AnalyzerContext {
+    pub(crate) fn declare_var(&mut self, name: &str, binding: Binding) {
+        if let Binding::Let(true, span) = binding {
+            self.mutable_vars.push((name.to_string(), span));
+        }
+    }
+
+    /// Checks that `lhs`, the left value assigned to at `span`, is a place
+    /// that can be assigned: a `let mut` variable, memory behind a pointer,
+    /// or an element or field of either. Variables declared without a value
+    /// can be assigned as a whole, the flow analysis checks only once.
+    pub(crate) fn check_assignable(&mut self, lhs: &Value, span: Span) {
+        let error = match lhs.place() {
+            Some(Place::Var(_, Binding::Let(true, _) | Binding::Deferred(_)) | Place::Deref) => {
+                None
+            }
+            Some(Place::Var(name, Binding::Let(false, declared) | Binding::Pattern(declared))) => {
+                Some(Error::AssignToImmutable(name.clone(), *declared))
+            }
+            Some(Place::Var(name, Binding::Param(declared))) => {
+                Some(Error::AssignToParam(name.clone(), *declared))
+            }
+            Some(Place::Const(name, declared)) => {
+                Some(Error::AssignToConst(name.clone(), *declared))
+            }
+            None => Some(Error::AssignToValue),
+        };
+        match error {
+            Some(error) => self.error_at(error, span),
+            None => self.note_mutation(lhs),
+        }
+    }
+
+    /// Notes that the variable `value` is stored in, if any, may change,
+    /// because it is assigned or a pointer to it is taken.
+    pub(crate) fn note_mutation(&mut self, value: &Value) {
+        if let Some(Place::Var(name, Binding::Let(true, span))) = value.place() {
+            self.mutated.insert((name.clone(), *span));
+        }
+    }
+
+    pub(crate) fn warn_unused_mut(&mut self) {
+        for (name, span) in std::mem::take(&mut self.mutable_vars) {
+            if !self.mutated.contains(&(name.clone(), span)) {
+                self.warning_at(Warning::UnusedMut(name), span);
+            }
+        }
+    }
+}
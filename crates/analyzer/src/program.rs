@@ -1,6 +1,9 @@
-use ast::visitor::{BlockVisitor, ExpVisitor};
+use ast::{
+    VarBinding,
+    visitor::{BlockVisitor, ExpVisitor},
+};
 
-use crate::{AnalyzerContext, Error, Symbol, Value};
+use crate::{AnalyzerContext, Binding, Error, Symbol, Type, Value};
 
 impl BlockVisitor<Value> for AnalyzerContext {
     fn on_enter_block(&mut self) {
@@ -14,20 +17,46 @@ impl BlockVisitor<Value> for AnalyzerContext {
     fn visit_inline_asm(&mut self, _inline_asm: &ast::InlineAsm) {}
 
     fn visit_var_def(&mut self, var_def: &ast::VarDef) {
-        let value = self.visit_right_value(var_def.initial_value);
-        if let Some(should_be_type) = var_def
+        let binding = match (var_def.mutable, var_def.initial_value) {
+            (false, None) => Binding::Deferred(var_def.span),
+            (mutable, _) => Binding::Let(mutable, var_def.span),
+        };
+        let should_be_type = var_def
             .var_type
-            .as_ref()
-            .map(|ty| self.visit_right_value(*ty).into_type())
-            && should_be_type != *value.type_()
-        {
-            self.error_at(
-                Error::TypeMismatch(should_be_type, value.clone().into_type()),
-                var_def.initial_value.span(),
-            );
-        }
+            .map(|ty| self.visit_right_value(ty).into_type());
+        // The parser makes sure there is a type if there is no value.
+        let value = match (var_def.initial_value, should_be_type) {
+            (Some(value), Some(should_be_type)) => {
+                self.check_coercion(value, &should_be_type);
+                Value::new(should_be_type)
+            }
+            (Some(value), None) => self.visit_right_value(value),
+            (None, should_be_type) => Value::new(should_be_type.unwrap()),
+        };
+        let value_span = var_def
+            .initial_value
+            .map(|value| value.span())
+            .unwrap_or(var_def.span);
 
-        self.symbols
-            .push(Symbol::Var(var_def.name.clone(), var_def.mutable, value));
+        match &var_def.binding {
+            VarBinding::Name(name) => {
+                self.declare_var(name, binding);
+                self.symbols.push(Symbol::Var(name.clone(), binding, value));
+            }
+            VarBinding::Tuple(names) => {
+                let elements = match value.into_type() {
+                    Type::Tuple(elements) if elements.len() == names.len() => elements,
+                    ty => {
+                        self.error_at(Error::DestructureMismatch(names.len(), ty), value_span);
+                        vec![Type::default(); names.len()]
+                    }
+                };
+                for (name, ty) in names.iter().zip(elements) {
+                    self.declare_var(name, binding);
+                    self.symbols
+                        .push(Symbol::Var(name.clone(), binding, Value::new(ty)));
+                }
+            }
+        }
     }
 }
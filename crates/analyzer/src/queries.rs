@@ -1,19 +1,23 @@
-use std::sync::{Arc, LazyLock};
-
-use ast::{
-    ConstExp, ConstInitialValue, Exp, FileTable, Span,
-    visitor::{BlockVisitor, ExpVisitor},
+use std::{
+    collections::HashSet,
+    sync::{Arc, LazyLock, RwLock},
 };
+
+use ast::{ConstExp, ConstInitialValue, Exp, FileTable, Span, visitor::ExpVisitor};
+use const_eval::queries::TRY_CONST_EVAL_PROVIDER;
 use lint::LintDumper;
 use query::{DefId, Provider, QueryContext};
 
-use crate::{AnalyzerContext, Error, Symbol, Type, Value, Warning};
+use crate::{AnalyzerContext, Error, Type, Value, Warning};
 
 pub static CHECK_CONST_DEF: LazyLock<Provider<DefId, AnalyzeResult>> =
     LazyLock::new(|| Provider::new(check_const_def));
 
+static IN_PROGRESS: LazyLock<RwLock<HashSet<DefId>>> =
+    LazyLock::new(|| RwLock::new(HashSet::new()));
+
 #[must_use]
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct AnalyzeResult {
     pub(crate) value: Value,
     pub(crate) errors: Vec<(Error, Span)>,
@@ -31,27 +35,47 @@ impl AnalyzeResult {
     }
 
     pub fn dump(&mut self, ctx: Arc<QueryContext>, file_table: &FileTable) {
-        for required in &self.required {
-            let AnalyzeResult {
-                errors, warnings, ..
-            } = ctx.query(&CHECK_CONST_DEF, *required).unwrap();
+        for AnalyzeResult {
+            errors, warnings, ..
+        } in check_required(&ctx, &self.required)
+        {
             self.errors.extend(errors);
             self.warnings.extend(warnings);
         }
 
         let mut dumper = LintDumper::new(file_table);
 
-        dumper.lints(self.errors.iter());
+        for (error, span) in &self.errors {
+            dumper.lint(error, *span, error.related());
+        }
         dumper.lints(self.warnings.iter());
 
         dumper.dump();
     }
 }
 
+/// Checks the consts `roots` require, and the ones those require in turn,
+/// each once. Consts being checked right now are left to whoever started.
+pub(crate) fn check_required(ctx: &Arc<QueryContext>, roots: &[DefId]) -> Vec<AnalyzeResult> {
+    let mut visited = HashSet::new();
+    let mut pending = roots.to_vec();
+    let mut results = Vec::new();
+    while let Some(def_id) = pending.pop() {
+        if !visited.insert(def_id) || IN_PROGRESS.read().unwrap().contains(&def_id) {
+            continue;
+        }
+        let result = ctx.query_cached(&CHECK_CONST_DEF, def_id).unwrap();
+        pending.extend(&result.required);
+        results.push(result);
+    }
+    results
+}
+
 fn check_const_def(ctx: Arc<QueryContext>, def_id: DefId) -> AnalyzeResult {
     let Some(const_def) = ctx.get_def(def_id) else {
         return AnalyzeResult::default();
     };
+    IN_PROGRESS.write().unwrap().insert(def_id);
     let mut analyzer_ctx = AnalyzerContext::new(ctx.clone(), None);
 
     let result = match &const_def.initial_value {
@@ -59,62 +83,39 @@ fn check_const_def(ctx: Arc<QueryContext>, def_id: DefId) -> AnalyzeResult {
             let ast_ctx = ctx.ast_ctx();
             let exp_body = ast_ctx.exp(*exp);
             let ty = match exp_body {
-                Exp::ProtoDef(proto) => {
-                    let ret_ty = proto
-                        .return_type
-                        .as_ref()
-                        .map(|t| analyzer_ctx.visit_right_value(*t).into_type())
-                        .unwrap_or(Type::Unit);
-                    let param_types = proto
-                        .params
-                        .iter()
-                        .map(|p| analyzer_ctx.visit_right_value(p.param_type).into_type())
-                        .collect::<Vec<_>>();
-                    Type::Function(Box::new(ret_ty), param_types)
-                }
-                Exp::Function(func) => {
-                    let ret_ty = func
-                        .return_type
-                        .as_ref()
-                        .map(|t| analyzer_ctx.visit_right_value(*t).into_type())
-                        .unwrap_or(Type::Unit);
-                    analyzer_ctx.ret_ty = Some(ret_ty.clone());
-                    let mut param_types = Vec::new();
-                    for param in func.params.iter() {
-                        let ty = analyzer_ctx.visit_right_value(param.param_type).into_type();
-                        param_types.push(ty.clone());
-                        analyzer_ctx.symbols.pre_push(Symbol::Var(
-                            param.name.clone(),
-                            false,
-                            Value::new(ty),
-                        ));
-                    }
-                    if let Some(got_ret_ty) = <AnalyzerContext as BlockVisitor<_>>::visit_block(
-                        &mut analyzer_ctx,
-                        &func.block,
-                    ) {
-                        let got_ret_ty = got_ret_ty.into_type();
-                        if ret_ty != got_ret_ty {
-                            analyzer_ctx.error_at(
-                                Error::TypeMismatch(ret_ty.clone(), got_ret_ty),
-                                func.block.span,
-                            );
+                Exp::ProtoDef(proto) => analyzer_ctx.proto_type(proto),
+                Exp::Function(func) if func.is_generic() => Type::Generic(*exp),
+                Exp::Function(func) => analyzer_ctx.check_function(func),
+                _ => {
+                    let ty = analyzer_ctx.visit_right_value(*exp).into_type();
+                    analyzer_ctx.infer_literals(true);
+                    // Only well-typed consts can be evaluated, and only once
+                    // the types of the literals in what they call are known.
+                    if analyzer_ctx.errors.is_empty() {
+                        check_required(&ctx, &analyzer_ctx.required);
+                        if let Err((error, span)) =
+                            ctx.query_cached(&TRY_CONST_EVAL_PROVIDER, def_id).unwrap()
+                        {
+                            analyzer_ctx.const_eval_error_at(error, span);
                         }
                     }
-                    Type::Function(Box::new(ret_ty), param_types)
+                    ty
                 }
-                _ => analyzer_ctx.visit_right_value(*exp).into_type(),
             };
-            Value::new(ty)
+            analyzer_ctx.finish();
+            Value::new(analyzer_ctx.resolve(&ty))
         }
     };
+    IN_PROGRESS.write().unwrap().remove(&def_id);
 
     let AnalyzerContext {
         errors,
         warnings,
-        required,
+        mut required,
         ..
     } = analyzer_ctx;
+    // A recursive function doesn't need itself checked again.
+    required.retain(|&required| required != def_id);
 
     AnalyzeResult {
         value: result,
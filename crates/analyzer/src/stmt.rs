@@ -1,21 +1,28 @@
 use ast::{
-    Assign, For, IfExp, While,
+    Assign, ConstInitialValue, Exp, For, IfExp, Match, Pattern, While,
     visitor::{ExpVisitor, StatementVisitor},
 };
 
-use crate::{AnalyzerContext, Error, Symbol, Type, Value};
+use crate::{AnalyzerContext, Binding, Error, Symbol, Type, Value, Warning, number_pattern};
 
 impl StatementVisitor<Value> for AnalyzerContext {
     fn visit_assign(&mut self, assign: &Assign) -> Value {
-        let Assign { lhs, rhs, .. } = assign;
+        let Assign { lhs, op, rhs, span } = assign;
 
         let lhs_val = self.visit_left_value(*lhs);
-        let lhs_type = lhs_val.into_type();
+        self.check_assignable(&lhs_val, lhs.span());
+        let lhs_type = self.resolve(lhs_val.type_());
 
+        let Some(op) = op else {
+            self.check_coercion(*rhs, &lhs_type);
+            return Value::new(Type::Unit);
+        };
         let rhs_val = self.visit_right_value(*rhs);
-        let rhs_type = rhs_val.into_type();
+        let rhs_type = self
+            .visit_binary(op, Value::new(lhs_type.clone()), rhs_val, span)
+            .into_type();
 
-        if lhs_type != rhs_type {
+        if !self.unify(&lhs_type, &rhs_type) {
             self.error_at(Error::TypeMismatch(lhs_type, rhs_type), rhs.span());
         }
         Value::new(Type::Unit)
@@ -36,7 +43,7 @@ impl StatementVisitor<Value> for AnalyzerContext {
         let end_val = self.visit_right_value(*end);
         let end_type = end_val.into_type();
 
-        if start_type != end_type {
+        if !self.unify(&start_type, &end_type) {
             self.error_at(
                 Error::TypeMismatch(start_type.clone(), end_type),
                 end.span(),
@@ -45,7 +52,7 @@ impl StatementVisitor<Value> for AnalyzerContext {
 
         if let Some(step_val) = step.as_ref().map(|s| self.visit_right_value(*s)) {
             let step_type = step_val.into_type();
-            if step_type != start_type {
+            if !self.unify(&start_type, &step_type) {
                 self.error_at(
                     Error::TypeMismatch(start_type, step_type),
                     step.as_ref().unwrap().span(),
@@ -53,14 +60,17 @@ impl StatementVisitor<Value> for AnalyzerContext {
             }
         }
 
-        self.symbols
-            .pre_push(Symbol::Var(var.clone(), false, start_val));
+        self.symbols.pre_push(Symbol::Var(
+            var.clone(),
+            Binding::Pattern(for_.span),
+            start_val,
+        ));
 
-        self.toggle_in_loop();
+        self.push_loop(&for_.label, false);
         let block_ret_type = self.visit_block(body).into_type();
-        self.toggle_in_loop();
+        self.pop_loop();
 
-        if block_ret_type != Type::Unit {
+        if !self.unify(&Type::Unit, &block_ret_type) {
             self.error_at(
                 Error::TypeMismatch(Type::Unit, block_ret_type),
                 body.return_value.as_ref().unwrap().span(),
@@ -95,27 +105,153 @@ impl StatementVisitor<Value> for AnalyzerContext {
         } else {
             Type::Unit
         };
-        if then_branch_ty != else_branch_ty {
+        if !self.unify(&then_branch_ty, &else_branch_ty) {
             self.error_at(
-                Error::TypeMismatch(else_branch_ty, then_branch_ty.clone()),
+                Error::TypeMismatch(else_branch_ty.clone(), then_branch_ty.clone()),
                 then_branch.span,
             );
         }
 
-        Value::new(then_branch_ty)
+        // The value comes from the branch control can leave normally.
+        match then_branch_ty {
+            Type::Never => Value::new(else_branch_ty),
+            then_branch_ty => Value::new(then_branch_ty),
+        }
+    }
+
+    fn visit_match(&mut self, match_: &Match) -> Value {
+        let Match { exp, arms, span } = match_;
+
+        let ty = self.visit_right_value(*exp).into_type();
+
+        let mut covered_numbers = Vec::new();
+        let mut covered_variants = Vec::new();
+        let mut has_wildcard = false;
+        let mut result_ty: Option<Type> = None;
+
+        for arm in arms {
+            if has_wildcard {
+                self.warning_at(Warning::UnreachablePattern, arm.pattern.span());
+            }
+
+            self.symbols.push_scope();
+            match &arm.pattern {
+                Pattern::Wildcard(_) => has_wildcard = true,
+                Pattern::Number(number, negative) => {
+                    let pattern_ty = self.new_literal(number, *negative, number.span);
+                    if !matches!(ty, number_pattern!()) {
+                        self.error_at(Error::PatternMismatch(ty.clone()), number.span);
+                    } else if !self.unify(&ty, &pattern_ty) {
+                        self.error_at(Error::TypeMismatch(ty.clone(), pattern_ty), number.span);
+                    }
+                    // `-0` is the same pattern as `0`.
+                    let value = (*negative && number.num != 0, number.num);
+                    if covered_numbers.contains(&value) {
+                        self.warning_at(Warning::UnreachablePattern, number.span);
+                    } else {
+                        covered_numbers.push(value);
+                    }
+                }
+                Pattern::Variant(path, binding, span) => {
+                    let Type::Enum(variants) = &ty else {
+                        self.error_at(Error::PatternMismatch(ty.clone()), *span);
+                        self.symbols.pop_scope();
+                        continue;
+                    };
+                    let name = path.path.last().unwrap();
+
+                    let full_name = path.path.join("::");
+                    let variant_exp = self.ctx.lookup_def_id(&full_name).and_then(|def_id| {
+                        let ConstInitialValue::Exp(exp) = &self.ctx.get_def(def_id)?.initial_value;
+                        Some(exp.exp)
+                    });
+                    let Some(variant_exp) = variant_exp else {
+                        self.error_at(Error::Unknown(full_name), *span);
+                        self.symbols.pop_scope();
+                        continue;
+                    };
+                    let ast_ctx = self.ctx.ast_ctx();
+                    let Exp::Variant(variant) = ast_ctx.exp(variant_exp) else {
+                        self.error_at(Error::InvalidVariant(name.clone(), ty.clone()), *span);
+                        self.symbols.pop_scope();
+                        continue;
+                    };
+                    let enum_ty = self.visit_right_value(variant.ty).into_type();
+                    if enum_ty != ty {
+                        self.error_at(Error::TypeMismatch(ty.clone(), enum_ty), *span);
+                    }
+
+                    let payload = variants
+                        .iter()
+                        .find(|(variant, _)| variant == name)
+                        .and_then(|(_, payload)| payload.clone());
+                    if covered_variants.contains(name) {
+                        self.warning_at(Warning::UnreachablePattern, *span);
+                    } else {
+                        covered_variants.push(name.clone());
+                    }
+
+                    match (binding, payload) {
+                        (Some(binding), Some(payload)) => self.symbols.push(Symbol::Var(
+                            binding.clone(),
+                            Binding::Pattern(*span),
+                            Value::new(payload),
+                        )),
+                        (Some(_), None) => {
+                            self.error_at(Error::UnexpectedBinding(name.clone()), *span)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            let arm_ty = self.visit_right_value(arm.body).into_type();
+            self.symbols.pop_scope();
+
+            match &result_ty {
+                None | Some(Type::Never) => result_ty = Some(arm_ty),
+                Some(expected) if !self.unify(expected, &arm_ty) => {
+                    self.error_at(
+                        Error::TypeMismatch(expected.clone(), arm_ty),
+                        arm.body.span(),
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        if !has_wildcard {
+            if let Type::Enum(variants) = &ty {
+                let missing = variants
+                    .iter()
+                    .filter(|(name, _)| !covered_variants.contains(name))
+                    .map(|(name, _)| name.clone())
+                    .collect::<Vec<_>>();
+                if !missing.is_empty() {
+                    self.error_at(Error::NonExhaustiveMatch(missing.join(", ")), *span);
+                }
+            } else {
+                self.error_at(Error::NonExhaustiveMatch("_".into()), *span);
+            }
+        }
+
+        Value::new(result_ty.unwrap_or(Type::Unit))
     }
 
     fn visit_loop(&mut self, loop_: &ast::Loop) -> Value {
-        self.toggle_in_loop();
+        self.push_loop(&loop_.label, true);
         let loop_ret_ty = self.visit_block(&loop_.body).into_type();
-        self.toggle_in_loop();
-        if !loop_ret_ty.is_unit() {
+        let break_ty = self.pop_loop();
+        if !self.unify(&Type::Unit, &loop_ret_ty) {
             self.error_at(
                 Error::TypeMismatch(Type::Unit, loop_ret_ty),
                 loop_.body.return_value.as_ref().unwrap().span(),
             );
         }
-        Value::new(Type::Unit)
+        if self.flow.diverges(loop_.span) {
+            Value::new(Type::Never)
+        } else {
+            Value::new(break_ty.unwrap_or(Type::Unit))
+        }
     }
 
     fn visit_return(&mut self, return_stmt: &ast::Return) -> Value {
@@ -124,12 +260,12 @@ impl StatementVisitor<Value> for AnalyzerContext {
             .as_ref()
             .map(|v| self.visit_right_value(*v).into_type())
             .unwrap_or(Type::Unit);
-        let Some(should_be_ty) = self.ret_ty.as_ref() else {
+        let Some(should_be_ty) = self.ret_ty.clone() else {
             return Value::default();
         };
-        if ty != *should_be_ty {
+        if !self.unify(&should_be_ty, &ty) {
             self.error_at(
-                Error::TypeMismatch(should_be_ty.clone(), ty),
+                Error::TypeMismatch(should_be_ty, ty),
                 return_stmt
                     .value
                     .as_ref()
@@ -137,7 +273,7 @@ impl StatementVisitor<Value> for AnalyzerContext {
                     .unwrap_or(return_stmt.span),
             );
         }
-        Value::default()
+        Value::new(Type::Never)
     }
 
     fn visit_while(&mut self, while_: &While) -> Value {
@@ -153,11 +289,11 @@ impl StatementVisitor<Value> for AnalyzerContext {
             );
         }
 
-        self.toggle_in_loop();
+        self.push_loop(&while_.label, false);
         let loop_ret_ty = self.visit_block(body).into_type();
-        self.toggle_in_loop();
+        self.pop_loop();
 
-        if !loop_ret_ty.is_unit() {
+        if !self.unify(&Type::Unit, &loop_ret_ty) {
             self.error_at(
                 Error::TypeMismatch(Type::Unit, loop_ret_ty),
                 body.return_value.as_ref().unwrap().span(),
@@ -166,17 +302,60 @@ impl StatementVisitor<Value> for AnalyzerContext {
         Value::new(Type::Unit)
     }
 
-    fn visit_break(&mut self, span: ast::Span) -> Value {
-        if !self.in_loop {
-            self.error_at(Error::BreakOutsideLoop, span);
+    fn visit_break(&mut self, break_: &ast::Break) -> Value {
+        let ty = break_
+            .value
+            .map(|value| self.visit_right_value(value).into_type())
+            .unwrap_or(Type::Unit);
+        let Some(target) = self.find_loop(&break_.label, break_.span, Error::BreakOutsideLoop)
+        else {
+            return Value::new(Type::Never);
+        };
+        let value_span = break_
+            .value
+            .map(|value| value.span())
+            .unwrap_or(break_.span);
+
+        if break_.value.is_some() && !self.loops[target].takes_value {
+            self.error_at(Error::BreakValueOutsideLoop, value_span);
+            return Value::new(Type::Never);
+        }
+        match self.loops[target].break_ty.clone() {
+            None | Some(Type::Never) => self.loops[target].break_ty = Some(ty),
+            Some(expected) => {
+                if !self.unify(&expected, &ty) {
+                    self.error_at(Error::TypeMismatch(expected, ty), value_span);
+                }
+            }
         }
-        Value::default()
+        Value::new(Type::Never)
     }
 
-    fn visit_continue(&mut self, span: ast::Span) -> Value {
-        if !self.in_loop {
-            self.error_at(Error::ContinueOutsideLoop, span);
+    fn visit_continue(&mut self, continue_: &ast::Continue) -> Value {
+        self.find_loop(&continue_.label, continue_.span, Error::ContinueOutsideLoop);
+        Value::new(Type::Never)
+    }
+}
+
+impl AnalyzerContext {
+    fn find_loop(
+        &mut self,
+        label: &Option<String>,
+        span: ast::Span,
+        outside: Error,
+    ) -> Option<usize> {
+        let target = self
+            .loops
+            .iter()
+            .rposition(|scope| label.is_none() || scope.label == *label);
+        if target.is_none() {
+            match label {
+                Some(label) if !self.loops.is_empty() => {
+                    self.error_at(Error::UndeclaredLabel(label.clone()), span)
+                }
+                _ => self.error_at(outside, span),
+            }
         }
-        Value::default()
+        target
     }
 }
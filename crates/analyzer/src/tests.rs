@@ -0,0 +1,266 @@
+use std::{io::Write, sync::Arc};
+
+use ast::{FileTable, ParseContext};
+use parser::CaraParser;
+use query::QueryContext;
+use targets::spec::Target;
+
+use crate::{
+    Error, Type, Warning,
+    queries::{AnalyzeResult, CHECK_CONST_DEF},
+};
+
+/// Checks the const `name` declared in `source`. Query results are cached per
+/// process, so `name` is checked uncached, and only one test may refer to
+/// other consts.
+fn check(source: &str, name: &str) -> AnalyzeResult {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(source.as_bytes()).unwrap();
+    let file_table = FileTable::new();
+    let file_id = file_table
+        .register_file(file.path().to_string_lossy().into_owned())
+        .unwrap();
+    let ast = ParseContext::new(&file_table)
+        .parse(&CaraParser, file_id)
+        .unwrap();
+    let ctx = QueryContext::builder()
+        .crate_name("main".into())
+        .ast(Arc::new(simplifier::simplify("main".into(), ast)))
+        .target(Target::default())
+        .build();
+    let def_id = ctx.lookup_def_id(format!("::main::{}", name)).unwrap();
+    ctx.query(&CHECK_CONST_DEF, def_id).unwrap()
+}
+
+/// The start and end of the first occurrence of `text` in `source`.
+fn span_of(source: &str, text: &str) -> (usize, usize) {
+    let start = source.find(text).unwrap();
+    (start, start + text.len())
+}
+
+/// The only error checking `name` gives.
+fn only_error(source: &str, name: &str) -> (Error, (usize, usize)) {
+    let result = check(source, name);
+    let [(error, span)] = result.errors.as_slice() else {
+        panic!("expected one error, found {:?}", result.errors);
+    };
+    (error.clone(), (span.start(), span.end()))
+}
+
+#[test]
+fn match_without_wildcard_is_not_exhaustive() {
+    let text = "match n { 0 => 1, -1 => 2 }";
+    let source = format!("const f = fn(n: i32) -> i32 {{{}}};", text);
+    let result = check(&source, "f");
+    let [(error, span)] = result.errors.as_slice() else {
+        panic!("expected one error, found {:?}", result.errors);
+    };
+    assert!(matches!(error, Error::NonExhaustiveMatch(missing) if missing == "_"));
+    assert_eq!((span.start(), span.end()), span_of(&source, text));
+}
+
+#[test]
+fn repeated_number_patterns_are_unreachable() {
+    let source =
+        "const f = fn(n: i128) -> i32 { match n { -1 => 1, 0 => 2, -0 => 3, -1 => 4, _ => 5 } };";
+    let result = check(source, "f");
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    let spans = result
+        .warnings
+        .iter()
+        .map(|(warning, span)| {
+            assert!(matches!(warning, Warning::UnreachablePattern));
+            (span.start(), span.end())
+        })
+        .collect::<Vec<_>>();
+    let second = source.rfind("-1").unwrap();
+    assert_eq!(spans, [span_of(source, "-0"), (second, second + 2)]);
+}
+
+#[test]
+fn patterns_after_a_wildcard_are_unreachable() {
+    let source = "const f = fn(n: u8) -> i32 { match n { _ => 1, 255 => 2 } };";
+    let result = check(source, "f");
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    let [(Warning::UnreachablePattern, span)] = result.warnings.as_slice() else {
+        panic!("expected one warning, found {:?}", result.warnings);
+    };
+    assert_eq!((span.start(), span.end()), span_of(source, "255"));
+}
+
+#[test]
+fn match_lists_missing_variants() {
+    let text = "match s { E::A => 1 }";
+    let source = format!(
+        "const E = enum {{ A, B, C: i32 }}; const f = fn(s: E) -> i32 {{{}}};",
+        text
+    );
+    let result = check(&source, "f");
+    let [(error, span)] = result.errors.as_slice() else {
+        panic!("expected one error, found {:?}", result.errors);
+    };
+    assert!(matches!(error, Error::NonExhaustiveMatch(missing) if missing == "B, C"));
+    assert_eq!((span.start(), span.end()), span_of(&source, text));
+}
+
+#[test]
+fn alignment_must_be_a_power_of_two() {
+    let text = "align(0xC) struct { a: u8 }";
+    let source = format!("const S = {};", text);
+    let result = check(&source, "S");
+    let [(error, span)] = result.errors.as_slice() else {
+        panic!("expected one error, found {:?}", result.errors);
+    };
+    assert!(matches!(error, Error::InvalidAlignment(12)));
+    assert_eq!((span.start(), span.end()), span_of(&source, text));
+}
+
+#[test]
+fn literals_must_fit_their_type() {
+    let source = "const f = fn() -> u8 { let a: u8 = 256; a };";
+    let (error, span) = only_error(source, "f");
+    assert!(
+        matches!(error, Error::LiteralOutOfRange(literal, Type::Unsigned(8)) if literal == "256")
+    );
+    assert_eq!(span, span_of(source, "256"));
+
+    let source = "const f = fn() -> i8 { -129 };";
+    let (error, span) = only_error(source, "f");
+    assert!(
+        matches!(error, Error::LiteralOutOfRange(literal, Type::Signed(8)) if literal == "-129")
+    );
+    assert_eq!(span, span_of(source, "-129"));
+
+    let source = "const f = fn() -> i8 { -128 };";
+    assert!(check(source, "f").errors.is_empty());
+}
+
+#[test]
+fn literal_types_are_inferred_from_later_uses() {
+    let source = "const f = fn() -> u8 { let a = 300; let b: u8 = a; b };";
+    let (error, span) = only_error(source, "f");
+    assert!(
+        matches!(error, Error::LiteralOutOfRange(literal, Type::Unsigned(8)) if literal == "300")
+    );
+    assert_eq!(span, span_of(source, "300"));
+}
+
+/// The start of `span` and the text it covers, without the whitespace after
+/// it that the spans of variables take.
+fn text(source: &str, (start, end): (usize, usize)) -> (usize, &str) {
+    (start, source[start..end].trim_end())
+}
+
+#[test]
+fn immutable_places_are_not_assigned() {
+    let source = "const f = fn() -> i32 { let a = 1; a = 2; a };";
+    let (error, span) = only_error(source, "f");
+    let Error::AssignToImmutable(name, declared) = &error else {
+        panic!("expected an immutable assignment, found {error:?}");
+    };
+    assert_eq!(name, "a");
+    assert_eq!(declared.start(), span_of(source, "let a").0);
+    assert_eq!(text(source, span), (span_of(source, "a = 2").0, "a"));
+
+    let source = "const f = fn(x: i32) -> i32 { x = 2; x };";
+    let (error, span) = only_error(source, "f");
+    assert!(matches!(&error, Error::AssignToParam(name, _) if name == "x"));
+    assert_eq!(text(source, span), (span_of(source, "x = 2").0, "x"));
+
+    let source = "const f = fn() -> i32 { 1 = 2; 0 };";
+    let (error, span) = only_error(source, "f");
+    assert!(matches!(error, Error::AssignToValue));
+    assert_eq!(text(source, span), (span_of(source, "1 =").0, "1"));
+}
+
+#[test]
+fn unmutated_mut_is_a_warning() {
+    let source = "const f = fn() -> i32 { let mut a = 1; let mut b = 2; b = 3; a + b };";
+    let result = check(source, "f");
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    let [(Warning::UnusedMut(name), span)] = result.warnings.as_slice() else {
+        panic!("expected one warning, found {:?}", result.warnings);
+    };
+    assert_eq!(name, "a");
+    assert_eq!(span.start(), span_of(source, "let mut a").0);
+}
+
+#[test]
+fn every_path_must_return() {
+    let body = "{ if x > 0 { return 1; } }";
+    let source = format!("const f = fn(x: i32) -> i32 {};", body);
+    let (error, span) = only_error(&source, "f");
+    assert!(matches!(error, Error::MissingReturn(Type::Signed(32))));
+    assert_eq!(span, span_of(&source, body));
+
+    let source = "const f = fn(x: i32) -> i32 { if x > 0 { return 1; } else { return 2; } };";
+    assert!(check(source, "f").errors.is_empty());
+}
+
+#[test]
+fn code_after_return_is_unreachable() {
+    let source = "const f = fn() -> i32 { return 1; 2 };";
+    let result = check(source, "f");
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    let [(Warning::UnreachableCode, span)] = result.warnings.as_slice() else {
+        panic!("expected one warning, found {:?}", result.warnings);
+    };
+    let value = source.rfind('2').unwrap();
+    assert_eq!((span.start(), span.end()), (value, value + 1));
+}
+
+#[test]
+fn variables_are_assigned_once_before_use() {
+    let source = "const f = fn(x: i32) -> i32 { let a: i32; if x > 0 { a = 1; } a };";
+    let (error, span) = only_error(source, "f");
+    assert!(matches!(&error, Error::Uninitialized(name) if name == "a"));
+    assert_eq!(text(source, span), (source.rfind("a }").unwrap(), "a"));
+
+    let source = "const f = fn() -> i32 { let a: i32; a = 1; a = 2; a };";
+    let (error, span) = only_error(source, "f");
+    assert!(matches!(&error, Error::AssignTwice(name, _) if name == "a"));
+    assert_eq!(text(source, span), (span_of(source, "a = 2").0, "a"));
+
+    let source =
+        "const f = fn(x: i32) -> i32 { let a: i32; if x > 0 { a = 1; } else { a = 2; } a };";
+    assert!(check(source, "f").errors.is_empty());
+}
+
+#[test]
+fn labels_must_name_an_enclosing_loop() {
+    let source = "const f = fn() -> i32 { 'outer: loop { loop { break 'inner; }; }; 0 };";
+    let result = check(source, "f");
+    let [(Error::UndeclaredLabel(label), span)] = result.errors.as_slice() else {
+        panic!("expected one error, found {:?}", result.errors);
+    };
+    assert_eq!(label, "inner");
+    assert_eq!((span.start(), span.end()), span_of(source, "break 'inner"));
+
+    let source = "const f = fn() -> i32 { 'outer: loop { loop { break 'outer; }; }; 0 };";
+    assert!(check(source, "f").errors.is_empty());
+}
+
+#[test]
+fn only_loop_breaks_with_a_value() {
+    let source = "const f = fn() -> i32 { for i in (0, 1) { break 1; }; 0 };";
+    let (error, span) = only_error(source, "f");
+    assert!(matches!(error, Error::BreakValueOutsideLoop));
+    let value = span_of(source, "break 1").1 - 1;
+    assert_eq!(span, (value, value + 1));
+
+    let source = "const f = fn() -> i32 { loop { break 1; } };";
+    assert!(check(source, "f").errors.is_empty());
+}
+
+#[test]
+fn radix_literals_are_range_checked() {
+    let source = "const f = fn() -> u8 { 0x1_00 };";
+    let (error, span) = only_error(source, "f");
+    assert!(
+        matches!(error, Error::LiteralOutOfRange(literal, Type::Unsigned(8)) if literal == "256")
+    );
+    assert_eq!(span, span_of(source, "0x1_00"));
+
+    let source = "const f = fn() -> i8 { -0b1000_0000 + 0o177 + 0x0 };";
+    assert!(check(source, "f").errors.is_empty());
+}
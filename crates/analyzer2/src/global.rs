@@ -28,7 +28,10 @@ impl GlobalContext {
                 let element_type = Self::infer_exp(values[0], ctx);
                 Arc::new(Type::Array(element_type))
             }
-            _ => unimplemented!(),
+            Array::Template(value, _, _) => {
+                let element_type = Self::infer_exp(*value, ctx);
+                Arc::new(Type::Array(element_type))
+            }
         }
     }
 }
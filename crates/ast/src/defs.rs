@@ -2,17 +2,35 @@ use super::*;
 
 #[derive(Debug, Clone)]
 pub struct VarDef {
-    pub name: String,
+    pub binding: VarBinding,
     pub var_type: Option<ExpId>,
-    pub initial_value: ExpId,
+    /// `None` for `let x: T;`, which is assigned later.
+    pub initial_value: Option<ExpId>,
     pub mutable: bool,
     pub span: Span,
 }
 
+#[derive(Debug, Clone)]
+pub enum VarBinding {
+    Name(String),
+    Tuple(Vec<String>),
+}
+
+impl VarBinding {
+    pub fn names(&self) -> &[String] {
+        match self {
+            VarBinding::Name(name) => std::slice::from_ref(name),
+            VarBinding::Tuple(names) => names,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ConstDef {
     pub name: String,
     pub initial_value: ConstInitialValue,
+    /// The `///` comments before the definition, one line each.
+    pub doc: Option<String>,
     pub span: Span,
 }
 
@@ -44,9 +62,20 @@ pub struct FunctionDef {
     pub span: Span,
 }
 
+impl FunctionDef {
+    pub fn is_generic(&self) -> bool {
+        self.params.iter().any(|param| param.comptime)
+    }
+
+    pub fn runtime_params(&self) -> impl Iterator<Item = &Param> {
+        self.params.iter().filter(|param| !param.comptime)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Param {
     pub name: String,
     pub param_type: ExpId,
+    pub comptime: bool,
     pub span: Span,
 }
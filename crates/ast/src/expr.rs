@@ -33,13 +33,17 @@ pub enum Exp {
     Exp(ExpId, Span),
     Type(Type),
     Number(Number),
+    Float(Float),
     Var(Var),
-    Str(String, Span),
+    Str(Vec<u8>, Span),
+    ByteStr(Vec<u8>, Span),
+    Bool(bool, Span),
     Unary(UnaryOp, ExpId, Span),
     Binary(BinaryOp, ExpId, ExpId, Span),
     GetAddr(GetAddr),
     Deref(Deref),
     Index(Index),
+    Slice(Slice),
     Array(Array),
     Call(Call),
     Block(Block),
@@ -54,9 +58,13 @@ pub enum Exp {
     Unit(Span),
     TypeCast(TypeCast),
     Structure(Structure),
+    Tuple(Tuple),
     FieldAccess(FieldAccess),
-    Break(Span),
-    Continue(Span),
+    Break(Break),
+    Continue(Continue),
+    Match(Match),
+    Variant(EnumVariant),
+    Builtin(BuiltinCall),
 }
 
 impl Exp {
@@ -65,13 +73,16 @@ impl Exp {
             Self::Exp(_, span) => *span,
             Self::Type(type_) => type_.span,
             Self::Number(number) => number.span,
+            Self::Float(float) => float.span,
             Self::Var(var) => var.span,
             Self::Unary(_, _, span) => *span,
             Self::Binary(_, _, _, span) => *span,
             Self::GetAddr(get_addr) => get_addr.span,
-            Self::Str(_, span) => *span,
+            Self::Str(_, span) | Self::ByteStr(_, span) => *span,
+            Self::Bool(_, span) => *span,
             Self::Deref(deref) => deref.span,
             Self::Index(index) => index.span,
+            Self::Slice(slice) => slice.span,
             Self::Array(array) => array.span(),
             Self::Call(call) => call.span,
             Self::Block(block) => block.span,
@@ -86,9 +97,13 @@ impl Exp {
             Self::ProtoDef(proto_def) => proto_def.span,
             Self::TypeCast(type_cast) => type_cast.span,
             Self::Structure(structure) => structure.span,
+            Self::Tuple(tuple) => tuple.span,
             Self::FieldAccess(field_access) => field_access.span,
-            Self::Break(span) => *span,
-            Self::Continue(span) => *span,
+            Self::Break(break_) => break_.span,
+            Self::Continue(continue_) => continue_.span,
+            Self::Match(match_) => match_.span,
+            Self::Variant(variant) => variant.span,
+            Self::Builtin(builtin) => builtin.span,
         }
     }
 }
@@ -120,6 +135,14 @@ pub struct Index {
     pub span: Span,
 }
 
+#[derive(Debug, Clone)]
+pub struct Slice {
+    pub exp: ExpId,
+    pub start: Option<ExpId>,
+    pub end: Option<ExpId>,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone)]
 pub struct Deref {
     pub exp: ExpId,
@@ -134,7 +157,15 @@ pub struct GetAddr {
 
 #[derive(Debug, Clone)]
 pub struct Number {
-    pub num: u64,
+    /// Negative patterns hold the `u64` their value wraps around to.
+    pub num: u128,
+    pub ty: Option<TypeEnum>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Float {
+    pub num: f64,
     pub ty: Option<TypeEnum>,
     pub span: Span,
 }
@@ -148,6 +179,9 @@ pub struct Path {
 #[derive(Debug, Clone)]
 pub struct Assign {
     pub lhs: ExpId,
+    /// The operator of a compound assignment such as `+=`, which
+    /// evaluates `lhs` only once.
+    pub op: Option<BinaryOp>,
     pub rhs: ExpId,
     pub span: Span,
 }
@@ -158,6 +192,19 @@ pub struct Return {
     pub span: Span,
 }
 
+#[derive(Debug, Clone)]
+pub struct Break {
+    pub label: Option<String>,
+    pub value: Option<ExpId>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Continue {
+    pub label: Option<String>,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone)]
 pub struct IfExp {
     pub condition: ExpId,
@@ -169,12 +216,14 @@ pub struct IfExp {
 
 #[derive(Debug, Clone)]
 pub struct Loop {
+    pub label: Option<String>,
     pub body: Block,
     pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct While {
+    pub label: Option<String>,
     pub condition: ExpId,
     pub body: Block,
     pub span: Span,
@@ -182,6 +231,7 @@ pub struct While {
 
 #[derive(Debug, Clone)]
 pub struct For {
+    pub label: Option<String>,
     pub var: String,
     pub start: ExpId,
     pub end: ExpId,
@@ -190,6 +240,47 @@ pub struct For {
     pub span: Span,
 }
 
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub exp: ExpId,
+    pub arms: Vec<MatchArm>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: ExpId,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// A number literal, negated if the `bool` is set.
+    Number(Number, bool),
+    Variant(Path, Option<String>, Span),
+    Wildcard(Span),
+}
+
+impl Pattern {
+    pub fn span(&self) -> Span {
+        match self {
+            Pattern::Number(number, _) => number.span,
+            Pattern::Variant(_, _, span) => *span,
+            Pattern::Wildcard(span) => *span,
+        }
+    }
+}
+
+/// A variant of the enum type `ty`, generated by the simplifier for every
+/// variant of an enum bound to a const.
+#[derive(Debug, Clone)]
+pub struct EnumVariant {
+    pub ty: ExpId,
+    pub name: String,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone)]
 pub struct Structure {
     pub ty: ExpId,
@@ -197,6 +288,35 @@ pub struct Structure {
     pub span: Span,
 }
 
+/// `(a, b)`. A tuple of types, such as `(i32, bool)`, is a tuple type, whose
+/// elements are accessed as the fields `0`, `1` and so on.
+#[derive(Debug, Clone)]
+pub struct Tuple {
+    pub elements: Vec<ExpId>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct BuiltinCall {
+    pub builtin: Builtin,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum Builtin {
+    SizeOf(ExpId),
+    AlignOf(ExpId),
+    OffsetOf(ExpId, String),
+}
+
+impl Builtin {
+    pub fn ty(&self) -> ExpId {
+        match self {
+            Builtin::SizeOf(ty) | Builtin::AlignOf(ty) | Builtin::OffsetOf(ty, _) => *ty,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FieldAccess {
     pub lhs: ExpId,
@@ -209,6 +329,7 @@ pub enum UnaryOp {
     Pos,
     Neg,
     Not,
+    BitNot,
     Ptr,
 }
 
@@ -230,6 +351,36 @@ pub enum BinaryOp {
     And,
     Or,
 
+    BitAnd,
+    BitOr,
+    BitXor,
+
     LShift,
     RShift,
 }
+
+impl std::fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "%",
+            BinaryOp::Lt => "<",
+            BinaryOp::Gt => ">",
+            BinaryOp::Le => "<=",
+            BinaryOp::Ge => ">=",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::BitOr => "|",
+            BinaryOp::BitXor => "^",
+            BinaryOp::LShift => "<<",
+            BinaryOp::RShift => ">>",
+        };
+        write!(f, "{}", op)
+    }
+}
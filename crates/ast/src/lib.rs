@@ -48,24 +48,37 @@ impl Hash for ExpId {
 pub struct ParseContext<'ctx> {
     file_table: &'ctx FileTable,
     exp_map: RefCell<HashMap<ExpId, Exp>>,
-    current_file: RefCell<usize>,
+    /// The files being parsed, each imported by the one before it. The
+    /// last is the current file.
+    files: RefCell<Vec<usize>>,
 }
 
 impl ParseContext<'_> {
     pub fn insert_exp(&self, exp: Exp) -> ExpId {
-        let id = ExpId(self.exp_map.borrow().len() as u64, self.span(0, 0));
+        let id = ExpId(self.exp_map.borrow().len() as u64, exp.span());
         self.exp_map.borrow_mut().insert(id, exp);
         id
     }
 
     pub fn find_module(&self, path: &str) -> Option<String> {
-        let current_path = self.file_table().get_path(*self.current_file.borrow())?;
+        let current_path = self.file_table().get_path(self.current_file())?;
         let path = std::path::Path::new(&current_path).parent()?.join(path);
         path.exists().then_some(path.to_string_lossy().into_owned())
     }
 
     pub fn span(&self, start: usize, end: usize) -> Span {
-        Span(start, end, *self.current_file.borrow())
+        Span(start, end, self.current_file())
+    }
+
+    fn current_file(&self) -> usize {
+        self.files.borrow().last().copied().unwrap_or_default()
+    }
+
+    /// The chain of imports from the first file parsed to the current one,
+    /// if `file` is in it, so that importing it again would be circular.
+    pub fn import_cycle(&self, file: usize) -> Option<Vec<usize>> {
+        let files = self.files.borrow();
+        files.contains(&file).then(|| files.clone())
     }
 }
 
@@ -74,7 +87,7 @@ impl<'ctx> ParseContext<'ctx> {
         ParseContext {
             file_table,
             exp_map: RefCell::new(HashMap::new()),
-            current_file: RefCell::new(0),
+            files: RefCell::new(Vec::new()),
         }
     }
 
@@ -85,7 +98,7 @@ impl<'ctx> ParseContext<'ctx> {
     pub fn parse<T: Parser>(self, parser: &T, file: usize) -> Result<AstContext, T::Error> {
         let content = self.file_table.read_source(file).unwrap();
 
-        self.current_file.replace(file);
+        self.files.borrow_mut().push(file);
         let root = parser.parse_content(&self, content)?;
         Ok(AstContext {
             exp_map: self.exp_map.take(),
@@ -96,10 +109,10 @@ impl<'ctx> ParseContext<'ctx> {
     pub fn parse_module<T: Parser>(&self, parser: &T, file: usize) -> Result<StructType, T::Error> {
         let content = self.file_table.read_source(file).unwrap();
 
-        let current_file = self.current_file.replace(file);
-        let root = parser.parse_content(self, content)?;
-        self.current_file.replace(current_file);
-        Ok(root)
+        self.files.borrow_mut().push(file);
+        let root = parser.parse_content(self, content);
+        self.files.borrow_mut().pop();
+        root
     }
 }
 
@@ -185,6 +198,27 @@ impl FileTable {
     pub fn get_path(&self, file: usize) -> Option<String> {
         self.files.borrow().get(&file).map(|(path, _)| path.clone())
     }
+
+    pub fn source_map(&self) -> SourceMap {
+        SourceMap {
+            files: self.files.borrow().clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SourceMap {
+    files: HashMap<usize, (String, Arc<String>)>,
+}
+
+impl SourceMap {
+    pub fn location(&self, span: Span) -> Option<String> {
+        let (path, source) = self.files.get(&span.file())?;
+        let before = source.get(..span.start())?;
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+        Some(format!("{}:{}:{}", path, line, column))
+    }
 }
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -8,11 +8,18 @@ pub enum TypeEnum {
     Unsigned(u32),
     Usize,
     Isize,
+    Float(u32),
+    Bool,
 
-    Array(ExpId, u32),
+    Slice(ExpId),
     Structure(StructType),
+    Enum(EnumType),
+    Interface(InterfaceType),
+    Dyn(ExpId),
+    SelfType,
 
     Unit,
+    Type,
 }
 
 #[derive(Debug, Clone)]
@@ -28,22 +35,111 @@ impl Display for Type {
             TypeEnum::Unsigned(bits) => write!(f, "u{}", bits),
             TypeEnum::Usize => write!(f, "usize"),
             TypeEnum::Isize => write!(f, "isize"),
-            TypeEnum::Array(inner, len) => write!(f, "[{:?}; {}]", inner, len),
-            TypeEnum::Structure(StructType { fields, .. }) => {
-                write!(f, "{{")?;
+            TypeEnum::Float(bits) => write!(f, "f{}", bits),
+            TypeEnum::Bool => write!(f, "bool"),
+            TypeEnum::Slice(inner) => write!(f, "[]{:?}", inner),
+            TypeEnum::Structure(StructType { fields, layout, .. }) => {
+                write!(f, "{}{{", layout)?;
                 for (name, ty) in fields {
                     write!(f, "{}: {:?}, ", name, ty)?;
                 }
                 write!(f, "}}")
             }
+            TypeEnum::Enum(EnumType { variants, .. }) => {
+                write!(f, "enum {{")?;
+                for Variant { name, payload, .. } in variants {
+                    match payload {
+                        Some(ty) => write!(f, "{}: {:?}, ", name, ty)?,
+                        None => write!(f, "{}, ", name)?,
+                    }
+                }
+                write!(f, "}}")
+            }
+            TypeEnum::Interface(InterfaceType { name, .. }) => {
+                write!(f, "interface {}", name.as_deref().unwrap_or_default())
+            }
+            TypeEnum::Dyn(interface) => write!(f, "dyn {:?}", interface),
+            TypeEnum::SelfType => write!(f, "Self"),
             TypeEnum::Unit => write!(f, "()"),
+            TypeEnum::Type => write!(f, "type"),
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct StructType {
-    pub fields: HashMap<String, ExpId>,
+    pub fields: Vec<(String, ExpId)>,
+    /// The `///` comments of the fields that have them.
+    pub field_docs: HashMap<String, String>,
     pub members: Vec<GlobalItem>,
+    /// The path the simplifier moves `members` to, so `value.method()` can
+    /// find the methods of the structure.
+    pub namespace: Option<String>,
+    pub implements: Vec<ExpId>,
+    pub layout: StructLayout,
+    /// The `//!` comments at the start of the body, or of the module file.
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+/// How the fields of a structure are placed in memory. The default is the C
+/// layout: every field is aligned to its natural alignment.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct StructLayout {
+    pub packed: bool,
+    pub align: Option<u64>,
+}
+
+impl StructLayout {
+    /// Places fields of the given sizes and alignments in order, giving their
+    /// offsets and the size and alignment of the structure. Both the layout
+    /// builtins and `codegen_llvm` use it.
+    pub fn place(&self, fields: impl IntoIterator<Item = (u64, u64)>) -> (Vec<u64>, u64, u64) {
+        let mut offsets = Vec::new();
+        let mut offset = 0u64;
+        let mut align = 1;
+        for (field_size, field_align) in fields {
+            let field_align = if self.packed { 1 } else { field_align };
+            offset = offset.next_multiple_of(field_align);
+            offsets.push(offset);
+            offset += field_size;
+            align = align.max(field_align);
+        }
+        let align = align.max(self.align.unwrap_or(1));
+        (offsets, offset.next_multiple_of(align), align)
+    }
+}
+
+impl Display for StructLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.packed {
+            write!(f, "packed ")?;
+        }
+        if let Some(align) = self.align {
+            write!(f, "align({}) ", align)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InterfaceType {
+    pub methods: Vec<(String, ExpId)>,
+    /// The name of the const the interface is bound to, set by the
+    /// simplifier, which tells interfaces apart.
+    pub name: Option<String>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnumType {
+    pub variants: Vec<Variant>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Variant {
+    pub name: String,
+    pub payload: Option<ExpId>,
     pub span: Span,
 }
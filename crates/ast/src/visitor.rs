@@ -23,6 +23,9 @@ pub trait ExpVisitor<V>: StatementVisitor<V> {
         let exp = actx.exp(exp);
         match exp {
             Exp::Array(array) => self.visit_array(array),
+            Exp::Binary(op @ (BinaryOp::And | BinaryOp::Or), lhs, rhs, span) => {
+                self.visit_logical(op, *lhs, *rhs, span)
+            }
             Exp::Binary(op, lhs, rhs, span) => {
                 let lhs = self.visit_right_value(*lhs);
                 let rhs = self.visit_right_value(*rhs);
@@ -34,9 +37,16 @@ pub trait ExpVisitor<V>: StatementVisitor<V> {
             Exp::Exp(exp, _) => self.visit_left_value(*exp),
             Exp::GetAddr(get_addr) => self.visit_get_addr(get_addr),
             Exp::Index(index) => self.visit_index(index),
+            Exp::Slice(slice) => self.visit_slice(slice),
             Exp::Var(var) => self.visit_var(var),
             Exp::Number(number) => self.visit_number(number),
+            Exp::Float(float) => self.visit_float(float),
             Exp::Str(string, _) => self.visit_str(string),
+            Exp::ByteStr(bytes, _) => self.visit_byte_str(bytes),
+            Exp::Bool(value, _) => self.visit_bool(*value),
+            Exp::Unary(UnaryOp::Neg, value, span) if let Exp::Number(number) = actx.exp(*value) => {
+                self.visit_negative_number(number, span)
+            }
             Exp::Unary(op, value, span) => {
                 let value = self.visit_right_value(*value);
                 self.visit_unary(op, value, span)
@@ -52,10 +62,14 @@ pub trait ExpVisitor<V>: StatementVisitor<V> {
             Exp::While(while_) => self.visit_while(while_),
             Exp::TypeCast(type_cast) => self.visit_type_cast(type_cast),
             Exp::Structure(structure) => self.visit_structure(structure),
+            Exp::Tuple(tuple) => self.visit_tuple(tuple),
             Exp::FieldAccess(field_access) => self.visit_field_access(field_access),
             Exp::Type(type_) => self.visit_type(type_),
-            Exp::Break(span) => self.visit_break(*span),
-            Exp::Continue(span) => self.visit_continue(*span),
+            Exp::Break(break_) => self.visit_break(break_),
+            Exp::Continue(continue_) => self.visit_continue(continue_),
+            Exp::Match(match_) => self.visit_match(match_),
+            Exp::Variant(variant) => self.visit_variant(variant),
+            Exp::Builtin(builtin) => self.visit_builtin(builtin),
         }
     }
 
@@ -69,35 +83,60 @@ pub trait ExpVisitor<V>: StatementVisitor<V> {
         self.pass_left_value_as_right_value(exp)
     }
 
+    /// Visits `&&` and `||`. Both sides are evaluated unless overridden.
+    fn visit_logical(&mut self, op: &BinaryOp, lhs: ExpId, rhs: ExpId, span: &Span) -> V {
+        let lhs = self.visit_right_value(lhs);
+        let rhs = self.visit_right_value(rhs);
+        self.visit_binary(op, lhs, rhs, span)
+    }
+
+    /// Visits `-number`, the literal negated at `span`.
+    fn visit_negative_number(&mut self, number: &Number, span: &Span) -> V {
+        let value = self.visit_number(number);
+        self.visit_unary(&UnaryOp::Neg, value, span)
+    }
+
+    /// Visits `b"..."`, which is a string of `u8`s.
+    fn visit_byte_str(&mut self, bytes: &[u8]) -> V {
+        self.visit_str(bytes)
+    }
+
     fn visit_array(&mut self, array: &Array) -> V;
     fn visit_binary(&mut self, op: &BinaryOp, lhs: V, rhs: V, span: &Span) -> V;
     fn visit_unary(&mut self, op: &UnaryOp, value: V, span: &Span) -> V;
     fn visit_call(&mut self, call: &Call) -> V;
     fn visit_deref(&mut self, deref: &Deref) -> V;
     fn visit_index(&mut self, index: &Index) -> V;
+    fn visit_slice(&mut self, slice: &Slice) -> V;
     fn visit_var(&mut self, var: &Var) -> V;
     fn visit_number(&mut self, number: &Number) -> V;
-    fn visit_str(&mut self, string: &str) -> V;
+    fn visit_float(&mut self, float: &Float) -> V;
+    fn visit_str(&mut self, string: &[u8]) -> V;
+    fn visit_bool(&mut self, value: bool) -> V;
     fn visit_block(&mut self, block: &Block) -> V;
     fn visit_proto(&mut self, proto_def: &ProtoDef) -> V;
     fn visit_function(&mut self, func: &FunctionDef) -> V;
     fn visit_unit(&mut self) -> V;
     fn visit_type_cast(&mut self, type_cast: &TypeCast) -> V;
     fn visit_structure(&mut self, structure: &Structure) -> V;
+    fn visit_tuple(&mut self, tuple: &Tuple) -> V;
     fn visit_field_access(&mut self, field_access: &FieldAccess) -> V;
     fn visit_type(&mut self, type_: &Type) -> V;
+    fn visit_variant(&mut self, variant: &EnumVariant) -> V;
+    fn visit_builtin(&mut self, builtin: &BuiltinCall) -> V;
 }
 
 pub trait StatementVisitor<V> {
     fn visit_assign(&mut self, assign: &Assign) -> V;
     fn visit_if_exp(&mut self, if_exp: &IfExp) -> V;
+    fn visit_match(&mut self, match_: &Match) -> V;
     /// If this returns `Some`, the function returns the value.
     fn visit_return(&mut self, return_stmt: &Return) -> V;
     fn visit_for(&mut self, for_: &For) -> V;
     fn visit_loop(&mut self, loop_: &Loop) -> V;
     fn visit_while(&mut self, while_: &While) -> V;
-    fn visit_break(&mut self, span: Span) -> V;
-    fn visit_continue(&mut self, span: Span) -> V;
+    fn visit_break(&mut self, break_: &Break) -> V;
+    fn visit_continue(&mut self, continue_: &Continue) -> V;
 }
 
 pub trait BlockVisitor<V>: ExpVisitor<V> {
@@ -113,6 +152,7 @@ pub trait BlockVisitor<V>: ExpVisitor<V> {
                     None
                 }
             } {
+                self.on_leave_block();
                 return Some(return_value);
             }
         }
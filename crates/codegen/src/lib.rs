@@ -41,6 +41,9 @@ pub struct BackendOptions {
     pub code_model: CodeModel,
     pub optimize_level: OptimizeLevel,
     pub reloc_mode: RelocMode,
+    /// Checks indexing and slicing against the length at runtime.
+    #[builder(default)]
+    pub debug_assertions: bool,
 }
 
 #[derive(Builder)]
@@ -0,0 +1,90 @@
+use ast::{ExpId, visitor::ExpVisitor};
+use inkwell::{IntPredicate, llvm_sys::core::LLVMIsNull, values::AsValueRef};
+
+use crate::{
+    LLVM_CONTEXT, VisitorCtx,
+    info::{TypeKind, Value},
+};
+
+impl<'v> VisitorCtx<'v> {
+    /// Lowers `[value; len]`, which is an array type if `value` is a type. A
+    /// zero `value` gives a zero constant, other constant bytes are filled
+    /// with a memset, and anything else is stored element by element.
+    pub(crate) fn build_array_template(&mut self, value: ExpId, len: ExpId) -> Value<'v> {
+        let value = self.visit_right_value(value);
+        let len = const_eval::eval_exp(self.queries.clone(), len, &self.bindings).as_int() as u32;
+        if let Value::Type(element) = value {
+            return Value::Type(element.new_array(len));
+        }
+        if value.is_unit() {
+            return Value::Unit;
+        }
+
+        let element = value.type_();
+        let ty = element.new_array(len);
+        if unsafe { LLVMIsNull(value.as_value_ref()) } == 1 {
+            return Value::Array {
+                value: ty.as_array_type().const_zero(),
+                ty,
+            };
+        }
+
+        let pointer_width = self.queries.target().pointer_width();
+        let usize_ty = TypeKind::new_int(pointer_width, false);
+        let array = self.create_entry_bb_alloca("", ty.clone());
+        if let Value::Int { value: byte, .. } = &value
+            && byte.get_type().get_bit_width() == 8
+            && byte.is_const()
+        {
+            let size = usize_ty.const_int(len as i128).as_int();
+            self.builder
+                .build_memset(array.as_ptr(), 1, *byte, size)
+                .unwrap();
+            return array;
+        }
+
+        let current_fn = self.current_fn.as_fn();
+        let condition_block = LLVM_CONTEXT.append_basic_block(current_fn, "fill_condition");
+        let fill_block = LLVM_CONTEXT.append_basic_block(current_fn, "fill");
+        let end_block = LLVM_CONTEXT.append_basic_block(current_fn, "fill_end");
+
+        let counter = self.create_entry_bb_alloca_with_init("", usize_ty.const_int(0));
+        self.build_branch(condition_block);
+
+        self.builder.position_at_end(condition_block);
+        let index = counter.as_right_value(&self.builder).as_int();
+        let condition = self
+            .builder
+            .build_int_compare(
+                IntPredicate::ULT,
+                index,
+                usize_ty.const_int(len as i128).as_int(),
+                "",
+            )
+            .unwrap();
+        self.build_conditional_branch(condition, fill_block, end_block);
+
+        self.builder.position_at_end(fill_block);
+        let slot = unsafe {
+            self.builder
+                .build_gep(element.clone(), array.as_ptr(), &[index], "")
+                .unwrap()
+        };
+        value.build_store(
+            Value::Alloca {
+                value: slot,
+                value_ty: element,
+            },
+            &self.builder,
+        );
+        let next = self
+            .builder
+            .build_int_add(index, usize_ty.const_int(1).as_int(), "")
+            .unwrap();
+        self.builder.build_store(counter.as_ptr(), next).unwrap();
+        self.build_branch(condition_block);
+
+        self.builder.position_at_end(end_block);
+        array
+    }
+}
@@ -1,12 +1,14 @@
+use std::iter::zip;
+
 use const_eval::queries::CONST_EVAL_PROVIDER;
 use inkwell::{
-    IntPredicate,
+    FloatPredicate, IntPredicate,
     module::Linkage,
     values::{AnyValue, BasicValue, InstructionOpcode},
 };
 
-use ast::{Array, BinaryOp, Call, Span, TypeEnum, Var, visitor::ExpVisitor};
-use monomorphize::CodegenItem;
+use ast::{Array, BinaryOp, Call, Exp, ExpId, Span, TypeEnum, Var, visitor::ExpVisitor};
+use monomorphize::{CodegenItem, callee_function, generic_callee};
 use uuid::Uuid;
 
 use crate::{
@@ -44,29 +46,63 @@ impl<'v> ExpVisitor<Value<'v>> for VisitorCtx<'v> {
         let rhs = rhs_.as_basic_value_enum();
 
         let builder = &self.builder;
+        if lhs_.is_float() {
+            let op_code = match op {
+                BinaryOp::Add => InstructionOpcode::FAdd,
+                BinaryOp::Sub => InstructionOpcode::FSub,
+                BinaryOp::Mul => InstructionOpcode::FMul,
+                BinaryOp::Div => InstructionOpcode::FDiv,
+                BinaryOp::Mod => InstructionOpcode::FRem,
+                _ => {
+                    let cmp = match op {
+                        BinaryOp::Lt => FloatPredicate::OLT,
+                        BinaryOp::Le => FloatPredicate::OLE,
+                        BinaryOp::Gt => FloatPredicate::OGT,
+                        BinaryOp::Ge => FloatPredicate::OGE,
+                        BinaryOp::Eq => FloatPredicate::OEQ,
+                        BinaryOp::Ne => FloatPredicate::UNE,
+                        _ => unreachable!(),
+                    };
+                    return Value::new_bool(
+                        builder
+                            .build_float_compare(
+                                cmp,
+                                lhs.into_float_value(),
+                                rhs.into_float_value(),
+                                "",
+                            )
+                            .unwrap(),
+                    );
+                }
+            };
+            let result = builder.build_binop(op_code, lhs, rhs, "").unwrap();
+            return Value::new_from(result.as_any_value_enum(), lhs_.type_());
+        }
+
+        let signed = lhs_.is_signed();
         let op_code = match op {
             BinaryOp::Add => InstructionOpcode::Add,
             BinaryOp::Sub => InstructionOpcode::Sub,
             BinaryOp::Mul => InstructionOpcode::Mul,
+            BinaryOp::Div if signed => InstructionOpcode::SDiv,
             BinaryOp::Div => InstructionOpcode::UDiv,
+            BinaryOp::Mod if signed => InstructionOpcode::SRem,
             BinaryOp::Mod => InstructionOpcode::URem,
-            BinaryOp::And => InstructionOpcode::And,
-            BinaryOp::Or => InstructionOpcode::Or,
+            BinaryOp::BitAnd => InstructionOpcode::And,
+            BinaryOp::BitOr => InstructionOpcode::Or,
+            BinaryOp::BitXor => InstructionOpcode::Xor,
             BinaryOp::LShift => InstructionOpcode::Shl,
+            BinaryOp::RShift if signed => InstructionOpcode::AShr,
             BinaryOp::RShift => InstructionOpcode::LShr,
             _ => {
-                let cmp = match op {
-                    BinaryOp::Lt => IntPredicate::SLT,
-                    BinaryOp::Le => IntPredicate::SLE,
-                    BinaryOp::Gt => IntPredicate::SGT,
-                    BinaryOp::Ge => IntPredicate::SGE,
-                    BinaryOp::Eq => IntPredicate::EQ,
-                    BinaryOp::Ne => IntPredicate::NE,
-                    _ => unreachable!(),
-                };
-                return Value::Int(
+                return Value::new_bool(
                     builder
-                        .build_int_compare(cmp, lhs.into_int_value(), rhs.into_int_value(), "")
+                        .build_int_compare(
+                            int_predicate(op, signed),
+                            lhs.into_int_value(),
+                            rhs.into_int_value(),
+                            "",
+                        )
                         .unwrap(),
                 );
             }
@@ -77,6 +113,44 @@ impl<'v> ExpVisitor<Value<'v>> for VisitorCtx<'v> {
         Value::new_from(result.as_any_value_enum(), lhs_.type_())
     }
 
+    fn visit_logical(&mut self, op: &BinaryOp, lhs: ExpId, rhs: ExpId, _: &Span) -> Value<'v> {
+        let lhs = self.visit_right_value(lhs).as_int();
+        let lhs_block = self.builder.get_insert_block().unwrap();
+
+        let current_fn = self.current_fn.as_fn();
+        let rhs_block = LLVM_CONTEXT.append_basic_block(current_fn, "rhs");
+        let end_block = LLVM_CONTEXT.append_basic_block(current_fn, "end");
+
+        // The right side only runs if the left one doesn't decide the result.
+        let short_circuit = match op {
+            BinaryOp::And => {
+                self.build_conditional_branch(lhs, rhs_block, end_block);
+                false
+            }
+            BinaryOp::Or => {
+                self.build_conditional_branch(lhs, end_block, rhs_block);
+                true
+            }
+            _ => unreachable!(),
+        };
+
+        self.builder.position_at_end(rhs_block);
+        let rhs = self.visit_right_value(rhs).as_int();
+        let rhs_block = self.builder.get_insert_block().unwrap();
+        self.build_branch(end_block);
+
+        self.builder.position_at_end(end_block);
+        let phi = self
+            .builder
+            .build_phi(LLVM_CONTEXT.bool_type(), "logical_result")
+            .unwrap();
+        let short_circuit = LLVM_CONTEXT
+            .bool_type()
+            .const_int(short_circuit as u64, false);
+        phi.add_incoming(&[(&short_circuit, lhs_block), (&rhs, rhs_block)]);
+        Value::new_bool(phi.as_basic_value().into_int_value())
+    }
+
     fn visit_block(&mut self, block: &ast::Block) -> Value<'v> {
         use ast::visitor::BlockVisitor;
         <Self as BlockVisitor<Value<'v>>>::visit_block(self, block).unwrap_or(Value::Unit)
@@ -87,42 +161,65 @@ impl<'v> ExpVisitor<Value<'v>> for VisitorCtx<'v> {
     }
 
     fn visit_index(&mut self, index_node: &ast::Index) -> Value<'v> {
-        let ptr_value = self.visit_left_value(index_node.exp);
+        let container = self.visit_left_value(index_node.exp);
+        let (ptr, element, len) = self.elements(container);
         let index = self.visit_right_value(index_node.index);
-
-        let pointee_ty = ptr_value.type_().derefed().derefed();
-        let ptr = ptr_value.as_basic_value_enum().into_pointer_value();
+        let usize_ty = LLVM_CONTEXT.custom_width_int_type(self.queries.target().pointer_width());
+        let index = self
+            .builder
+            .build_int_cast_sign_flag(index.as_int(), usize_ty, index.is_signed(), "")
+            .unwrap();
+        self.build_index_check(index, len, index_node.span);
 
         let ptr = unsafe {
             self.builder
-                .build_gep(pointee_ty.clone(), ptr, &[index.as_int()], "")
+                .build_gep(element.clone(), ptr, &[index], "")
                 .unwrap()
         };
         Value::Alloca {
             value: ptr,
-            value_ty: pointee_ty,
+            value_ty: element,
         }
     }
 
+    fn visit_slice(&mut self, slice: &ast::Slice) -> Value<'v> {
+        self.build_slice(slice)
+    }
+
     fn visit_number(&mut self, number: &ast::Number) -> Value<'v> {
-        let ty = if let Some(ty) = &number.ty {
-            match ty {
-                TypeEnum::Signed(width) | TypeEnum::Unsigned(width) => {
-                    LLVM_CONTEXT.custom_width_int_type(*width)
-                }
-                TypeEnum::Isize | TypeEnum::Usize => {
-                    LLVM_CONTEXT.custom_width_int_type(self.queries.target().pointer_width())
-                }
-                _ => unreachable!(),
-            }
-        } else {
-            LLVM_CONTEXT.i32_type()
+        let pointer_width = self.queries.target().pointer_width();
+        let inferred = number.ty.clone().or_else(|| {
+            self.queries
+                .literal_types()
+                .get(number.span, &const_eval::literal_scope(&self.bindings))
+        });
+        let ty = match &inferred {
+            Some(TypeEnum::Signed(width)) => TypeKind::new_int(*width, true),
+            Some(TypeEnum::Unsigned(width)) => TypeKind::new_int(*width, false),
+            Some(TypeEnum::Isize) => TypeKind::new_int(pointer_width, true),
+            Some(TypeEnum::Usize) => TypeKind::new_int(pointer_width, false),
+            Some(_) => unreachable!(),
+            None => TypeKind::new_int(32, true),
         };
-        Value::Int(ty.const_int(number.num, true))
+        ty.const_int(number.num as i128)
     }
 
-    fn visit_str(&mut self, string: &str) -> Value<'v> {
-        let string = LLVM_CONTEXT.const_string(string.as_bytes(), true);
+    fn visit_float(&mut self, float: &ast::Float) -> Value<'v> {
+        let ty = match &float.ty {
+            Some(TypeEnum::Float(width)) => TypeKind::new_float(*width),
+            Some(_) => unreachable!(),
+            None => TypeKind::new_float(64),
+        };
+        Value::Float(ty.as_float_type().const_float(float.num))
+    }
+
+    fn visit_bool(&mut self, value: bool) -> Value<'v> {
+        Value::new_bool(LLVM_CONTEXT.bool_type().const_int(value as u64, false))
+    }
+
+    fn visit_str(&mut self, string: &[u8]) -> Value<'v> {
+        // Strings are passed to C as they are, so they end with a NUL.
+        let string = LLVM_CONTEXT.const_string(string, true);
         let global = self.module.add_global(
             string.get_type(),
             None,
@@ -135,7 +232,15 @@ impl<'v> ExpVisitor<Value<'v>> for VisitorCtx<'v> {
         global.set_alignment(1);
         Value::Pointer {
             value: global.as_pointer_value(),
-            ty: TypeKind::new_int(8).new_ptr(),
+            ty: TypeKind::new_int(8, true).new_ptr(),
+        }
+    }
+
+    fn visit_byte_str(&mut self, bytes: &[u8]) -> Value<'v> {
+        let value = self.visit_str(bytes).as_ptr();
+        Value::Pointer {
+            value,
+            ty: TypeKind::new_int(8, false).new_ptr(),
         }
     }
 
@@ -143,23 +248,72 @@ impl<'v> ExpVisitor<Value<'v>> for VisitorCtx<'v> {
         if op == &ast::UnaryOp::Ptr {
             return Value::Type(value.as_type().new_ptr());
         }
+        if value.is_float() {
+            let value = value.as_float();
+            return Value::Float(match op {
+                ast::UnaryOp::Neg => self.builder.build_float_neg(value, "").unwrap(),
+                ast::UnaryOp::Pos => value,
+                _ => unreachable!(),
+            });
+        }
+        let signed = value.is_signed();
         let value = value.as_int();
-        Value::Int(match op {
-            ast::UnaryOp::Neg => self.builder.build_int_neg(value, "").unwrap(),
-            ast::UnaryOp::Pos => value,
-            ast::UnaryOp::Not => self.builder.build_not(value, "").unwrap(),
-            _ => unreachable!(),
-        })
+        Value::Int {
+            value: match op {
+                ast::UnaryOp::Neg => self.builder.build_int_neg(value, "").unwrap(),
+                ast::UnaryOp::Pos => value,
+                ast::UnaryOp::Not | ast::UnaryOp::BitNot => {
+                    self.builder.build_not(value, "").unwrap()
+                }
+                _ => unreachable!(),
+            },
+            signed,
+        }
     }
 
     fn visit_call(&mut self, call: &Call) -> Value<'v> {
-        let Value::Function(func, ret_ty) = self.visit_right_value(call.func) else {
+        if let Exp::FieldAccess(field_access) = self.queries.ast_ctx().exp(call.func) {
+            return self.build_method_call(field_access, call);
+        }
+
+        if let Some(func) = callee_function(&self.queries, call.func)
+            && const_eval::is_type_function(&self.queries, &func)
+        {
+            let ty = const_eval::eval_call(self.queries.clone(), call, &self.bindings).as_type();
+            return Value::Type(const_eval_type_to_llvm_type(self.queries.clone(), &ty));
+        }
+
+        if let Some(func) = generic_callee(&self.queries, call.func) {
+            let item = CodegenItem::instantiate(
+                self.queries.clone(),
+                func.clone(),
+                &call.args,
+                &self.bindings,
+            );
+            let Value::Function(func_value, ret_ty) = self.global_funcs[&item].clone() else {
+                unreachable!()
+            };
+            let runtime_args = zip(&func.params, &call.args)
+                .filter(|(param, _)| !param.comptime)
+                .map(|(_, arg)| *arg);
+            let args = zip(runtime_args, func_value.get_type().get_param_types())
+                .map(|(arg, param_ty)| self.visit_arg(arg, param_ty).into())
+                .collect::<Vec<_>>();
+            let result = self.builder.build_call(func_value, &args, "").unwrap();
+
+            return Value::new_from(result.as_any_value_enum(), ret_ty);
+        }
+
+        let func = self.visit_right_value(call.func);
+        if let Value::EnumCtor(ty, tag) = func {
+            let payload = self.visit_right_value(call.args[0]);
+            return self.build_enum(ty, tag, Some(payload));
+        }
+        let Value::Function(func, ret_ty) = func else {
             unreachable!()
         };
-        let args = call
-            .args
-            .iter()
-            .map(|arg| self.visit_right_value(*arg).into())
+        let args = zip(&call.args, func.get_type().get_param_types())
+            .map(|(arg, param_ty)| self.visit_arg(*arg, param_ty).into())
             .collect::<Vec<_>>();
         let result = self.builder.build_call(func, &args, "").unwrap();
 
@@ -177,9 +331,7 @@ impl<'v> ExpVisitor<Value<'v>> for VisitorCtx<'v> {
 
                 ty.const_array(&values)
             }
-            Array::Template(_, _, _) => {
-                unimplemented!()
-            }
+            Array::Template(value, len, _) => self.build_array_template(*value, len.exp),
         }
     }
 
@@ -214,22 +366,66 @@ impl<'v> ExpVisitor<Value<'v>> for VisitorCtx<'v> {
         let target_ty = self.visit_right_value(type_cast.ty).as_type();
 
         if value.is_int() && target_ty.is_int() {
-            Value::Int(
+            // Widening extends the sign of the source, so `bool` and unsigned
+            // values are zero-extended.
+            Value::Int {
+                value: self
+                    .builder
+                    .build_int_cast_sign_flag(
+                        value.as_int(),
+                        target_ty.as_int_type(),
+                        value.is_signed(),
+                        "",
+                    )
+                    .unwrap(),
+                signed: target_ty.is_signed(),
+            }
+        } else if value.is_int() && target_ty.is_float() {
+            let float_ty = target_ty.as_float_type();
+            Value::Float(if value.is_signed() {
+                self.builder
+                    .build_signed_int_to_float(value.as_int(), float_ty, "")
+                    .unwrap()
+            } else {
                 self.builder
-                    .build_int_cast(value.as_int(), target_ty.as_int_type(), "")
+                    .build_unsigned_int_to_float(value.as_int(), float_ty, "")
+                    .unwrap()
+            })
+        } else if value.is_float() && target_ty.is_int() {
+            let int_ty = target_ty.as_int_type();
+            Value::Int {
+                value: if target_ty.is_signed() {
+                    self.builder
+                        .build_float_to_signed_int(value.as_float(), int_ty, "")
+                        .unwrap()
+                } else {
+                    self.builder
+                        .build_float_to_unsigned_int(value.as_float(), int_ty, "")
+                        .unwrap()
+                },
+                signed: target_ty.is_signed(),
+            }
+        } else if value.is_float() && target_ty.is_float() {
+            Value::Float(
+                self.builder
+                    .build_float_cast(value.as_float(), target_ty.as_float_type(), "")
                     .unwrap(),
             )
+        } else if target_ty.is_dyn() {
+            self.build_dyn(value, target_ty)
         } else if value.is_ptr() && target_ty.is_ptr() {
             Value::Pointer {
                 value: value.as_ptr(),
                 ty: target_ty,
             }
         } else if value.is_ptr() && target_ty.is_int() {
-            Value::Int(
-                self.builder
+            Value::Int {
+                value: self
+                    .builder
                     .build_ptr_to_int(value.as_ptr(), target_ty.as_int_type(), "")
                     .unwrap(),
-            )
+                signed: target_ty.is_signed(),
+            }
         } else if value.is_int() && target_ty.is_ptr() {
             Value::Pointer {
                 value: self
@@ -248,68 +444,112 @@ impl<'v> ExpVisitor<Value<'v>> for VisitorCtx<'v> {
     }
 
     fn visit_field_access(&mut self, field_access: &ast::FieldAccess) -> Value<'v> {
-        let value = self.visit_left_value(field_access.lhs);
+        let value = match self.visit_left_value(field_access.lhs) {
+            value @ (Value::Alloca { .. } | Value::Pointer { .. }) => value,
+            value => self.create_entry_bb_alloca_with_init("", value),
+        };
         let value_ty = value.type_().derefed();
-        let value = value.as_ptr();
-
-        let TypeKind::Structure {
-            field_ids,
-            field_types,
-            ..
-        } = value_ty
-        else {
+        if let TypeKind::Slice { ty, .. } = &value_ty {
+            let len_ptr = self
+                .builder
+                .build_struct_gep(*ty, value.as_ptr(), 1, "")
+                .unwrap();
+            return Value::Alloca {
+                value: len_ptr,
+                value_ty: TypeKind::new_int(self.queries.target().pointer_width(), false),
+            };
+        }
+        let Some(field) = value_ty.field(&field_access.field) else {
             panic!("Invalid type {:?}", value_ty)
         };
 
-        let field_id = *field_ids
-            .iter()
-            .find(|(_, name)| *name == &field_access.field)
-            .unwrap()
-            .0;
-        let field_type = field_types[field_id].clone();
-
-        let field_ptr = unsafe {
-            self.builder.build_in_bounds_gep(
-                field_type.clone(),
-                value,
-                &[TypeKind::new_int(64).const_int(field_id as i64).as_int()],
-                "",
-            )
-        }
-        .unwrap();
+        let field_ptr = self
+            .builder
+            .build_struct_gep(value_ty.clone(), value.as_ptr(), field.index, "")
+            .unwrap();
 
         Value::Alloca {
             value: field_ptr,
-            value_ty: field_type.clone(),
+            value_ty: field.ty.clone(),
         }
     }
 
     fn visit_structure(&mut self, structure: &ast::Structure) -> Value<'v> {
         let ty = self.visit_right_value(structure.ty).as_type();
-        let TypeKind::Structure { field_ids, .. } = &ty else {
+        let TypeKind::Structure { fields, .. } = &ty else {
             unreachable!()
         };
 
-        let mut field_values = Vec::new();
-        for id in 0..field_ids.len() {
-            let name = field_ids[&id].clone();
-            let field_value = self.visit_right_value(structure.fields[&name]);
-            field_values.push(field_value);
-        }
+        let field_values = fields
+            .iter()
+            .map(|field| self.visit_arg(structure.fields[&field.name], field.ty.clone().into()))
+            .collect();
 
-        Value::Structure {
-            value: field_values,
-            ty,
+        self.build_struct(ty, field_values)
+    }
+
+    fn visit_tuple(&mut self, tuple: &ast::Tuple) -> Value<'v> {
+        let pointer_width = self.queries.target().pointer_width();
+        let elements = tuple
+            .elements
+            .iter()
+            .map(|element| self.visit_right_value(*element))
+            .collect::<Vec<_>>();
+        if elements
+            .iter()
+            .all(|element| matches!(element, Value::Type(_)))
+        {
+            let elements = elements.iter().map(Value::as_type).collect();
+            return Value::Type(TypeKind::new_tuple(elements, pointer_width));
         }
+        let ty = TypeKind::new_tuple(elements.iter().map(Value::type_).collect(), pointer_width);
+        self.build_struct(ty, elements)
+    }
+
+    fn visit_builtin(&mut self, builtin: &ast::BuiltinCall) -> Value<'v> {
+        let ty = self.visit_right_value(builtin.builtin.ty()).as_type();
+        let pointer_width = self.queries.target().pointer_width();
+        let value = match &builtin.builtin {
+            ast::Builtin::SizeOf(_) => ty.size_of(pointer_width),
+            ast::Builtin::AlignOf(_) => ty.align_of(pointer_width),
+            ast::Builtin::OffsetOf(_, field) => ty.field(field).unwrap().offset,
+        };
+        TypeKind::new_int(pointer_width, false).const_int(value as i128)
     }
 
     fn visit_type(&mut self, type_: &ast::Type) -> Value<'v> {
+        // The element may name a `comptime` parameter, which only the
+        // visitor's symbols know about.
+        if let TypeEnum::Slice(element) = &type_.kind {
+            let pointer_width = self.queries.target().pointer_width();
+            return Value::Type(
+                self.visit_right_value(*element)
+                    .as_type()
+                    .new_slice(pointer_width),
+            );
+        }
         Value::Type(get_llvm_type(self.queries.clone(), type_))
     }
+
+    fn visit_variant(&mut self, variant: &ast::EnumVariant) -> Value<'v> {
+        let ty = self.visit_right_value(variant.ty).as_type();
+        let TypeKind::Enum { variants, .. } = &ty else {
+            unreachable!()
+        };
+        let tag = variants
+            .iter()
+            .position(|(name, _)| *name == variant.name)
+            .unwrap();
+        if variants[tag].1.is_some() {
+            Value::EnumCtor(ty, tag)
+        } else {
+            self.build_enum(ty, tag, None)
+        }
+    }
 }
 
 impl<'v> VisitorCtx<'v> {
-    fn const_value_to_llvm_value(&mut self, value: &const_eval::Value) -> Value<'v> {
+    pub(crate) fn const_value_to_llvm_value(&mut self, value: &const_eval::Value) -> Value<'v> {
         match value.kind() {
             const_eval::ValueKind::Function(f) => self
                 .global_funcs
@@ -324,28 +564,130 @@ impl<'v> VisitorCtx<'v> {
             const_eval::ValueKind::Int(i) => {
                 const_eval_type_to_llvm_type(self.queries.clone(), &value.ty()).const_int(i)
             }
+            const_eval::ValueKind::Float(f) => {
+                let ty = const_eval_type_to_llvm_type(self.queries.clone(), &value.ty());
+                Value::Float(ty.as_float_type().const_float(f))
+            }
             const_eval::ValueKind::Unit => Value::Unit,
+            // Pointers to compile-time locals never end up in a const.
+            const_eval::ValueKind::Pointer(_) => unreachable!(),
+            const_eval::ValueKind::Enum(ty, tag, payload) => {
+                let ty = const_eval_type_to_llvm_type(self.queries.clone(), &ty);
+                let payload = payload.map(|payload| self.const_value_to_llvm_value(&payload));
+                self.build_enum(ty, tag, payload)
+            }
+            const_eval::ValueKind::EnumCtor(ty, tag) => {
+                Value::EnumCtor(const_eval_type_to_llvm_type(self.queries.clone(), &ty), tag)
+            }
             const_eval::ValueKind::Type(ty) => {
                 Value::Type(const_eval_type_to_llvm_type(self.queries.clone(), &ty))
             }
+            const_eval::ValueKind::Array(ty, elements) => {
+                let const_eval::TypeKind::Array(element, _) = ty.as_ref() else {
+                    unreachable!()
+                };
+                let element = const_eval_type_to_llvm_type(self.queries.clone(), element);
+                let elements = elements
+                    .iter()
+                    .map(|element| self.const_value_to_llvm_value(element))
+                    .collect::<Vec<_>>();
+                element.const_array(&elements)
+            }
             const_eval::ValueKind::Structure(ty, fields) => {
                 let ty = const_eval_type_to_llvm_type(self.queries.clone(), &ty);
-                let TypeKind::Structure { field_ids, .. } = &ty else {
+                let TypeKind::Structure {
+                    fields: field_types,
+                    ..
+                } = &ty
+                else {
                     unreachable!()
                 };
 
-                let mut field_values = Vec::new();
-                for id in 0..field_ids.len() {
-                    let name = field_ids.get(&id).unwrap();
-                    let value = self.const_value_to_llvm_value(fields.get(name).unwrap());
-                    field_values.push(value);
-                }
+                let field_values = field_types
+                    .iter()
+                    .map(|field| self.const_value_to_llvm_value(&fields[&field.name]))
+                    .collect();
 
-                Value::Structure {
-                    value: field_values,
-                    ty,
-                }
+                self.build_struct(ty, field_values)
             }
         }
     }
 }
+
+impl<'v> VisitorCtx<'v> {
+    fn build_struct(&self, ty: TypeKind<'v>, field_values: Vec<Value<'v>>) -> Value<'v> {
+        let TypeKind::Structure {
+            ty: struct_ty,
+            fields,
+            ..
+        } = &ty
+        else {
+            unreachable!()
+        };
+        let mut value = struct_ty.get_undef();
+        for (field, field_value) in fields.iter().zip(field_values) {
+            value = self
+                .builder
+                .build_insert_value(value, field_value, field.index, "")
+                .unwrap()
+                .into_struct_value();
+        }
+        Value::Structure { value, ty }
+    }
+
+    fn build_enum(&self, ty: TypeKind<'v>, tag: usize, payload: Option<Value<'v>>) -> Value<'v> {
+        let TypeKind::Enum {
+            ty: struct_ty,
+            variants,
+        } = &ty
+        else {
+            unreachable!()
+        };
+
+        let alloca = self.create_entry_bb_alloca("", ty.clone());
+        let ptr = alloca.as_ptr();
+
+        let tag_ptr = self
+            .builder
+            .build_struct_gep(*struct_ty, ptr, 0, "")
+            .unwrap();
+        self.builder
+            .build_store(
+                tag_ptr,
+                LLVM_CONTEXT.i32_type().const_int(tag as u64, false),
+            )
+            .unwrap();
+
+        if let Some(payload) = payload {
+            let payload_ptr = self
+                .builder
+                .build_struct_gep(*struct_ty, ptr, 1, "")
+                .unwrap();
+            payload.build_store(
+                Value::Alloca {
+                    value: payload_ptr,
+                    value_ty: variants[tag].1.clone().unwrap(),
+                },
+                &self.builder,
+            );
+        }
+
+        alloca.as_right_value(&self.builder)
+    }
+}
+
+pub(crate) fn int_predicate(op: &BinaryOp, signed: bool) -> IntPredicate {
+    match op {
+        BinaryOp::Lt if signed => IntPredicate::SLT,
+        BinaryOp::Lt => IntPredicate::ULT,
+        BinaryOp::Le if signed => IntPredicate::SLE,
+        BinaryOp::Le => IntPredicate::ULE,
+        BinaryOp::Gt if signed => IntPredicate::SGT,
+        BinaryOp::Gt => IntPredicate::UGT,
+        BinaryOp::Ge if signed => IntPredicate::SGE,
+        BinaryOp::Ge => IntPredicate::UGE,
+        BinaryOp::Eq => IntPredicate::EQ,
+        BinaryOp::Ne => IntPredicate::NE,
+        _ => unreachable!(),
+    }
+}
@@ -1,21 +1,27 @@
-use std::collections::HashMap;
-
 use inkwell::{
     AddressSpace,
     types::{
         AnyType, AnyTypeEnum, ArrayType, AsTypeRef, BasicMetadataTypeEnum, BasicType,
-        BasicTypeEnum, FunctionType, IntType, PointerType, StructType, VoidType,
+        BasicTypeEnum, FloatType, FunctionType, IntType, PointerType, StructType, VoidType,
     },
     values::BasicValue,
 };
 
+use ast::StructLayout;
+
 use crate::{LLVM_CONTEXT, info::Value};
 
 #[derive(Debug, Clone)]
 pub enum TypeKind<'t> {
     Unit(VoidType<'t>),
     Function(FunctionType<'t>),
-    Int(IntType<'t>),
+    /// `signed` picks the signed or unsigned forms of division, remainder,
+    /// right shift, comparison and conversion instructions.
+    Int {
+        ty: IntType<'t>,
+        signed: bool,
+    },
+    Float(FloatType<'t>),
     Array {
         ty: ArrayType<'t>,
         element: Box<Self>,
@@ -24,20 +30,61 @@ pub enum TypeKind<'t> {
         ty: PointerType<'t>,
         pointee: Box<Self>,
     },
+    /// `[]T` as `{ ptr data, usize len }`.
+    Slice {
+        ty: StructType<'t>,
+        element: Box<Self>,
+    },
+    /// Emitted as a packed LLVM structure with explicit `[n x i8]` padding,
+    /// so every field sits at the offset its `StructLayout` computed.
     Structure {
         ty: StructType<'t>,
-        field_ids: HashMap<usize, String>,
-        field_types: Vec<Self>,
+        fields: Vec<StructField<'t>>,
+        size: u64,
+        align: u64,
+        namespace: Option<String>,
+    },
+    /// A tagged union laid out as `{ i32 tag, [n x iN] payload }`.
+    Enum {
+        ty: StructType<'t>,
+        variants: Vec<(String, Option<Self>)>,
+    },
+    /// `dyn I` as `{ ptr data, ptr vtable }`. The vtable holds the methods of
+    /// the interface in declaration order, each with its function and return
+    /// type, and `self` passed as the data pointer.
+    Dyn {
+        ty: StructType<'t>,
+        interface: String,
+        methods: Vec<(String, Self, Self)>,
     },
 }
 
+#[derive(Debug, Clone)]
+pub struct StructField<'t> {
+    pub name: String,
+    pub index: u32,
+    pub offset: u64,
+    pub ty: TypeKind<'t>,
+}
+
 impl<'t> TypeKind<'t> {
     pub fn new_unit() -> Self {
         TypeKind::Unit(LLVM_CONTEXT.void_type())
     }
 
-    pub fn new_int(width: u32) -> Self {
-        TypeKind::Int(LLVM_CONTEXT.custom_width_int_type(width))
+    pub fn new_int(width: u32, signed: bool) -> Self {
+        TypeKind::Int {
+            ty: LLVM_CONTEXT.custom_width_int_type(width),
+            signed,
+        }
+    }
+
+    pub fn new_float(width: u32) -> Self {
+        match width {
+            32 => TypeKind::Float(LLVM_CONTEXT.f32_type()),
+            64 => TypeKind::Float(LLVM_CONTEXT.f64_type()),
+            _ => unreachable!(),
+        }
     }
 
     pub fn new_ptr(&self) -> Self {
@@ -57,6 +104,99 @@ impl<'t> TypeKind<'t> {
         }
     }
 
+    pub fn new_slice(&self, pointer_width: u32) -> Self {
+        let ptr = LLVM_CONTEXT.ptr_type(AddressSpace::default());
+        let len = LLVM_CONTEXT.custom_width_int_type(pointer_width);
+        TypeKind::Slice {
+            ty: LLVM_CONTEXT.struct_type(&[ptr.into(), len.into()], false),
+            element: Box::new(self.clone()),
+        }
+    }
+
+    pub fn new_enum(variants: Vec<(String, Option<Self>)>, pointer_width: u32) -> Self {
+        let (size, align) = variants
+            .iter()
+            .filter_map(|(_, payload)| payload.as_ref())
+            .fold((0, 1), |(size, align), payload| {
+                (
+                    size.max(payload.size_of(pointer_width)),
+                    align.max(payload.align_of(pointer_width)),
+                )
+            });
+        let payload = LLVM_CONTEXT
+            .custom_width_int_type(align as u32 * 8)
+            .array_type(size.div_ceil(align) as u32);
+        let ty = LLVM_CONTEXT.struct_type(&[LLVM_CONTEXT.i32_type().into(), payload.into()], false);
+        TypeKind::Enum { ty, variants }
+    }
+
+    pub fn new_struct(
+        fields: Vec<(String, Self)>,
+        layout: StructLayout,
+        namespace: Option<String>,
+        pointer_width: u32,
+    ) -> Self {
+        let (offsets, size, align) = layout.place(
+            fields
+                .iter()
+                .map(|(_, ty)| (ty.size_of(pointer_width), ty.align_of(pointer_width))),
+        );
+
+        let padding = |len: u64| LLVM_CONTEXT.i8_type().array_type(len as u32).into();
+        let mut elements = Vec::new();
+        let mut struct_fields = Vec::new();
+        let mut end = 0;
+        for ((name, ty), offset) in fields.into_iter().zip(offsets) {
+            if offset > end {
+                elements.push(padding(offset - end));
+            }
+            end = offset + ty.size_of(pointer_width);
+            struct_fields.push(StructField {
+                name,
+                index: elements.len() as u32,
+                offset,
+                ty: ty.clone(),
+            });
+            elements.push(ty.as_basic_type_enum());
+        }
+        if size > end {
+            elements.push(padding(size - end));
+        }
+
+        TypeKind::Structure {
+            ty: LLVM_CONTEXT.struct_type(&elements, true),
+            fields: struct_fields,
+            size,
+            align,
+            namespace,
+        }
+    }
+
+    pub fn new_tuple(elements: Vec<Self>, pointer_width: u32) -> Self {
+        let fields = elements
+            .into_iter()
+            .enumerate()
+            .map(|(index, element)| (index.to_string(), element))
+            .collect();
+        Self::new_struct(fields, StructLayout::default(), None, pointer_width)
+    }
+
+    pub fn new_dyn(interface: String, methods: Vec<(String, Self, Self)>) -> Self {
+        let ptr = LLVM_CONTEXT.ptr_type(AddressSpace::default());
+        TypeKind::Dyn {
+            ty: LLVM_CONTEXT.struct_type(&[ptr.into(), ptr.into()], false),
+            interface,
+            methods,
+        }
+    }
+
+    pub fn field(&self, name: &str) -> Option<&StructField<'t>> {
+        match self {
+            TypeKind::Structure { fields, .. } => fields.iter().find(|field| field.name == name),
+            _ => None,
+        }
+    }
+
     pub fn derefed(&self) -> Self {
         match self {
             TypeKind::Ptr { pointee, .. } => pointee.as_ref().clone(),
@@ -72,12 +212,69 @@ impl<'t> TypeKind<'t> {
     }
 
     pub fn is_int(&self) -> bool {
-        matches!(self, Self::Int(_))
+        matches!(self, Self::Int { .. })
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Self::Int { signed: true, .. })
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Self::Float(_))
     }
 
     pub fn is_ptr(&self) -> bool {
         matches!(self, Self::Ptr { .. })
     }
+
+    pub fn is_enum(&self) -> bool {
+        matches!(self, Self::Enum { .. })
+    }
+
+    pub fn is_dyn(&self) -> bool {
+        matches!(self, Self::Dyn { .. })
+    }
+}
+
+impl TypeKind<'_> {
+    /// Size in bytes, following the C layout rules LLVM uses for these types
+    /// and the `StructLayout` of structures.
+    pub fn size_of(&self, pointer_width: u32) -> u64 {
+        match self {
+            TypeKind::Unit(_) | TypeKind::Function(_) => 0,
+            TypeKind::Int { ty, .. } => (ty.get_bit_width() as u64).div_ceil(8).next_power_of_two(),
+            TypeKind::Float(ty) => ty.get_bit_width() as u64 / 8,
+            TypeKind::Ptr { .. } => pointer_width as u64 / 8,
+            TypeKind::Array { ty, element } => ty.len() as u64 * element.size_of(pointer_width),
+            TypeKind::Structure { size, .. } => *size,
+            TypeKind::Enum { ty, .. } => {
+                let (len, unit) = enum_payload_layout(ty);
+                StructLayout::default()
+                    .place([(4, 4), (len * unit, unit)])
+                    .1
+            }
+            TypeKind::Slice { .. } | TypeKind::Dyn { .. } => pointer_width as u64 / 4,
+        }
+    }
+
+    pub fn align_of(&self, pointer_width: u32) -> u64 {
+        match self {
+            TypeKind::Unit(_) | TypeKind::Function(_) => 1,
+            TypeKind::Int { .. } | TypeKind::Float(_) => self.size_of(pointer_width).min(16),
+            TypeKind::Ptr { .. } | TypeKind::Slice { .. } | TypeKind::Dyn { .. } => {
+                pointer_width as u64 / 8
+            }
+            TypeKind::Array { element, .. } => element.align_of(pointer_width),
+            TypeKind::Structure { align, .. } => *align,
+            TypeKind::Enum { ty, .. } => enum_payload_layout(ty).1.max(4),
+        }
+    }
+}
+
+fn enum_payload_layout(ty: &StructType<'_>) -> (u64, u64) {
+    let payload = ty.get_field_type_at_index(1).unwrap().into_array_type();
+    let unit = payload.get_element_type().into_int_type().get_bit_width() as u64 / 8;
+    (payload.len() as u64, unit)
 }
 
 impl<'t> TypeKind<'t> {
@@ -88,10 +285,18 @@ impl<'t> TypeKind<'t> {
             .collect::<Vec<_>>();
         match self {
             TypeKind::Unit(void_type) => TypeKind::Function(void_type.fn_type(&arg_types, false)),
-            TypeKind::Int(int_type) => TypeKind::Function(int_type.fn_type(&arg_types, false)),
+            TypeKind::Int { ty: int_type, .. } => {
+                TypeKind::Function(int_type.fn_type(&arg_types, false))
+            }
+            TypeKind::Float(float_type) => {
+                TypeKind::Function(float_type.fn_type(&arg_types, false))
+            }
             TypeKind::Array { ty, .. } => TypeKind::Function(ty.fn_type(&arg_types, false)),
             TypeKind::Ptr { ty, .. } => TypeKind::Function(ty.fn_type(&arg_types, false)),
             TypeKind::Structure { ty, .. } => TypeKind::Function(ty.fn_type(&arg_types, false)),
+            TypeKind::Slice { ty, .. } | TypeKind::Enum { ty, .. } | TypeKind::Dyn { ty, .. } => {
+                TypeKind::Function(ty.fn_type(&arg_types, false))
+            }
             _ => unreachable!(),
         }
     }
@@ -112,7 +317,14 @@ impl<'t> TypeKind<'t> {
 
     pub fn as_int_type(&self) -> IntType<'t> {
         match self {
-            TypeKind::Int(ty) => *ty,
+            TypeKind::Int { ty, .. } => *ty,
+            _ => panic!("Incorrect usage of type."),
+        }
+    }
+
+    pub fn as_float_type(&self) -> FloatType<'t> {
+        match self {
+            TypeKind::Float(ty) => *ty,
             _ => panic!("Incorrect usage of type."),
         }
     }
@@ -124,14 +336,15 @@ impl<'t> TypeKind<'t> {
         }
     }
 
-    pub fn const_int(&self, value: i64) -> Value<'t> {
+    /// Builds the integer whose low bits are those of `value`.
+    pub fn const_int(&self, value: i128) -> Value<'t> {
         match self {
-            TypeKind::Int(ty) => {
-                let mut int = ty.const_int(value.unsigned_abs(), true);
-                if value.is_negative() {
-                    int = int.const_neg();
+            TypeKind::Int { ty, signed } => {
+                let bits = value as u128;
+                Value::Int {
+                    value: ty.const_int_arbitrary_precision(&[bits as u64, (bits >> 64) as u64]),
+                    signed: *signed,
                 }
-                Value::Int(int)
             }
             _ => panic!("Incorrect usage of type."),
         }
@@ -140,9 +353,12 @@ impl<'t> TypeKind<'t> {
     pub fn const_array(&self, values: &[Value<'t>]) -> Value<'t> {
         let value_iter = values.iter().map(|v| v.as_basic_value_enum());
         let value = match self {
-            TypeKind::Int(ty) => {
+            TypeKind::Int { ty, .. } => {
                 ty.const_array(&value_iter.map(|v| v.into_int_value()).collect::<Vec<_>>())
             }
+            TypeKind::Float(ty) => {
+                ty.const_array(&value_iter.map(|v| v.into_float_value()).collect::<Vec<_>>())
+            }
             TypeKind::Array { ty, .. } => {
                 ty.const_array(&value_iter.map(|v| v.into_array_value()).collect::<Vec<_>>())
             }
@@ -151,7 +367,10 @@ impl<'t> TypeKind<'t> {
                     .map(|v| v.into_pointer_value())
                     .collect::<Vec<_>>(),
             ),
-            TypeKind::Structure { ty, .. } => ty.const_array(
+            TypeKind::Structure { ty, .. }
+            | TypeKind::Slice { ty, .. }
+            | TypeKind::Enum { ty, .. }
+            | TypeKind::Dyn { ty, .. } => ty.const_array(
                 &value_iter
                     .map(|v| v.into_struct_value())
                     .collect::<Vec<_>>(),
@@ -169,10 +388,12 @@ impl<'t> TypeKind<'t> {
 impl<'t> From<TypeKind<'t>> for BasicTypeEnum<'t> {
     fn from(value: TypeKind<'t>) -> Self {
         match value {
-            TypeKind::Int(int_type) => int_type.into(),
+            TypeKind::Int { ty, .. } => ty.into(),
+            TypeKind::Float(float_type) => float_type.into(),
             TypeKind::Ptr { ty, .. } => ty.into(),
             TypeKind::Array { ty, .. } => ty.into(),
-            TypeKind::Structure { ty, .. } => ty.into(),
+            TypeKind::Structure { ty, .. } | TypeKind::Slice { ty, .. } => ty.into(),
+            TypeKind::Enum { ty, .. } | TypeKind::Dyn { ty, .. } => ty.into(),
             _ => unreachable!(),
         }
     }
@@ -181,10 +402,12 @@ impl<'t> From<TypeKind<'t>> for BasicTypeEnum<'t> {
 impl<'t> From<TypeKind<'t>> for BasicMetadataTypeEnum<'t> {
     fn from(value: TypeKind<'t>) -> Self {
         match value {
-            TypeKind::Int(int_type) => int_type.into(),
+            TypeKind::Int { ty, .. } => ty.into(),
+            TypeKind::Float(float_type) => float_type.into(),
             TypeKind::Ptr { ty, .. } => ty.into(),
             TypeKind::Array { ty, .. } => ty.into(),
-            TypeKind::Structure { ty, .. } => ty.into(),
+            TypeKind::Structure { ty, .. } | TypeKind::Slice { ty, .. } => ty.into(),
+            TypeKind::Enum { ty, .. } | TypeKind::Dyn { ty, .. } => ty.into(),
             _ => unreachable!(),
         }
     }
@@ -195,10 +418,12 @@ impl<'t> From<TypeKind<'t>> for AnyTypeEnum<'t> {
         match value {
             TypeKind::Unit(void_type) => void_type.into(),
             TypeKind::Function(func_type) => func_type.into(),
-            TypeKind::Int(int_type) => int_type.into(),
+            TypeKind::Int { ty, .. } => ty.into(),
+            TypeKind::Float(float_type) => float_type.into(),
             TypeKind::Ptr { ty, .. } => ty.into(),
             TypeKind::Array { ty, .. } => ty.into(),
-            TypeKind::Structure { ty, .. } => ty.into(),
+            TypeKind::Structure { ty, .. } | TypeKind::Slice { ty, .. } => ty.into(),
+            TypeKind::Enum { ty, .. } | TypeKind::Dyn { ty, .. } => ty.into(),
         }
     }
 }
@@ -2,7 +2,7 @@ use inkwell::{
     builder::Builder,
     values::{
         AnyValue, AnyValueEnum, ArrayValue, AsValueRef, BasicMetadataValueEnum, BasicValue,
-        FunctionValue, IntValue, PointerValue,
+        FloatValue, FunctionValue, IntValue, PointerValue, StructValue,
     },
 };
 
@@ -10,7 +10,11 @@ use crate::info::TypeKind;
 
 #[derive(Debug, Clone)]
 pub enum Value<'v> {
-    Int(IntValue<'v>),
+    Int {
+        value: IntValue<'v>,
+        signed: bool,
+    },
+    Float(FloatValue<'v>),
     Function(FunctionValue<'v>, TypeKind<'v>),
     Pointer {
         value: PointerValue<'v>,
@@ -25,16 +29,37 @@ pub enum Value<'v> {
         value_ty: TypeKind<'v>,
     },
     Structure {
-        value: Vec<Self>,
+        value: StructValue<'v>,
+        ty: TypeKind<'v>,
+    },
+    Enum {
+        value: StructValue<'v>,
         ty: TypeKind<'v>,
     },
+    EnumCtor(TypeKind<'v>, usize),
     Type(TypeKind<'v>),
     Unit,
 }
 
 impl<'v> Value<'v> {
+    pub fn new_bool(value: IntValue<'v>) -> Self {
+        Value::Int {
+            value,
+            signed: false,
+        }
+    }
+
     pub fn as_int(&self) -> IntValue<'v> {
-        let Value::Int(v) = self else { unreachable!() };
+        let Value::Int { value, .. } = self else {
+            unreachable!()
+        };
+        *value
+    }
+
+    pub fn as_float(&self) -> FloatValue<'v> {
+        let Value::Float(v) = self else {
+            unreachable!()
+        };
         *v
     }
 
@@ -90,7 +115,15 @@ impl<'v> Value<'v> {
     }
 
     pub fn is_int(&self) -> bool {
-        matches!(self, Self::Int(_))
+        matches!(self, Self::Int { .. })
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Self::Int { signed: true, .. })
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Self::Float(_))
     }
 
     pub fn is_ptr(&self) -> bool {
@@ -101,13 +134,18 @@ impl<'v> Value<'v> {
 impl<'v> Value<'v> {
     pub fn type_(&self) -> TypeKind<'v> {
         match self {
-            Value::Int(v) => TypeKind::Int(v.get_type()),
+            Value::Int { value, signed } => TypeKind::Int {
+                ty: value.get_type(),
+                signed: *signed,
+            },
+            Value::Float(v) => TypeKind::Float(v.get_type()),
             Value::Function(f, _) => TypeKind::Function(f.get_type()),
             Value::Pointer { ty, .. } => ty.clone(),
             Value::Alloca { value_ty, .. } => value_ty.new_ptr(),
             Value::Unit => TypeKind::new_unit(),
             Value::Array { ty, .. } => ty.clone(),
             Value::Structure { ty, .. } => ty.clone(),
+            Value::Enum { ty, .. } => ty.clone(),
             _ => unreachable!(),
         }
     }
@@ -119,7 +157,11 @@ impl<'v> Value<'v> {
             return Value::Unit;
         }
         match value {
-            AnyValueEnum::IntValue(v) => Value::Int(v),
+            AnyValueEnum::IntValue(v) => Value::Int {
+                value: v,
+                signed: ty.is_signed(),
+            },
+            AnyValueEnum::FloatValue(v) => Value::Float(v),
             AnyValueEnum::PointerValue(v) => Value::Pointer {
                 value: v,
                 ty: ty.clone(),
@@ -129,6 +171,14 @@ impl<'v> Value<'v> {
                 value: v,
                 ty: ty.clone(),
             },
+            AnyValueEnum::StructValue(v) if ty.is_enum() => Value::Enum {
+                value: v,
+                ty: ty.clone(),
+            },
+            AnyValueEnum::StructValue(v) => Value::Structure {
+                value: v,
+                ty: ty.clone(),
+            },
             _ => panic!("unexpected: {}", value),
         }
     }
@@ -136,20 +186,14 @@ impl<'v> Value<'v> {
 
 impl<'v> Value<'v> {
     pub fn build_store(&self, ptr: Self, builder: &Builder<'v>) {
-        let pointee = ptr.type_().derefed();
         let ptr = ptr.as_ptr();
         match self {
-            Value::Int(v) => _ = builder.build_store(ptr, *v).unwrap(),
+            Value::Int { value, .. } => _ = builder.build_store(ptr, *value).unwrap(),
+            Value::Float(v) => _ = builder.build_store(ptr, *v).unwrap(),
             Value::Pointer { value, .. } => _ = builder.build_store(ptr, *value).unwrap(),
             Value::Array { value, .. } => _ = builder.build_store(ptr, *value).unwrap(),
-            Value::Structure { value, .. } => {
-                for (field_id, field_value) in value.iter().enumerate() {
-                    let ptr = builder
-                        .build_struct_gep(pointee.clone(), ptr, field_id as u32, "")
-                        .unwrap();
-                    builder.build_store(ptr, field_value.clone()).unwrap();
-                }
-            }
+            Value::Enum { value, .. } => _ = builder.build_store(ptr, *value).unwrap(),
+            Value::Structure { value, .. } => _ = builder.build_store(ptr, *value).unwrap(),
             _ => unreachable!(),
         };
     }
@@ -158,9 +202,12 @@ impl<'v> Value<'v> {
 impl<'v> From<Value<'v>> for BasicMetadataValueEnum<'v> {
     fn from(value: Value<'v>) -> Self {
         match value {
-            Value::Int(v) => v.into(),
+            Value::Int { value, .. } => value.into(),
+            Value::Float(v) => v.into(),
             Value::Pointer { value, .. } => value.into(),
             Value::Array { value, .. } => value.into(),
+            Value::Structure { value, .. } => value.into(),
+            Value::Enum { value, .. } => value.into(),
             _ => panic!("Creating BasicMetadataValueEnum from {:?}", value),
         }
     }
@@ -169,12 +216,15 @@ impl<'v> From<Value<'v>> for BasicMetadataValueEnum<'v> {
 unsafe impl<'v> AsValueRef for Value<'v> {
     fn as_value_ref(&self) -> inkwell::llvm_sys::prelude::LLVMValueRef {
         match self {
-            Value::Int(v) => v.as_value_ref(),
+            Value::Int { value, .. } => value.as_value_ref(),
+            Value::Float(v) => v.as_value_ref(),
             Value::Function(v, _) => v.as_value_ref(),
             Value::Pointer { value, .. } => value.as_value_ref(),
             Value::Alloca { value, .. } => value.as_value_ref(),
             Value::Unit => unreachable!(),
             Value::Array { value, .. } => value.as_value_ref(),
+            Value::Structure { value, .. } => value.as_value_ref(),
+            Value::Enum { value, .. } => value.as_value_ref(),
             _ => unreachable!(),
         }
     }
@@ -0,0 +1,146 @@
+use ast::{Call, FieldAccess};
+use const_eval::{ValueKind, queries::CONST_EVAL_PROVIDER};
+use inkwell::{
+    AddressSpace,
+    module::Linkage,
+    values::{AnyValue, PointerValue},
+};
+use monomorphize::CodegenItem;
+
+use crate::{
+    LLVM_CONTEXT, VisitorCtx,
+    info::{TypeKind, Value},
+};
+
+impl<'v> VisitorCtx<'v> {
+    pub(crate) fn build_dyn(&mut self, value: Value<'v>, ty: TypeKind<'v>) -> Value<'v> {
+        let TypeKind::Dyn {
+            ty: dyn_ty,
+            interface,
+            methods,
+        } = &ty
+        else {
+            unreachable!()
+        };
+        let TypeKind::Structure {
+            namespace: Some(namespace),
+            ..
+        } = value.type_().derefed()
+        else {
+            unreachable!()
+        };
+        let vtable = self.vtable(&namespace, interface, methods);
+
+        let fat = self
+            .builder
+            .build_insert_value(dyn_ty.get_undef(), value.as_ptr(), 0, "")
+            .unwrap();
+        let fat = self
+            .builder
+            .build_insert_value(fat, vtable, 1, "")
+            .unwrap()
+            .into_struct_value();
+        Value::Structure { value: fat, ty }
+    }
+
+    /// The vtable of the structure living in `namespace` for `interface`,
+    /// emitted the first time a pointer to it is turned into `dyn interface`.
+    fn vtable(
+        &self,
+        namespace: &str,
+        interface: &str,
+        methods: &[(String, TypeKind<'v>, TypeKind<'v>)],
+    ) -> PointerValue<'static> {
+        let name = format!("vtable.{}.{}", namespace, interface);
+        if let Some(vtable) = self.module.get_global(&name) {
+            return vtable.as_pointer_value();
+        }
+
+        let slots = methods
+            .iter()
+            .map(|(method, _, _)| {
+                let def_id = self
+                    .queries
+                    .lookup_def_id(format!("{}::{}", namespace, method))
+                    .unwrap();
+                let ValueKind::Function(func) = self
+                    .queries
+                    .query_cached(&CONST_EVAL_PROVIDER, def_id)
+                    .unwrap()
+                    .kind()
+                else {
+                    unreachable!()
+                };
+                let func = self.global_funcs[&CodegenItem::Func(func)].as_fn();
+                self.module
+                    .get_function(func.get_name().to_str().unwrap())
+                    .unwrap()
+                    .as_global_value()
+                    .as_pointer_value()
+            })
+            .collect::<Vec<_>>();
+        let slots = LLVM_CONTEXT
+            .ptr_type(AddressSpace::default())
+            .const_array(&slots);
+
+        let vtable = self.module.add_global(slots.get_type(), None, &name);
+        vtable.set_initializer(&slots);
+        vtable.set_linkage(Linkage::Private);
+        vtable.set_constant(true);
+        vtable.as_pointer_value()
+    }
+
+    pub(crate) fn build_dyn_call(
+        &mut self,
+        receiver: Value<'v>,
+        field_access: &FieldAccess,
+        call: &Call,
+    ) -> Value<'v> {
+        let TypeKind::Dyn { methods, .. } = receiver.type_() else {
+            unreachable!()
+        };
+        let Value::Structure { value: fat, .. } = receiver else {
+            unreachable!()
+        };
+        let (slot, (_, fn_ty, ret_ty)) = methods
+            .iter()
+            .enumerate()
+            .find(|(_, (name, _, _))| *name == field_access.field)
+            .unwrap();
+
+        let ptr_ty = LLVM_CONTEXT.ptr_type(AddressSpace::default());
+        let data = self.builder.build_extract_value(fat, 0, "").unwrap();
+        let vtable = self
+            .builder
+            .build_extract_value(fat, 1, "")
+            .unwrap()
+            .into_pointer_value();
+        let slot = unsafe {
+            self.builder
+                .build_gep(
+                    ptr_ty,
+                    vtable,
+                    &[LLVM_CONTEXT.i32_type().const_int(slot as u64, false)],
+                    "",
+                )
+                .unwrap()
+        };
+        let func = self
+            .builder
+            .build_load(ptr_ty, slot, "")
+            .unwrap()
+            .into_pointer_value();
+
+        let mut args = vec![data.into()];
+        let param_types = fn_ty.as_function_type().get_param_types();
+        for (arg, param_ty) in call.args.iter().zip(param_types.into_iter().skip(1)) {
+            args.push(self.visit_arg(*arg, param_ty).into());
+        }
+        let result = self
+            .builder
+            .build_indirect_call(fn_ty.as_function_type(), func, &args, "")
+            .unwrap();
+
+        Value::new_from(result.as_any_value_enum(), ret_ty.clone())
+    }
+}
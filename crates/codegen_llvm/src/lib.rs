@@ -9,6 +9,7 @@ use ast::{ExpId, FunctionDef, Param, ProtoDef, visitor::BlockVisitor};
 use codegen::{
     BackendOptions, CodegenBackend, CodegenBackendBase, CodegenResult, EmitOptions, OutputType,
 };
+use const_eval::Bindings;
 use inkwell::{
     OptimizationLevel,
     basic_block::BasicBlock,
@@ -30,12 +31,16 @@ use uuid::Uuid;
 use crate::{
     info::{Symbol, TypeKind, Value},
     targets::llvm_target,
-    types::get_llvm_type_from_exp,
+    types::{const_eval_type_to_llvm_type, get_llvm_type_with_bindings},
 };
 
+mod array;
 mod expr;
 mod info;
+mod interface;
+mod method;
 mod program;
+mod slice;
 mod stmt;
 mod targets;
 mod types;
@@ -79,7 +84,13 @@ impl CodegenBackend for LLVMBackend {
         let module = Arc::new(module);
 
         for item in codegen_units {
-            Self::codegen_item(ctx.clone(), &item, global_funcs.clone(), module.clone());
+            Self::codegen_item(
+                ctx.clone(),
+                &item,
+                global_funcs.clone(),
+                module.clone(),
+                self.backend_options.debug_assertions,
+            );
         }
 
         Box::new(LLVMCodegenResult::new(
@@ -124,22 +135,25 @@ impl LLVMBackend {
                 continue;
             }
 
-            let CodegenItem::Func(func) = unit else {
-                panic!("Expected function value");
+            let (func, function_name, bindings) = match unit {
+                CodegenItem::Func(func) => {
+                    let function_name = match &func.abi {
+                        ast::Abi::CAbi(name) => name.clone(),
+                        _ => Uuid::new_v4().to_string(),
+                    };
+                    (func, function_name, Bindings::new())
+                }
+                CodegenItem::Instance(inst) => (&inst.func, inst.symbol.clone(), inst.bindings()),
+                CodegenItem::Proto(_) => unreachable!(),
             };
             let FunctionDef {
-                abi,
                 params,
                 return_type,
                 ..
             } = func.as_ref();
 
-            let function_name = match abi {
-                ast::Abi::CAbi(name) => name.clone(),
-                _ => Uuid::new_v4().to_string(),
-            };
-
-            let (function_type, return_type) = Self::llvm_fn_sig(ctx.clone(), params, return_type);
+            let (function_type, return_type) =
+                Self::llvm_fn_sig_with_bindings(ctx.clone(), params, return_type, &bindings);
             let function_value =
                 module.add_function(&function_name, function_type.as_function_type(), None);
             function_value.set_call_conventions(LLVMCallConv::LLVMCCallConv as u32);
@@ -157,15 +171,28 @@ impl LLVMBackend {
         ctx: Arc<QueryContext>,
         params: &[Param],
         return_type: &Option<ExpId>,
+    ) -> (TypeKind<'static>, TypeKind<'static>) {
+        Self::llvm_fn_sig_with_bindings(ctx, params, return_type, &Bindings::new())
+    }
+
+    fn llvm_fn_sig_with_bindings(
+        ctx: Arc<QueryContext>,
+        params: &[Param],
+        return_type: &Option<ExpId>,
+        bindings: &Bindings,
     ) -> (TypeKind<'static>, TypeKind<'static>) {
         let mut param_types = Vec::new();
-        for param in params {
-            param_types.push(get_llvm_type_from_exp(ctx.clone(), param.param_type));
+        for param in params.iter().filter(|param| !param.comptime) {
+            param_types.push(get_llvm_type_with_bindings(
+                ctx.clone(),
+                param.param_type,
+                bindings,
+            ));
         }
 
         let return_type = return_type
             .as_ref()
-            .map(|return_type| get_llvm_type_from_exp(ctx.clone(), *return_type))
+            .map(|return_type| get_llvm_type_with_bindings(ctx.clone(), *return_type, bindings))
             .unwrap_or(TypeKind::new_unit());
         let function_type = return_type.function(param_types);
 
@@ -177,11 +204,14 @@ impl LLVMBackend {
         item: &CodegenItem,
         global_funcs: Arc<FunctionMap>,
         module: Arc<Module<'static>>,
+        debug_assertions: bool,
     ) {
-        let CodegenItem::Func(func) = item else {
-            return;
+        let (func, bindings) = match item {
+            CodegenItem::Func(func) => (func, Bindings::new()),
+            CodegenItem::Instance(inst) => (&inst.func, inst.bindings()),
+            CodegenItem::Proto(_) => return,
         };
-        let FunctionDef { params, block, .. } = func.as_ref();
+        let FunctionDef { block, .. } = func.as_ref();
 
         let func_value = global_funcs.get(item).cloned().unwrap();
         let function = func_value.as_fn();
@@ -198,10 +228,23 @@ impl LLVMBackend {
             current_fn: func_value,
             global_funcs,
             loop_blocks: Vec::new(),
+            bindings: bindings.clone(),
+            debug_assertions,
         };
 
-        for (id, param) in params.iter().enumerate() {
-            let ty = get_llvm_type_from_exp(ctx.clone(), param.param_type);
+        visitor_ctx.symbols.push_scope();
+        for (name, value) in bindings.iter() {
+            let value = match value.kind() {
+                const_eval::ValueKind::Type(ty) => {
+                    Value::Type(const_eval_type_to_llvm_type(ctx.clone(), &ty))
+                }
+                _ => visitor_ctx.const_value_to_llvm_value(value),
+            };
+            visitor_ctx.symbols.push(Symbol::Var(name.clone(), value));
+        }
+
+        for (id, param) in func.runtime_params().enumerate() {
+            let ty = get_llvm_type_with_bindings(ctx.clone(), param.param_type, &bindings);
             let param_value = function.get_nth_param(id as u32).unwrap();
             let param_value = Value::new_from(param_value.as_any_value_enum(), ty.clone());
 
@@ -219,8 +262,14 @@ impl LLVMBackend {
         {
             visitor_ctx.builder.build_return(Some(&value)).unwrap();
         }
+        // The analyzer makes sure a function returning a value can't reach
+        // its end any other way.
         visitor_ctx.build_terminator(&visitor_ctx.builder, |builder| {
-            builder.build_return(None).unwrap();
+            if function.get_type().get_return_type().is_some() {
+                builder.build_unreachable().unwrap();
+            } else {
+                builder.build_return(None).unwrap();
+            }
         });
     }
 }
@@ -247,6 +296,7 @@ impl LLVMCodegenResult {
             code_model,
             optimize_level,
             reloc_mode,
+            ..
         } = backend_options;
 
         let code_model = match code_model {
@@ -330,6 +380,14 @@ impl CodegenResult for LLVMCodegenResult {
     }
 }
 
+struct LoopBlocks<'v> {
+    label: Option<String>,
+    entry: BasicBlock<'v>,
+    exit: BasicBlock<'v>,
+    /// Where `break value` stores its value, created by the first one.
+    result: Option<Value<'v>>,
+}
+
 struct VisitorCtx<'v> {
     builder: Builder<'v>,
     symbols: SymbolTable<Symbol<'v>>,
@@ -337,7 +395,9 @@ struct VisitorCtx<'v> {
     queries: Arc<QueryContext>,
     current_fn: Value<'v>,
     global_funcs: Arc<HashMap<CodegenItem, Value<'v>>>,
-    loop_blocks: Vec<(BasicBlock<'v>, BasicBlock<'v>)>,
+    loop_blocks: Vec<LoopBlocks<'v>>,
+    bindings: Bindings,
+    debug_assertions: bool,
 }
 
 impl<'v> VisitorCtx<'v> {
@@ -356,12 +416,22 @@ impl<'v> VisitorCtx<'v> {
         }
 
         let alloca_ty = match ty {
-            TypeKind::Unit(_) => TypeKind::new_int(8).new_array(0),
+            TypeKind::Unit(_) => TypeKind::new_int(8, false).new_array(0),
             _ => ty.clone(),
         };
 
+        let pointer_width = self.queries.target().pointer_width();
+        let value = builder.build_alloca(alloca_ty, name).unwrap();
+        // Structures are emitted packed, so their alignment has to be asked
+        // for explicitly.
+        value
+            .as_instruction()
+            .unwrap()
+            .set_alignment(ty.align_of(pointer_width) as u32)
+            .unwrap();
+
         Value::Alloca {
-            value: builder.build_alloca(alloca_ty, name).unwrap(),
+            value,
             value_ty: ty,
         }
     }
@@ -374,16 +444,25 @@ impl<'v> VisitorCtx<'v> {
         alloca
     }
 
-    fn current_loop(&self) -> Option<(BasicBlock<'v>, BasicBlock<'v>)> {
-        self.loop_blocks.last().copied()
+    fn find_loop(&mut self, label: &Option<String>) -> &mut LoopBlocks<'v> {
+        self.loop_blocks
+            .iter_mut()
+            .rev()
+            .find(|blocks| label.is_none() || blocks.label == *label)
+            .unwrap()
     }
 
-    fn push_loop(&mut self, entry: BasicBlock<'v>, exit: BasicBlock<'v>) {
-        self.loop_blocks.push((entry, exit));
+    fn push_loop(&mut self, label: &Option<String>, entry: BasicBlock<'v>, exit: BasicBlock<'v>) {
+        self.loop_blocks.push(LoopBlocks {
+            label: label.clone(),
+            entry,
+            exit,
+            result: None,
+        });
     }
 
-    fn pop_loop_block(&mut self) {
-        self.loop_blocks.pop();
+    fn pop_loop_block(&mut self) -> Option<Value<'v>> {
+        self.loop_blocks.pop().and_then(|blocks| blocks.result)
     }
 
     fn build_terminator<F>(&self, builder: &Builder<'v>, f: F)
@@ -400,6 +479,13 @@ impl<'v> VisitorCtx<'v> {
         }
     }
 
+    /// The block being built if control can still fall out of it, which is
+    /// where a value built in it comes from.
+    fn open_block(&self) -> Option<BasicBlock<'v>> {
+        let block = self.builder.get_insert_block().unwrap();
+        block.get_terminator().is_none().then_some(block)
+    }
+
     fn build_branch(&self, bb: BasicBlock<'v>) {
         self.build_terminator(&self.builder, |builder| {
             builder.build_unconditional_branch(bb).unwrap();
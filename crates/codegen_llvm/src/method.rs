@@ -0,0 +1,100 @@
+use ast::{Call, FieldAccess, visitor::ExpVisitor};
+use const_eval::{ValueKind, queries::CONST_EVAL_PROVIDER};
+use inkwell::values::AnyValue;
+use monomorphize::CodegenItem;
+
+use crate::{
+    VisitorCtx,
+    info::{TypeKind, Value},
+};
+
+impl<'v> VisitorCtx<'v> {
+    pub(crate) fn build_method_call(
+        &mut self,
+        field_access: &FieldAccess,
+        call: &Call,
+    ) -> Value<'v> {
+        let receiver = self.visit_left_value(field_access.lhs);
+        let is_dyn = match &receiver {
+            Value::Alloca { value_ty, .. } => value_ty.is_dyn(),
+            value => value.type_().is_dyn(),
+        };
+        if is_dyn {
+            let receiver = receiver.as_right_value(&self.builder);
+            return self.build_dyn_call(receiver, field_access, call);
+        }
+        let receiver = match receiver {
+            value @ (Value::Alloca { .. } | Value::Pointer { .. }) => value,
+            value => self.create_entry_bb_alloca_with_init("", value),
+        };
+        let mut struct_ty = receiver.type_().derefed();
+        let mut struct_ptr = receiver.as_ptr();
+        if struct_ty.is_ptr() {
+            struct_ptr = self
+                .builder
+                .build_load(struct_ty.clone(), struct_ptr, "")
+                .unwrap()
+                .into_pointer_value();
+            struct_ty = struct_ty.derefed();
+        }
+
+        let mut args = Vec::new();
+        let func = if let Some(field) = struct_ty.field(&field_access.field) {
+            let field_ptr = self
+                .builder
+                .build_struct_gep(struct_ty.clone(), struct_ptr, field.index, "")
+                .unwrap();
+            Value::Alloca {
+                value: field_ptr,
+                value_ty: field.ty.clone(),
+            }
+            .as_right_value(&self.builder)
+        } else {
+            let TypeKind::Structure {
+                namespace: Some(namespace),
+                ..
+            } = &struct_ty
+            else {
+                unreachable!()
+            };
+            let name = format!("{}::{}", namespace, field_access.field);
+            let def_id = self.queries.lookup_def_id(name).unwrap();
+            let ValueKind::Function(method) = self
+                .queries
+                .query_cached(&CONST_EVAL_PROVIDER, def_id)
+                .unwrap()
+                .kind()
+            else {
+                unreachable!()
+            };
+            let method = self.global_funcs[&CodegenItem::Func(method)].clone();
+
+            let by_address = method.as_fn().get_type().get_param_types()[0].is_pointer_type();
+            args.push(if by_address {
+                Value::Pointer {
+                    value: struct_ptr,
+                    ty: struct_ty.new_ptr(),
+                }
+            } else {
+                Value::Alloca {
+                    value: struct_ptr,
+                    value_ty: struct_ty,
+                }
+                .as_right_value(&self.builder)
+            });
+            method
+        };
+
+        let Value::Function(func, ret_ty) = func else {
+            unreachable!()
+        };
+        let param_types = func.get_type().get_param_types();
+        for (arg, param_ty) in call.args.iter().zip(param_types.into_iter().skip(1)) {
+            args.push(self.visit_arg(*arg, param_ty));
+        }
+        let args = args.into_iter().map(Into::into).collect::<Vec<_>>();
+        let result = self.builder.build_call(func, &args, "").unwrap();
+
+        Value::new_from(result.as_any_value_enum(), ret_ty)
+    }
+}
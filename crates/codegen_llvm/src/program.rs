@@ -1,8 +1,13 @@
-use ast::visitor::{BlockVisitor, ExpVisitor};
+use ast::{
+    VarBinding,
+    visitor::{BlockVisitor, ExpVisitor},
+};
+use inkwell::values::AnyValue;
 
 use crate::{
     LLVM_CONTEXT, VisitorCtx,
     info::{Symbol, TypeKind, Value},
+    types::get_llvm_type_with_bindings,
 };
 
 impl<'v> BlockVisitor<Value<'v>> for VisitorCtx<'v> {
@@ -15,10 +20,59 @@ impl<'v> BlockVisitor<Value<'v>> for VisitorCtx<'v> {
     }
 
     fn visit_var_def(&mut self, var_def: &ast::VarDef) {
-        let value = self.visit_right_value(var_def.initial_value);
-        let alloca = self.create_entry_bb_alloca_with_init(&var_def.name, value);
-
-        self.symbols.push(Symbol::Var(var_def.name.clone(), alloca));
+        // `let x: T;` only makes room for the variables, which are assigned
+        // later.
+        let Some(initial_value) = var_def.initial_value else {
+            let ty = get_llvm_type_with_bindings(
+                self.queries.clone(),
+                var_def.var_type.unwrap(),
+                &self.bindings,
+            );
+            match (&var_def.binding, ty) {
+                (VarBinding::Name(name), ty) => {
+                    let alloca = self.create_entry_bb_alloca(name, ty);
+                    self.symbols.push(Symbol::Var(name.clone(), alloca));
+                }
+                (VarBinding::Tuple(names), TypeKind::Structure { fields, .. }) => {
+                    for (name, field) in names.iter().zip(fields) {
+                        let alloca = self.create_entry_bb_alloca(name, field.ty);
+                        self.symbols.push(Symbol::Var(name.clone(), alloca));
+                    }
+                }
+                _ => unreachable!(),
+            }
+            return;
+        };
+        let value = match var_def.var_type {
+            Some(ty) => {
+                let ty = get_llvm_type_with_bindings(self.queries.clone(), ty, &self.bindings);
+                self.visit_stored(initial_value, ty)
+            }
+            None => self.visit_right_value(initial_value),
+        };
+        match &var_def.binding {
+            VarBinding::Name(name) => {
+                let alloca = self.create_entry_bb_alloca_with_init(name, value);
+                self.symbols.push(Symbol::Var(name.clone(), alloca));
+            }
+            VarBinding::Tuple(names) => {
+                let Value::Structure { value: tuple, ty } = value else {
+                    unreachable!()
+                };
+                let TypeKind::Structure { fields, .. } = ty else {
+                    unreachable!()
+                };
+                for (name, field) in names.iter().zip(fields) {
+                    let element = self
+                        .builder
+                        .build_extract_value(tuple, field.index, name)
+                        .unwrap();
+                    let element = Value::new_from(element.as_any_value_enum(), field.ty);
+                    let alloca = self.create_entry_bb_alloca_with_init(name, element);
+                    self.symbols.push(Symbol::Var(name.clone(), alloca));
+                }
+            }
+        }
     }
 
     fn visit_inline_asm(&mut self, inline_asm: &ast::InlineAsm) {
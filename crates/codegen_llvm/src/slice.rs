@@ -0,0 +1,277 @@
+use ast::{Exp, ExpId, Slice, Span, visitor::ExpVisitor};
+use inkwell::{
+    AddressSpace, IntPredicate,
+    intrinsics::Intrinsic,
+    module::Linkage,
+    types::BasicMetadataTypeEnum,
+    values::{BasicMetadataValueEnum, IntValue, PointerValue},
+};
+use targets::spec::Os;
+
+use crate::{
+    LLVM_CONTEXT, VisitorCtx,
+    info::{TypeKind, Value},
+};
+
+impl<'v> VisitorCtx<'v> {
+    pub(crate) fn elements(
+        &mut self,
+        value: Value<'v>,
+    ) -> (PointerValue<'v>, TypeKind<'v>, Option<IntValue<'v>>) {
+        let usize_ty = LLVM_CONTEXT.custom_width_int_type(self.queries.target().pointer_width());
+        let value = match value {
+            Value::Array { .. } => self.create_entry_bb_alloca_with_init("", value),
+            value => value,
+        };
+        match value {
+            Value::Alloca {
+                value: ptr,
+                value_ty: TypeKind::Array { ty, element },
+            } => (
+                ptr,
+                *element,
+                Some(usize_ty.const_int(ty.len() as u64, false)),
+            ),
+            Value::Alloca {
+                value_ty: TypeKind::Slice { .. },
+                ..
+            } => self.elements(value.as_right_value(&self.builder)),
+            Value::Alloca {
+                value: ptr,
+                value_ty: value_ty @ TypeKind::Ptr { .. },
+            } => {
+                let ptr = self
+                    .builder
+                    .build_load(value_ty.clone(), ptr, "")
+                    .unwrap()
+                    .into_pointer_value();
+                self.elements(Value::Pointer {
+                    value: ptr,
+                    ty: value_ty,
+                })
+            }
+            Value::Pointer { value: ptr, ty } => match ty.derefed() {
+                TypeKind::Array { ty, element } => (
+                    ptr,
+                    *element,
+                    Some(usize_ty.const_int(ty.len() as u64, false)),
+                ),
+                element => (ptr, element, None),
+            },
+            Value::Structure {
+                value: slice,
+                ty: TypeKind::Slice { element, .. },
+            } => {
+                let ptr = self.builder.build_extract_value(slice, 0, "").unwrap();
+                let len = self.builder.build_extract_value(slice, 1, "").unwrap();
+                (
+                    ptr.into_pointer_value(),
+                    *element,
+                    Some(len.into_int_value()),
+                )
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    pub(crate) fn build_slice(&mut self, slice: &Slice) -> Value<'v> {
+        let container = self.visit_left_value(slice.exp);
+        let (ptr, element, len) = self.elements(container);
+
+        let usize_ty = TypeKind::new_int(self.queries.target().pointer_width(), false);
+        let start = match slice.start {
+            Some(start) => self.visit_usize(start),
+            None => usize_ty.as_int_type().const_zero(),
+        };
+        let end = match slice.end {
+            Some(end) => self.visit_usize(end),
+            None => len.unwrap(),
+        };
+
+        if let Some(len) = len {
+            let ordered = self
+                .builder
+                .build_int_compare(IntPredicate::ULE, start, end, "")
+                .unwrap();
+            let in_range = self
+                .builder
+                .build_int_compare(IntPredicate::ULE, end, len, "")
+                .unwrap();
+            let valid = self.builder.build_and(ordered, in_range, "").unwrap();
+            self.build_check(
+                valid,
+                slice.span,
+                "range %zu..%zu out of bounds for length %zu",
+                &[start.into(), end.into(), len.into()],
+            );
+        }
+
+        let data = unsafe {
+            self.builder
+                .build_gep(element.clone(), ptr, &[start], "")
+                .unwrap()
+        };
+        let len = self.builder.build_int_sub(end, start, "").unwrap();
+        self.build_slice_value(element, data, len)
+    }
+
+    pub(crate) fn build_index_check(
+        &mut self,
+        index: IntValue<'v>,
+        len: Option<IntValue<'v>>,
+        span: Span,
+    ) {
+        let Some(len) = len else {
+            return;
+        };
+        let in_range = self
+            .builder
+            .build_int_compare(IntPredicate::ULT, index, len, "")
+            .unwrap();
+        self.build_check(
+            in_range,
+            span,
+            "index %zu out of bounds for length %zu",
+            &[index.into(), len.into()],
+        );
+    }
+
+    pub(crate) fn visit_arg(
+        &mut self,
+        arg: ExpId,
+        param_ty: BasicMetadataTypeEnum<'v>,
+    ) -> Value<'v> {
+        let value = self.visit_right_value(arg);
+        let slice_ty = TypeKind::new_unit().new_slice(self.queries.target().pointer_width());
+        let TypeKind::Slice { ty: slice_ty, .. } = slice_ty else {
+            unreachable!()
+        };
+        if param_ty != slice_ty.into() || !value.is_ptr() {
+            return value;
+        }
+
+        let usize_ty = LLVM_CONTEXT.custom_width_int_type(self.queries.target().pointer_width());
+        let string = match self.queries.ast_ctx().exp(arg) {
+            Exp::Str(string, _) => Some((string.len(), true)),
+            Exp::ByteStr(bytes, _) => Some((bytes.len(), false)),
+            _ => None,
+        };
+        if let Some((len, signed)) = string {
+            let len = usize_ty.const_int(len as u64, false);
+            return self.build_slice_value(TypeKind::new_int(8, signed), value.as_ptr(), len);
+        }
+        let (ptr, element, len) = self.elements(value);
+        self.build_slice_value(element, ptr, len.unwrap())
+    }
+
+    pub(crate) fn visit_stored(&mut self, exp: ExpId, ty: TypeKind<'v>) -> Value<'v> {
+        match ty {
+            TypeKind::Slice { .. } => self.visit_arg(exp, ty.into()),
+            _ => self.visit_right_value(exp),
+        }
+    }
+
+    fn visit_usize(&mut self, exp: ExpId) -> IntValue<'v> {
+        let value = self.visit_right_value(exp);
+        let usize_ty = LLVM_CONTEXT.custom_width_int_type(self.queries.target().pointer_width());
+        self.builder
+            .build_int_cast_sign_flag(value.as_int(), usize_ty, value.is_signed(), "")
+            .unwrap()
+    }
+
+    fn build_slice_value(
+        &self,
+        element: TypeKind<'v>,
+        data: PointerValue<'v>,
+        len: IntValue<'v>,
+    ) -> Value<'v> {
+        let ty = element.new_slice(self.queries.target().pointer_width());
+        let TypeKind::Slice { ty: slice_ty, .. } = &ty else {
+            unreachable!()
+        };
+        let slice = self
+            .builder
+            .build_insert_value(slice_ty.get_undef(), data, 0, "")
+            .unwrap();
+        let slice = self
+            .builder
+            .build_insert_value(slice, len, 1, "")
+            .unwrap()
+            .into_struct_value();
+        Value::Structure { value: slice, ty }
+    }
+
+    fn build_check(
+        &mut self,
+        condition: IntValue<'v>,
+        span: Span,
+        message: &str,
+        args: &[BasicMetadataValueEnum<'v>],
+    ) {
+        if !self.debug_assertions {
+            return;
+        }
+        let current_fn = self.current_fn.as_fn();
+        let panic_block = LLVM_CONTEXT.append_basic_block(current_fn, "panic");
+        let ok_block = LLVM_CONTEXT.append_basic_block(current_fn, "ok");
+        self.builder
+            .build_conditional_branch(condition, ok_block, panic_block)
+            .unwrap();
+
+        self.builder.position_at_end(panic_block);
+        self.build_panic(span, message, args);
+        self.builder.position_at_end(ok_block);
+    }
+
+    /// Prints `location: message` with `printf` and exits with status 101.
+    /// Without an operating system to print to, it traps instead.
+    fn build_panic(&mut self, span: Span, message: &str, args: &[BasicMetadataValueEnum<'v>]) {
+        if matches!(self.queries.target().os, Os::None) {
+            let trap = Intrinsic::find("llvm.trap")
+                .unwrap()
+                .get_declaration(&self.module, &[])
+                .unwrap();
+            self.builder.build_call(trap, &[], "").unwrap();
+            self.builder.build_unreachable().unwrap();
+            return;
+        }
+
+        let location = self
+            .queries
+            .source_map()
+            .location(span)
+            .unwrap_or_else(|| format!("<file {}>:{}", span.file(), span.start()));
+        let format = format!("{}: {}\n", location, message);
+        let format = LLVM_CONTEXT.const_string(format.as_bytes(), true);
+        let global = self.module.add_global(format.get_type(), None, "panic_msg");
+        global.set_initializer(&format);
+        global.set_linkage(Linkage::Private);
+        global.set_constant(true);
+
+        let ptr_ty = LLVM_CONTEXT.ptr_type(AddressSpace::default());
+        let i32_ty = LLVM_CONTEXT.i32_type();
+        let printf_ty = i32_ty.fn_type(&[ptr_ty.into()], true);
+        let exit_ty = LLVM_CONTEXT.void_type().fn_type(&[i32_ty.into()], false);
+        // A program may declare these itself, with other parameter types.
+        let printf = self
+            .module
+            .get_function("printf")
+            .unwrap_or_else(|| self.module.add_function("printf", printf_ty, None));
+        let exit = self
+            .module
+            .get_function("exit")
+            .unwrap_or_else(|| self.module.add_function("exit", exit_ty, None));
+
+        let mut printf_args = vec![global.as_pointer_value().into()];
+        printf_args.extend_from_slice(args);
+        let printf = printf.as_global_value().as_pointer_value();
+        self.builder
+            .build_indirect_call(printf_ty, printf, &printf_args, "")
+            .unwrap();
+        let exit = exit.as_global_value().as_pointer_value();
+        self.builder
+            .build_indirect_call(exit_ty, exit, &[i32_ty.const_int(101, false).into()], "")
+            .unwrap();
+        self.builder.build_unreachable().unwrap();
+    }
+}
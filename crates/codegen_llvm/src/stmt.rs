@@ -1,25 +1,33 @@
 use ast::{
-    Assign,
+    Assign, BinaryOp, Match, Pattern,
     visitor::{ExpVisitor, StatementVisitor},
 };
-use inkwell::{
-    IntPredicate,
-    values::{AnyValue, InstructionOpcode},
-};
+use inkwell::values::{AnyValue, InstructionOpcode};
 
 use crate::{
     LLVM_CONTEXT, VisitorCtx,
+    expr::int_predicate,
     info::{Symbol, TypeKind, Value},
 };
 
 impl<'v> StatementVisitor<Value<'v>> for VisitorCtx<'v> {
     fn visit_assign(&mut self, assign: &Assign) -> Value<'v> {
         let lhs = self.visit_left_value(assign.lhs);
-        let rhs = self.visit_right_value(assign.rhs);
+        let rhs = match &assign.op {
+            Some(_) => self.visit_right_value(assign.rhs),
+            None => self.visit_stored(assign.rhs, lhs.type_().derefed()),
+        };
 
         if let Value::Unit = rhs {
             return Value::Unit;
         }
+        let rhs = match &assign.op {
+            Some(op) => {
+                let current = self.get_right_value(lhs.clone());
+                self.visit_binary(op, current, rhs, &assign.span)
+            }
+            None => rhs,
+        };
 
         rhs.build_store(lhs, &self.builder);
 
@@ -60,6 +68,7 @@ impl<'v> StatementVisitor<Value<'v>> for VisitorCtx<'v> {
 
         self.builder.position_at_end(then_block);
         let then_value = self.visit_block(&if_exp.then_branch);
+        let then_incoming = self.open_block();
         self.build_branch(end_block);
 
         self.builder.position_at_end(else_block);
@@ -72,19 +81,158 @@ impl<'v> StatementVisitor<Value<'v>> for VisitorCtx<'v> {
         } else {
             (false, Value::Unit)
         };
+        let else_incoming = self.open_block();
         self.build_branch(end_block);
 
         self.builder.position_at_end(end_block);
-        if else_present && !matches!(then_value, Value::Unit) {
-            let phi = self
-                .builder
-                .build_phi(then_value.type_(), "if_result")
+        // A branch that returns or breaks leaves no value behind.
+        let incoming = [(then_value, then_incoming), (else_value, else_incoming)]
+            .into_iter()
+            .filter_map(|(value, block)| Some((value, block?)))
+            .filter(|(value, _)| !matches!(value, Value::Unit))
+            .collect::<Vec<_>>();
+        match incoming.first() {
+            Some((first, _)) if else_present => {
+                let phi = self.builder.build_phi(first.type_(), "if_result").unwrap();
+                for (value, block) in &incoming {
+                    phi.add_incoming(&[(value, *block)]);
+                }
+                Value::new_from(phi.as_any_value_enum(), first.type_())
+            }
+            _ => Value::Unit,
+        }
+    }
+
+    fn visit_match(&mut self, match_: &Match) -> Value<'v> {
+        let value = self.visit_right_value(match_.exp);
+        let current_fn = self.current_fn.as_fn();
+
+        let (discriminant, payload) = match &value {
+            Value::Enum { ty, .. } => {
+                let TypeKind::Enum {
+                    ty: struct_ty,
+                    variants,
+                } = ty
+                else {
+                    unreachable!()
+                };
+                let alloca = self.create_entry_bb_alloca_with_init("", value.clone());
+                let tag_ptr = self
+                    .builder
+                    .build_struct_gep(*struct_ty, alloca.as_ptr(), 0, "")
+                    .unwrap();
+                let tag = self
+                    .builder
+                    .build_load(LLVM_CONTEXT.i32_type(), tag_ptr, "tag")
+                    .unwrap()
+                    .into_int_value();
+                let payload_ptr = self
+                    .builder
+                    .build_struct_gep(*struct_ty, alloca.as_ptr(), 1, "")
+                    .unwrap();
+                (tag, Some((payload_ptr, variants.clone())))
+            }
+            _ => (value.as_int(), None),
+        };
+
+        let default_block = LLVM_CONTEXT.append_basic_block(current_fn, "match_default");
+        let end_block = LLVM_CONTEXT.append_basic_block(current_fn, "match_end");
+
+        let mut cases = Vec::new();
+        let mut covered = Vec::new();
+        let mut arms = Vec::new();
+        let mut has_default = false;
+        for arm in match_.arms.iter() {
+            // Cases are told apart by the bits of the pattern in the type of
+            // the discriminant.
+            let (case, case_value) = match &arm.pattern {
+                Pattern::Wildcard(_) => {
+                    arms.push((arm, default_block));
+                    has_default = true;
+                    break;
+                }
+                Pattern::Number(number, negative) => {
+                    let ty = discriminant.get_type();
+                    let mut bits = if *negative {
+                        0u128.wrapping_sub(number.num)
+                    } else {
+                        number.num
+                    };
+                    if ty.get_bit_width() < 128 {
+                        bits &= (1 << ty.get_bit_width()) - 1;
+                    }
+                    let words = [bits as u64, (bits >> 64) as u64];
+                    (ty.const_int_arbitrary_precision(&words), bits)
+                }
+                Pattern::Variant(path, _, _) => {
+                    let (_, variants) = payload.as_ref().unwrap();
+                    let name = path.path.last().unwrap();
+                    let tag = variants.iter().position(|(v, _)| v == name).unwrap();
+                    (
+                        LLVM_CONTEXT.i32_type().const_int(tag as u64, false),
+                        tag as u128,
+                    )
+                }
+            };
+            if covered.contains(&case_value) {
+                continue;
+            }
+            covered.push(case_value);
+
+            let block = LLVM_CONTEXT.append_basic_block(current_fn, "match_arm");
+            cases.push((case, block));
+            arms.push((arm, block));
+        }
+
+        self.build_terminator(&self.builder, |builder| {
+            builder
+                .build_switch(discriminant, default_block, &cases)
                 .unwrap();
-            phi.add_incoming(&[(&then_value, then_block), (&else_value, else_block)]);
-            Value::new_from(phi.as_any_value_enum(), then_value.type_())
-        } else {
-            Value::Unit
+        });
+
+        if !has_default {
+            self.builder.position_at_end(default_block);
+            self.builder.build_unreachable().unwrap();
+        }
+
+        let mut result = None;
+        for (arm, block) in arms {
+            self.builder.position_at_end(block);
+            self.symbols.push_scope();
+            if let Pattern::Variant(path, Some(binding), _) = &arm.pattern {
+                let (payload_ptr, variants) = payload.as_ref().unwrap();
+                let name = path.path.last().unwrap();
+                let (_, payload_ty) = variants.iter().find(|(v, _)| v == name).unwrap();
+                self.symbols.push(Symbol::Var(
+                    binding.clone(),
+                    Value::Alloca {
+                        value: *payload_ptr,
+                        value_ty: payload_ty.clone().unwrap(),
+                    },
+                ));
+            }
+            let value = self.visit_right_value(arm.body);
+            self.symbols.pop_scope();
+
+            let terminated = self
+                .builder
+                .get_insert_block()
+                .unwrap()
+                .get_terminator()
+                .is_some();
+            if !value.is_unit() && !terminated {
+                let slot = result.get_or_insert_with(|| {
+                    self.create_entry_bb_alloca("match_result", value.type_())
+                });
+                value.build_store(slot.clone(), &self.builder);
+            }
+            self.build_branch(end_block);
         }
+
+        self.builder.position_at_end(end_block);
+        result
+            .map(|slot| slot.as_right_value(&self.builder))
+            .unwrap_or(Value::Unit)
     }
 
     fn visit_loop(&mut self, loop_: &ast::Loop) -> Value<'v> {
@@ -96,24 +244,27 @@ impl<'v> StatementVisitor<Value<'v>> for VisitorCtx<'v> {
         self.builder.build_unconditional_branch(loop_block).unwrap();
 
         self.builder.position_at_end(loop_block);
-        self.push_loop(loop_block, end_block);
+        self.push_loop(&loop_.label, loop_block, end_block);
         self.visit_block(&loop_.body);
-        self.pop_loop_block();
+        let result = self.pop_loop_block();
         self.build_branch(loop_block);
 
         self.builder.position_at_end(end_block);
-        Value::Unit
+        result
+            .map(|slot| slot.as_right_value(&self.builder))
+            .unwrap_or(Value::Unit)
     }
 
     fn visit_for(&mut self, for_: &ast::For) -> Value<'v> {
         let current_fn = self.current_fn.as_fn();
 
         let start = self.visit_right_value(for_.start);
+        let signed = start.is_signed();
         let end = self.visit_right_value(for_.end);
         let step = for_
             .step
             .map(|step| self.visit_right_value(step))
-            .unwrap_or(TypeKind::new_int(32).const_int(1));
+            .unwrap_or(start.type_().const_int(1));
 
         let alloca = self.create_entry_bb_alloca_with_init(&for_.var, start);
         self.symbols
@@ -131,7 +282,7 @@ impl<'v> StatementVisitor<Value<'v>> for VisitorCtx<'v> {
         let condition = self
             .builder
             .build_int_compare(
-                IntPredicate::SLT,
+                int_predicate(&BinaryOp::Lt, signed),
                 alloca.as_right_value(&self.builder).as_int(),
                 end.as_int(),
                 "",
@@ -155,7 +306,7 @@ impl<'v> StatementVisitor<Value<'v>> for VisitorCtx<'v> {
         self.build_branch(condition_block);
 
         self.builder.position_at_end(loop_block);
-        self.push_loop(update_block, end_block);
+        self.push_loop(&for_.label, update_block, end_block);
         self.visit_block(&for_.body);
         self.pop_loop_block();
         self.build_branch(update_block);
@@ -180,7 +331,7 @@ impl<'v> StatementVisitor<Value<'v>> for VisitorCtx<'v> {
         self.build_conditional_branch(condition.as_int(), loop_block, end_block);
 
         self.builder.position_at_end(loop_block);
-        self.push_loop(condition_block, end_block);
+        self.push_loop(&while_.label, condition_block, end_block);
         self.visit_block(&while_.body);
         self.pop_loop_block();
         self.build_branch(condition_block);
@@ -190,15 +341,30 @@ impl<'v> StatementVisitor<Value<'v>> for VisitorCtx<'v> {
         Value::Unit
     }
 
-    fn visit_break(&mut self, _span: ast::Span) -> Value<'v> {
-        let exit = self.current_loop().unwrap().1;
-        self.builder.build_unconditional_branch(exit).unwrap();
+    fn visit_break(&mut self, break_: &ast::Break) -> Value<'v> {
+        let value = break_
+            .value
+            .map(|value| self.visit_right_value(value))
+            .unwrap_or(Value::Unit);
+        if !value.is_unit() && self.open_block().is_some() {
+            let slot = match self.find_loop(&break_.label).result.clone() {
+                Some(slot) => slot,
+                None => {
+                    let slot = self.create_entry_bb_alloca("loop_result", value.type_());
+                    self.find_loop(&break_.label).result = Some(slot.clone());
+                    slot
+                }
+            };
+            value.build_store(slot, &self.builder);
+        }
+        let exit = self.find_loop(&break_.label).exit;
+        self.build_branch(exit);
         Value::Unit
     }
 
-    fn visit_continue(&mut self, _span: ast::Span) -> Value<'v> {
-        let entry = self.current_loop().unwrap().0;
-        self.builder.build_unconditional_branch(entry).unwrap();
+    fn visit_continue(&mut self, continue_: &ast::Continue) -> Value<'v> {
+        let entry = self.find_loop(&continue_.label).entry;
+        self.build_branch(entry);
         Value::Unit
     }
 }
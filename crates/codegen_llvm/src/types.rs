@@ -1,11 +1,10 @@
-use std::{collections::HashMap, sync::Arc};
+use std::sync::Arc;
 
-use ast::{Exp, ExpId, StructType, Type, TypeEnum, UnaryOp};
-use const_eval::queries::CONST_EVAL_PROVIDER;
-use inkwell::types::BasicType;
+use ast::{EnumType, Exp, ExpId, InterfaceType, StructType, Type, TypeEnum, UnaryOp};
+use const_eval::{Bindings, queries::CONST_EVAL_PROVIDER};
 use query::QueryContext;
 
-use crate::{LLVM_CONTEXT, VisitorCtx, info::TypeKind};
+use crate::{LLVMBackend, VisitorCtx, info::TypeKind};
 
 pub(crate) fn const_eval_type_to_llvm_type(
     ctx: Arc<QueryContext>,
@@ -14,9 +13,38 @@ pub(crate) fn const_eval_type_to_llvm_type(
     match ty.as_ref() {
         const_eval::TypeKind::Primary(primary) => get_llvm_type(ctx, primary),
         const_eval::TypeKind::Ptr(primary) => const_eval_type_to_llvm_type(ctx, primary).new_ptr(),
+        const_eval::TypeKind::Array(element, len) => {
+            const_eval_type_to_llvm_type(ctx, element).new_array(*len)
+        }
+        const_eval::TypeKind::Slice(element) => const_eval_type_to_llvm_type(ctx.clone(), element)
+            .new_slice(ctx.target().pointer_width()),
+        const_eval::TypeKind::Structure(_, fields, layout, namespace) => {
+            let fields = fields
+                .iter()
+                .map(|(name, ty)| (name.clone(), const_eval_type_to_llvm_type(ctx.clone(), ty)))
+                .collect();
+            TypeKind::new_struct(
+                fields,
+                *layout,
+                namespace.clone(),
+                ctx.target().pointer_width(),
+            )
+        }
     }
 }
 
+pub(crate) fn get_llvm_type_with_bindings(
+    ctx: Arc<QueryContext>,
+    ty: ExpId,
+    bindings: &Bindings,
+) -> TypeKind<'static> {
+    if bindings.is_empty() {
+        return get_llvm_type_from_exp(ctx, ty);
+    }
+    let ty = const_eval::eval_exp(ctx.clone(), ty, bindings).as_type();
+    const_eval_type_to_llvm_type(ctx, &ty)
+}
+
 pub(crate) fn get_llvm_type_from_exp(ctx: Arc<QueryContext>, ty: ExpId) -> TypeKind<'static> {
     let ast_ctx = ctx.ast_ctx();
     let ty_body = ast_ctx.exp(ty);
@@ -37,38 +65,89 @@ pub(crate) fn get_llvm_type_from_exp(ctx: Arc<QueryContext>, ty: ExpId) -> TypeK
             }
         }
         Exp::Unary(UnaryOp::Ptr, value, _) => get_llvm_type_from_exp(ctx, *value).new_ptr(),
-        _ => unreachable!(),
+        Exp::Tuple(tuple) => {
+            let elements = tuple
+                .elements
+                .iter()
+                .map(|element| get_llvm_type_from_exp(ctx.clone(), *element))
+                .collect();
+            TypeKind::new_tuple(elements, ctx.target().pointer_width())
+        }
+        _ => {
+            let ty = const_eval::eval_exp(ctx.clone(), ty, &Bindings::new()).as_type();
+            const_eval_type_to_llvm_type(ctx, &ty)
+        }
     }
 }
 
 pub(crate) fn get_llvm_type(ctx: Arc<QueryContext>, ty: &Type) -> TypeKind<'static> {
     match &ty.kind {
-        TypeEnum::Signed(width) | TypeEnum::Unsigned(width) => TypeKind::new_int(*width),
-        TypeEnum::Array(inner, len) => get_llvm_type_from_exp(ctx.clone(), *inner).new_array(*len),
+        TypeEnum::Signed(width) => TypeKind::new_int(*width, true),
+        TypeEnum::Unsigned(width) => TypeKind::new_int(*width, false),
+        TypeEnum::Slice(element) => {
+            get_llvm_type_from_exp(ctx.clone(), *element).new_slice(ctx.target().pointer_width())
+        }
         TypeEnum::Unit => TypeKind::new_unit(),
-        TypeEnum::Structure(StructType { fields, .. }) => {
-            let field_ids = fields
-                .keys()
-                .cloned()
-                .enumerate()
-                .collect::<HashMap<_, _>>();
-            let field_types = fields
-                .values()
-                .map(|ty| get_llvm_type_from_exp(ctx.clone(), *ty))
-                .collect::<Vec<_>>();
-            let fields = field_types
+        TypeEnum::Type => unreachable!("types only exist at compile time"),
+        TypeEnum::Structure(StructType {
+            fields,
+            namespace,
+            layout,
+            ..
+        }) => {
+            let fields = fields
                 .iter()
-                .map(|ty| ty.as_basic_type_enum())
-                .collect::<Vec<_>>();
-            let ty = LLVM_CONTEXT.struct_type(&fields, false);
-
-            TypeKind::Structure {
-                ty,
-                field_ids,
-                field_types,
-            }
+                .map(|(name, ty)| (name.clone(), get_llvm_type_from_exp(ctx.clone(), *ty)))
+                .collect();
+            TypeKind::new_struct(
+                fields,
+                *layout,
+                namespace.clone(),
+                ctx.target().pointer_width(),
+            )
+        }
+        TypeEnum::Enum(EnumType { variants, .. }) => {
+            let variants = variants
+                .iter()
+                .map(|variant| {
+                    let payload = variant
+                        .payload
+                        .map(|ty| get_llvm_type_from_exp(ctx.clone(), ty));
+                    (variant.name.clone(), payload)
+                })
+                .collect();
+            TypeKind::new_enum(variants, ctx.target().pointer_width())
+        }
+        TypeEnum::Usize => TypeKind::new_int(ctx.target().pointer_width(), false),
+        TypeEnum::Isize => TypeKind::new_int(ctx.target().pointer_width(), true),
+        TypeEnum::Float(width) => TypeKind::new_float(*width),
+        TypeEnum::Bool => TypeKind::new_int(1, false),
+        TypeEnum::Interface(_) => unreachable!("interfaces only exist at compile time"),
+        TypeEnum::Dyn(interface) => {
+            let interface =
+                const_eval::eval_exp(ctx.clone(), *interface, &Bindings::new()).as_type();
+            let const_eval::TypeKind::Primary(interface) = interface.as_ref() else {
+                unreachable!()
+            };
+            let TypeEnum::Interface(InterfaceType { methods, name, .. }) = &interface.kind else {
+                unreachable!()
+            };
+            let ast_ctx = ctx.ast_ctx();
+            let methods = methods
+                .iter()
+                .map(|(method, proto)| {
+                    let Exp::ProtoDef(proto) = ast_ctx.exp(*proto) else {
+                        unreachable!()
+                    };
+                    let (fn_ty, ret_ty) =
+                        LLVMBackend::llvm_fn_sig(ctx.clone(), &proto.params, &proto.return_type);
+                    (method.clone(), fn_ty, ret_ty)
+                })
+                .collect();
+            TypeKind::new_dyn(name.clone().unwrap_or_default(), methods)
         }
-        TypeEnum::Usize | TypeEnum::Isize => TypeKind::new_int(ctx.target().pointer_width()),
+        // Only appears behind the `self` pointer of interface methods.
+        TypeEnum::SelfType => TypeKind::new_int(8, false),
     }
 }
 
@@ -0,0 +1,133 @@
+use std::{collections::HashMap, iter::zip};
+
+use ast::{Call, FieldAccess, FunctionDef, Span, StructType, TypeEnum, visitor::ExpVisitor};
+
+use crate::{
+    ConstEvalContext, DEPTH_LIMIT, EvalError, Flow, TypeKind, Value, ValueKind, is_type_function,
+    queries::TRY_CONST_EVAL_PROVIDER,
+};
+
+impl ConstEvalContext {
+    pub(crate) fn call_value(&mut self, func: Value, call: &Call) -> Value {
+        match func.kind() {
+            ValueKind::EnumCtor(ty, tag) => match self.eval(call.args[0]) {
+                Some(payload) => Value::new_enum(ty, tag, Some(payload)),
+                None => Value::new_unit(),
+            },
+            ValueKind::Function(func) if is_type_function(&self.ctx, &func) => {
+                self.eval_type_function(func, &call.args)
+            }
+            ValueKind::Function(func) => match self.eval_args(&call.args) {
+                Some(args) => self.call_function(&func, args, call.span),
+                None => Value::new_unit(),
+            },
+            ValueKind::Proto(_) => self.fail(EvalError::ProtoCall, call.span),
+            _ => self.fail(
+                EvalError::Invalid("Only functions can be called"),
+                call.span,
+            ),
+        }
+    }
+
+    pub(crate) fn call_member(&mut self, field_access: &FieldAccess, call: &Call) -> Value {
+        let Some(mut receiver) = self.locate(field_access.lhs) else {
+            return Value::new_unit();
+        };
+        let Some(mut structure) = self.read(&receiver, field_access.span) else {
+            return Value::new_unit();
+        };
+        if let ValueKind::Pointer(target) = structure.kind() {
+            receiver = target;
+            structure = match self.read(&receiver, field_access.span) {
+                Some(structure) => structure,
+                None => return Value::new_unit(),
+            };
+        }
+        let ValueKind::Structure(ty, fields) = structure.kind() else {
+            return self.fail(EvalError::Unsupported("Calls through `dyn`"), call.span);
+        };
+        if let Some(func) = fields.get(&field_access.field) {
+            return self.call_value(func.clone(), call);
+        }
+
+        let method = format!(
+            "{}::{}",
+            namespace(&ty).unwrap_or_default(),
+            field_access.field
+        );
+        let def_id = self.ctx.lookup_def_id(&method).unwrap();
+        let method = match self
+            .ctx
+            .query_cached(&TRY_CONST_EVAL_PROVIDER, def_id)
+            .unwrap()
+        {
+            Ok(method) => method.as_function(),
+            Err(_) => return self.fail(EvalError::ErroneousConst(method), field_access.span),
+        };
+        let self_ty = self
+            .visit_right_value(method.params[0].param_type)
+            .as_type();
+        let receiver = match self_ty.as_ref() {
+            TypeKind::Ptr(_) => Value::new_pointer(receiver, ty),
+            _ => structure,
+        };
+        let Some(args) = self.eval_args(&call.args) else {
+            return Value::new_unit();
+        };
+        let args = std::iter::once(receiver).chain(args).collect();
+        self.call_function(&method, args, call.span)
+    }
+
+    pub(crate) fn call_function(
+        &mut self,
+        func: &FunctionDef,
+        args: Vec<Value>,
+        span: Span,
+    ) -> Value {
+        if !self.step(span) {
+            return Value::new_unit();
+        }
+        if self.depth == DEPTH_LIMIT {
+            return self.fail(EvalError::RecursionLimit(DEPTH_LIMIT), span);
+        }
+
+        let scopes = std::mem::replace(&mut self.scopes, vec![HashMap::new()]);
+        let bindings = std::mem::take(&mut self.bindings);
+        let frame = self.next_slot;
+        self.depth += 1;
+        for (param, arg) in zip(&func.params, args) {
+            self.declare(&param.name, arg);
+        }
+
+        let value = self.eval_block(&func.block);
+        let value = match self.flow.take() {
+            Some(Flow::Return(value)) => value,
+            Some(error @ Flow::Error(..)) => {
+                self.flow = Some(error);
+                Value::new_unit()
+            }
+            _ => value,
+        };
+
+        self.depth -= 1;
+        self.slots.split_off(&frame);
+        self.bindings = bindings;
+        self.scopes = scopes;
+        value
+    }
+
+    fn eval_args(&mut self, args: &[ast::ExpId]) -> Option<Vec<Value>> {
+        args.iter().map(|arg| self.eval(*arg)).collect()
+    }
+}
+
+fn namespace(ty: &TypeKind) -> Option<String> {
+    match ty {
+        TypeKind::Primary(primary) => match &primary.kind {
+            TypeEnum::Structure(StructType { namespace, .. }) => namespace.clone(),
+            _ => None,
+        },
+        TypeKind::Structure(.., namespace) => namespace.clone(),
+        _ => None,
+    }
+}
@@ -0,0 +1,31 @@
+use thiserror::Error;
+
+#[derive(Debug, Clone, Error)]
+pub enum EvalError {
+    #[error("`proto` functions can't be called at compile time.")]
+    ProtoCall,
+    #[error("Inline assembly can't be run at compile time.")]
+    InlineAsm,
+    #[error("{0} can't be evaluated at compile time.")]
+    Unsupported(&'static str),
+    #[error("{0}.")]
+    Invalid(&'static str),
+    #[error("Compile-time evaluation took more than {0} steps.")]
+    StepLimit(u64),
+    #[error("Compile-time calls are nested more than {0} deep.")]
+    RecursionLimit(usize),
+    #[error("Index {0} is out of bounds for length {1}.")]
+    IndexOutOfBounds(i128, usize),
+    #[error("The local this pointer points to no longer exists.")]
+    DanglingPointer,
+    #[error("A pointer to a compile-time local can't be part of a const.")]
+    PointerInConst,
+    #[error("`{0}` overflows {1}.")]
+    Overflow(String, String),
+    #[error("Division by zero.")]
+    DivisionByZero,
+    #[error("Shifting {1} by {0} bits is out of range.")]
+    ShiftOutOfRange(String, String),
+    #[error("Evaluating `{0}` failed.")]
+    ErroneousConst(String),
+}
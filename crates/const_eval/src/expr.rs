@@ -1,11 +1,23 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, RwLock},
+};
 
 use ast::{
-    Array, BinaryOp, Block, Call, Deref, FunctionDef, Index, Number, Span, Type, UnaryOp, Var,
+    Array, BinaryOp, Block, Builtin, BuiltinCall, Call, Deref, EnumType, EnumVariant, Exp, ExpId,
+    Float, FunctionDef, GetAddr, Index, Number, Span, StructType, Type, TypeEnum, UnaryOp, Var,
     visitor::ExpVisitor,
 };
 
-use crate::{ConstEvalContext, TypeKind, ValueKind, info::Value, queries::CONST_EVAL_PROVIDER};
+use crate::{
+    Bindings, ConstEvalContext, EvalError, TypeKind, ValueKind, defining_name, info::Value,
+    int::Int, literal_scope, queries::TRY_CONST_EVAL_PROVIDER,
+};
+
+/// Structures returned by type-returning functions, keyed by their names, so
+/// calling one with the same arguments always gives the identical type.
+static INTERNED_TYPES: LazyLock<RwLock<HashMap<String, Arc<TypeKind>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
 
 impl ExpVisitor<Value> for ConstEvalContext {
     fn ast_ctx(&self) -> Arc<ast::AstContext> {
@@ -20,44 +32,150 @@ impl ExpVisitor<Value> for ConstEvalContext {
         left_value
     }
 
-    fn visit_array(&mut self, _array: &Array) -> Value {
-        unimplemented!()
-    }
-
-    fn visit_binary(&mut self, op: &BinaryOp, lhs_val: Value, rhs_val: Value, _: &Span) -> Value {
-        let lhs = lhs_val.as_int();
-        let rhs = rhs_val.as_int();
-        let mut result = Value::new_int(match op {
-            BinaryOp::Add => lhs + rhs,
-            BinaryOp::Sub => lhs - rhs,
-            BinaryOp::Mul => lhs * rhs,
-            BinaryOp::Div => lhs / rhs,
-            BinaryOp::Mod => lhs % rhs,
-            BinaryOp::And => lhs & rhs,
-            BinaryOp::Or => lhs | rhs,
-            BinaryOp::LShift => lhs << rhs,
-            BinaryOp::RShift => lhs >> rhs,
-            BinaryOp::Le => (lhs <= rhs) as i64,
-            BinaryOp::Lt => (lhs < rhs) as i64,
-            BinaryOp::Ge => (lhs >= rhs) as i64,
-            BinaryOp::Gt => (lhs > rhs) as i64,
-            BinaryOp::Eq => (lhs == rhs) as i64,
-            BinaryOp::Ne => (lhs != rhs) as i64,
-        });
-        result.set_type(lhs_val.ty());
-        result
+    fn visit_array(&mut self, array: &Array) -> Value {
+        match array {
+            Array::List(values, _) => {
+                let Some(elements) = values
+                    .iter()
+                    .map(|value| self.eval(*value))
+                    .collect::<Option<Vec<_>>>()
+                else {
+                    return Value::new_unit();
+                };
+                let ty = Arc::new(TypeKind::Array(elements[0].ty(), elements.len() as u32));
+                Value::new_array(ty, elements)
+            }
+            Array::Template(value, len, _) => {
+                let (Some(value), Some(len)) = (self.eval(*value), self.eval(len.exp)) else {
+                    return Value::new_unit();
+                };
+                let len = len.as_int() as u32;
+                if let ValueKind::Type(element) = value.kind() {
+                    return Value::new_type(Arc::new(TypeKind::Array(element, len)));
+                }
+                let ty = Arc::new(TypeKind::Array(value.ty(), len));
+                Value::new_array(ty, vec![value; len as usize])
+            }
+        }
+    }
+
+    /// Integers are computed in the width and signedness of their type, and
+    /// overflow, division by zero and shifts by at least the width are
+    /// errors.
+    fn visit_binary(
+        &mut self,
+        op: &BinaryOp,
+        lhs_val: Value,
+        rhs_val: Value,
+        span: &Span,
+    ) -> Value {
+        if self.flow.is_some() {
+            return Value::new_unit();
+        }
+        if let ValueKind::Float(lhs) = lhs_val.kind() {
+            return fold_float(op, lhs, rhs_val.as_float(), lhs_val.ty());
+        }
+        let ty = lhs_val.ty();
+        let int_ty = self.int_type(&ty).unwrap();
+        let rhs_ty = self.int_type(&rhs_val.ty()).unwrap();
+        let lhs = int_ty.value_of(lhs_val.as_int());
+        let rhs = rhs_ty.value_of(rhs_val.as_int());
+        let result = match op {
+            BinaryOp::Div | BinaryOp::Mod if rhs.is_zero() => {
+                return self.fail(EvalError::DivisionByZero, *span);
+            }
+            BinaryOp::LShift | BinaryOp::RShift if !rhs.is_below(int_ty.width()) => {
+                return self.fail(
+                    EvalError::ShiftOutOfRange(rhs.to_string(), ty.to_string()),
+                    *span,
+                );
+            }
+            // The bits shifted out are dropped.
+            BinaryOp::LShift => {
+                Int::fold(op, lhs, rhs).map(|result| int_ty.value_of(int_ty.wrap(result)))
+            }
+            BinaryOp::Le => return Value::new_bool(lhs <= rhs),
+            BinaryOp::Lt => return Value::new_bool(lhs < rhs),
+            BinaryOp::Ge => return Value::new_bool(lhs >= rhs),
+            BinaryOp::Gt => return Value::new_bool(lhs > rhs),
+            BinaryOp::Eq => return Value::new_bool(lhs == rhs),
+            BinaryOp::Ne => return Value::new_bool(lhs != rhs),
+            BinaryOp::And | BinaryOp::Or => unreachable!(),
+            _ => Int::fold(op, lhs, rhs),
+        };
+        match result.filter(|&result| int_ty.fits(result)) {
+            Some(result) => {
+                let mut result = Value::new_int(int_ty.wrap(result));
+                result.set_type(ty);
+                result
+            }
+            None => {
+                let operation = format!("{} {} {}", lhs, op, rhs);
+                self.fail(EvalError::Overflow(operation, ty.to_string()), *span)
+            }
+        }
+    }
+
+    fn visit_logical(&mut self, op: &BinaryOp, lhs: ExpId, rhs: ExpId, span: &Span) -> Value {
+        let Some(lhs) = self.eval(lhs) else {
+            return Value::new_unit();
+        };
+        let lhs = lhs.as_int() != 0;
+        let short_circuits = match op {
+            BinaryOp::And => !lhs,
+            BinaryOp::Or => lhs,
+            _ => {
+                return self.fail(
+                    EvalError::Invalid("Only `&&` and `||` short-circuit"),
+                    *span,
+                );
+            }
+        };
+        if short_circuits {
+            return Value::new_bool(lhs);
+        }
+        match self.eval(rhs) {
+            Some(rhs) => Value::new_bool(rhs.as_int() != 0),
+            None => Value::new_unit(),
+        }
+    }
+
+    fn visit_block(&mut self, block: &Block) -> Value {
+        self.eval_block(block)
     }
 
-    fn visit_block(&mut self, _block: &Block) -> Value {
-        unimplemented!()
+    fn visit_call(&mut self, call: &Call) -> Value {
+        if let Exp::FieldAccess(field_access) = self.ctx.ast_ctx().exp(call.func) {
+            return self.call_member(field_access, call);
+        }
+        match self.eval(call.func) {
+            Some(func) => self.call_value(func, call),
+            None => Value::new_unit(),
+        }
     }
 
-    fn visit_call(&mut self, _call: &Call) -> Value {
-        unimplemented!()
+    fn visit_deref(&mut self, deref: &Deref) -> Value {
+        let Some(pointer) = self.eval(deref.exp) else {
+            return Value::new_unit();
+        };
+        let ValueKind::Pointer(place) = pointer.kind() else {
+            return self.fail(
+                EvalError::Invalid("Only pointers can be dereferenced"),
+                deref.span,
+            );
+        };
+        self.read(&place, deref.span)
+            .unwrap_or_else(Value::new_unit)
     }
 
-    fn visit_deref(&mut self, _deref: &Deref) -> Value {
-        unimplemented!()
+    fn visit_get_addr(&mut self, get_addr: &GetAddr) -> Value {
+        let Some(place) = self.locate(get_addr.exp) else {
+            return Value::new_unit();
+        };
+        match self.read(&place, get_addr.span) {
+            Some(value) => Value::new_pointer(place, value.ty()),
+            None => Value::new_unit(),
+        }
     }
 
     fn visit_proto(&mut self, proto_def: &ast::ProtoDef) -> Value {
@@ -68,43 +186,119 @@ impl ExpVisitor<Value> for ConstEvalContext {
         Value::new_function(Arc::new(func.clone()))
     }
 
-    fn visit_index(&mut self, _index: &Index) -> Value {
-        unimplemented!()
+    fn visit_index(&mut self, index: &Index) -> Value {
+        self.index_place(index)
+            .and_then(|place| self.read(&place, index.span))
+            .unwrap_or_else(Value::new_unit)
+    }
+
+    fn visit_slice(&mut self, slice: &ast::Slice) -> Value {
+        self.fail(EvalError::Unsupported("Slices"), slice.span)
     }
 
     fn visit_var(&mut self, var: &Var) -> Value {
         let name = var.path.path.join("::");
-        let def_id = self.ctx.lookup_def_id(name).unwrap();
-        self.ctx.query_cached(&CONST_EVAL_PROVIDER, def_id).unwrap()
+        if let Some(slot) = self.local(&name) {
+            return self.slots[&slot].clone();
+        }
+        if let Some(value) = self.bindings.get(&name) {
+            return value.clone();
+        }
+        let def_id = self.ctx.lookup_def_id(&name).unwrap();
+        match self
+            .ctx
+            .query_cached(&TRY_CONST_EVAL_PROVIDER, def_id)
+            .unwrap()
+        {
+            Ok(value) => value,
+            Err(_) => self.fail(EvalError::ErroneousConst(name), var.span),
+        }
     }
 
     fn visit_number(&mut self, number: &Number) -> Value {
-        let mut value = Value::new_int(number.num as i64);
-        if let Some(ty) = number.ty.clone() {
-            value.set_type(TypeKind::new(Arc::new(Type {
+        self.number(number, false, number.span)
+    }
+
+    fn visit_negative_number(&mut self, number: &Number, span: &Span) -> Value {
+        self.number(number, true, *span)
+    }
+
+    fn visit_float(&mut self, float: &Float) -> Value {
+        let mut value = Value::new_float(float.num);
+        if let Some(ty) = float.ty.clone() {
+            let ty = TypeKind::new(Arc::new(Type {
                 kind: ty,
                 span: Span::default(),
-            })));
+            }));
+            value = Value::new_float(round_float(float.num, &ty));
+            value.set_type(ty);
         }
         value
     }
 
-    fn visit_str(&mut self, _string: &str) -> Value {
-        unimplemented!()
+    /// Has no span to report, so the error takes that of the enclosing
+    /// expression, see [`ConstEvalContext::eval`].
+    fn visit_str(&mut self, _string: &[u8]) -> Value {
+        self.fail(EvalError::Unsupported("String literals"), Span::default())
     }
 
-    fn visit_unary(&mut self, op: &UnaryOp, value: Value, _: &Span) -> Value {
+    fn visit_bool(&mut self, value: bool) -> Value {
+        Value::new_bool(value)
+    }
+
+    fn visit_unary(&mut self, op: &UnaryOp, value: Value, span: &Span) -> Value {
+        if self.flow.is_some() {
+            return Value::new_unit();
+        }
         if op == &UnaryOp::Ptr {
             return Value::new_type(value.as_type().new_ptr());
         }
-        let int_value = value.as_int();
-        let mut result = Value::new_int(match op {
-            UnaryOp::Neg => -int_value,
-            UnaryOp::Not => !int_value,
+        if let ValueKind::Float(float) = value.kind() {
+            let mut result = Value::new_float(match op {
+                UnaryOp::Neg => -float,
+                UnaryOp::Pos => float,
+                _ => {
+                    return self.fail(
+                        EvalError::Invalid("Only `-` and `+` apply to floats"),
+                        *span,
+                    );
+                }
+            });
+            result.set_type(value.ty());
+            return result;
+        }
+        if let TypeKind::Primary(ty) = value.ty().as_ref()
+            && matches!(ty.kind, TypeEnum::Bool)
+        {
+            return Value::new_bool(value.as_int() == 0);
+        }
+        let ty = value.ty();
+        let Some(int_ty) = self.int_type(&ty) else {
+            return self.fail(
+                EvalError::Invalid("Only numbers and `bool`s have unary operators"),
+                *span,
+            );
+        };
+        let int_value = int_ty.value_of(value.as_int());
+        let result = match op {
+            UnaryOp::Neg => match int_value
+                .checked_neg()
+                .filter(|&result| int_ty.fits(result))
+            {
+                Some(result) => result,
+                None => {
+                    let operation = format!("-({})", int_value);
+                    return self.fail(EvalError::Overflow(operation, ty.to_string()), *span);
+                }
+            },
+            UnaryOp::Not | UnaryOp::BitNot => int_value.not(),
             UnaryOp::Pos => int_value,
-            _ => unreachable!(),
-        });
-        result.set_type(value.ty());
+            UnaryOp::Ptr => {
+                return self.fail(EvalError::Invalid("Only types can be pointed to"), *span);
+            }
+        };
+        let mut result = Value::new_int(int_ty.wrap(result));
+        result.set_type(ty);
         result
     }
 
@@ -113,38 +307,263 @@ impl ExpVisitor<Value> for ConstEvalContext {
     }
 
     fn visit_type_cast(&mut self, type_cast: &ast::TypeCast) -> Value {
-        self.visit_right_value(type_cast.exp)
+        let (Some(value), Some(ty)) = (self.eval(type_cast.exp), self.eval(type_cast.ty)) else {
+            return Value::new_unit();
+        };
+        let ty = ty.as_type();
+        let from = self.int_type(&value.ty());
+        let to = self.int_type(&ty);
+        let mut result = match (value.kind(), ty.float_width()) {
+            (ValueKind::Int(int), Some(_)) => {
+                let int = from.map_or(int as f64, |from| from.value_of(int).as_f64());
+                Value::new_float(round_float(int, &ty))
+            }
+            (ValueKind::Float(float), Some(_)) => Value::new_float(round_float(float, &ty)),
+            (ValueKind::Float(float), None) => Value::new_int(float as i128),
+            // Casts between integers keep the bits that fit.
+            (ValueKind::Int(int), None) => match (from, to) {
+                (Some(from), Some(to)) => Value::new_int(to.wrap(from.value_of(int))),
+                _ => Value::new_int(int),
+            },
+            _ => return value,
+        };
+        result.set_type(ty);
+        result
     }
 
     fn visit_structure(&mut self, structure: &ast::Structure) -> Value {
         let mut fields = HashMap::new();
         for (name, &value) in &structure.fields {
-            let value = self.visit_right_value(value);
+            let Some(value) = self.eval(value) else {
+                return Value::new_unit();
+            };
             fields.insert(name.clone(), value);
         }
 
-        let ValueKind::Type(ty) = self.visit_right_value(structure.ty).kind() else {
-            unreachable!()
+        let Some(ty) = self.eval(structure.ty) else {
+            return Value::new_unit();
+        };
+        let ValueKind::Type(ty) = ty.kind() else {
+            return self.fail(
+                EvalError::Invalid("Only structures can be built from fields"),
+                structure.span,
+            );
         };
 
         Value::new_structure(ty, fields)
     }
 
-    fn visit_field_access(&mut self, field_access: &ast::FieldAccess) -> Value {
-        let lhs = self.visit_right_value(field_access.lhs);
-        let ValueKind::Structure(_, structure) = lhs.kind() else {
-            unreachable!()
-        };
-        let Some((_, value)) = structure
+    fn visit_tuple(&mut self, tuple: &ast::Tuple) -> Value {
+        let Some(elements) = tuple
+            .elements
             .iter()
-            .find(|(name, _)| *name == &field_access.field)
+            .map(|element| self.eval(*element))
+            .collect::<Option<Vec<_>>>()
         else {
-            unreachable!()
+            return Value::new_unit();
+        };
+        if elements
+            .iter()
+            .all(|element| matches!(element.kind(), ValueKind::Type(_)))
+        {
+            let elements = elements.iter().map(Value::as_type).collect();
+            return Value::new_type(TypeKind::new_tuple(elements));
+        }
+        let ty = TypeKind::new_tuple(elements.iter().map(Value::ty).collect());
+        let fields = elements
+            .into_iter()
+            .enumerate()
+            .map(|(index, element)| (index.to_string(), element))
+            .collect();
+        Value::new_structure(ty, fields)
+    }
+
+    fn visit_field_access(&mut self, field_access: &ast::FieldAccess) -> Value {
+        let Some(lhs) = self.eval(field_access.lhs) else {
+            return Value::new_unit();
         };
-        value.clone()
+        let ValueKind::Structure(_, structure) = lhs.kind() else {
+            return self.fail(
+                EvalError::Invalid("Only structures have fields"),
+                field_access.span,
+            );
+        };
+        match structure.get(&field_access.field) {
+            Some(value) => value.clone(),
+            None => self.fail(
+                EvalError::Invalid("The structure has no such field"),
+                field_access.span,
+            ),
+        }
     }
 
     fn visit_type(&mut self, type_: &Type) -> Value {
+        if let TypeEnum::Slice(element) = &type_.kind {
+            let element = self.visit_right_value(*element).as_type();
+            return Value::new_type(Arc::new(TypeKind::Slice(element)));
+        }
         Value::new_type(TypeKind::new(Arc::new(type_.clone())))
     }
+
+    fn visit_variant(&mut self, variant: &EnumVariant) -> Value {
+        let ty = self.visit_right_value(variant.ty).as_type();
+        let variants = match ty.as_ref() {
+            TypeKind::Primary(primary)
+                if let TypeEnum::Enum(EnumType { variants, .. }) = &primary.kind =>
+            {
+                variants
+            }
+            _ => return self.fail(EvalError::Invalid("Only enums have variants"), variant.span),
+        };
+        let Some(tag) = variants.iter().position(|v| v.name == variant.name) else {
+            return self.fail(
+                EvalError::Invalid("The enum has no such variant"),
+                variant.span,
+            );
+        };
+        if variants[tag].payload.is_some() {
+            Value::new_enum_ctor(ty, tag)
+        } else {
+            Value::new_enum(ty, tag, None)
+        }
+    }
+
+    fn visit_builtin(&mut self, builtin: &BuiltinCall) -> Value {
+        let ty = self.visit_right_value(builtin.builtin.ty()).as_type();
+        let mut value = Value::new_int(match &builtin.builtin {
+            Builtin::SizeOf(_) => self.size_of(&ty),
+            Builtin::AlignOf(_) => self.align_of(&ty),
+            Builtin::OffsetOf(_, field) => self.offset_of(&ty, field).unwrap(),
+        } as i128);
+        value.set_type(TypeKind::new(Arc::new(Type {
+            kind: TypeEnum::Usize,
+            span: Span::default(),
+        })));
+        value
+    }
+}
+
+impl ConstEvalContext {
+    pub(crate) fn eval_type_function(&mut self, func: Arc<FunctionDef>, args: &[ExpId]) -> Value {
+        let args = args
+            .iter()
+            .map(|arg| self.visit_right_value(*arg))
+            .collect::<Vec<_>>();
+        let name = format!(
+            "{}({})",
+            defining_name(&self.ctx, &func),
+            args.iter()
+                .map(Value::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        if let Some(ty) = INTERNED_TYPES.read().unwrap().get(&name) {
+            return Value::new_type(ty.clone());
+        }
+
+        let bindings = func
+            .params
+            .iter()
+            .map(|param| param.name.clone())
+            .zip(args)
+            .collect::<Bindings>();
+        let mut body_ctx = ConstEvalContext::new(self.ctx.clone(), bindings);
+        let ty = body_ctx.eval_block(&func.block).as_type();
+
+        let ty = match ty.as_ref() {
+            TypeKind::Primary(primary) => match &primary.kind {
+                TypeEnum::Structure(StructType {
+                    fields,
+                    namespace,
+                    layout,
+                    ..
+                }) => {
+                    let fields = fields
+                        .iter()
+                        .map(|(name, ty)| (name.clone(), body_ctx.visit_right_value(*ty).as_type()))
+                        .collect();
+                    Arc::new(TypeKind::Structure(
+                        name.clone(),
+                        fields,
+                        *layout,
+                        namespace.clone(),
+                    ))
+                }
+                _ => ty,
+            },
+            _ => ty,
+        };
+
+        let ty = INTERNED_TYPES
+            .write()
+            .unwrap()
+            .entry(name)
+            .or_insert(ty)
+            .clone();
+        Value::new_type(ty)
+    }
+}
+
+fn fold_float(op: &BinaryOp, lhs: f64, rhs: f64, ty: Arc<TypeKind>) -> Value {
+    let mut result = Value::new_float(match op {
+        BinaryOp::Add => round_float(lhs + rhs, &ty),
+        BinaryOp::Sub => round_float(lhs - rhs, &ty),
+        BinaryOp::Mul => round_float(lhs * rhs, &ty),
+        BinaryOp::Div => round_float(lhs / rhs, &ty),
+        BinaryOp::Mod => round_float(lhs % rhs, &ty),
+        BinaryOp::Le => return Value::new_bool(lhs <= rhs),
+        BinaryOp::Lt => return Value::new_bool(lhs < rhs),
+        BinaryOp::Ge => return Value::new_bool(lhs >= rhs),
+        BinaryOp::Gt => return Value::new_bool(lhs > rhs),
+        BinaryOp::Eq => return Value::new_bool(lhs == rhs),
+        BinaryOp::Ne => return Value::new_bool(lhs != rhs),
+        _ => unreachable!(),
+    });
+    result.set_type(ty);
+    result
+}
+
+fn round_float(value: f64, ty: &TypeKind) -> f64 {
+    if ty.float_width() == Some(32) {
+        value as f32 as f64
+    } else {
+        value
+    }
+}
+
+impl ConstEvalContext {
+    /// Evaluates an integer literal, negated if `negative`, failing if it
+    /// does not fit its type.
+    fn number(&mut self, number: &Number, negative: bool, span: Span) -> Value {
+        let mut value = Value::new_int(0);
+        if let Some(ty) = number.ty.clone().or_else(|| {
+            self.ctx
+                .literal_types()
+                .get(number.span, &literal_scope(&self.bindings))
+        }) {
+            value.set_type(TypeKind::new(Arc::new(Type {
+                kind: ty,
+                span: Span::default(),
+            })));
+        }
+        let ty = value.ty();
+        let int_ty = self.int_type(&ty).unwrap();
+        let int = if negative {
+            0i128.checked_sub_unsigned(number.num).map(Int::Signed)
+        } else {
+            Some(Int::Unsigned(number.num))
+        };
+        match int.filter(|int| int_ty.fits(*int)) {
+            Some(int) => {
+                let mut result = Value::new_int(int_ty.wrap(int));
+                result.set_type(ty);
+                result
+            }
+            None => {
+                let literal = format!("{}{}", if negative { "-" } else { "" }, number.num);
+                self.fail(EvalError::Overflow(literal, ty.to_string()), span)
+            }
+        }
+    }
 }
@@ -1,17 +1,36 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, fmt::Display, sync::Arc};
 
-use ast::{FunctionDef, ProtoDef, Span, Type, TypeEnum};
+use ast::{FunctionDef, ProtoDef, Span, StructLayout, Type, TypeEnum};
 
 #[derive(Debug, Clone)]
 pub enum ValueKind {
-    Int(i64),
+    Int(i128),
+    Float(f64),
     Function(Arc<FunctionDef>),
     Proto(Arc<ProtoDef>),
     Structure(Arc<TypeKind>, HashMap<String, Value>),
+    Array(Arc<TypeKind>, Vec<Value>),
+    Enum(Arc<TypeKind>, usize, Option<Box<Value>>),
+    EnumCtor(Arc<TypeKind>, usize),
     Type(Arc<TypeKind>),
+    Pointer(Place),
     Unit,
 }
 
+/// A local of a function evaluated at compile time, or a field or element of
+/// one.
+#[derive(Debug, Clone)]
+pub struct Place {
+    pub(crate) slot: usize,
+    pub(crate) projections: Vec<Projection>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Projection {
+    Field(String),
+    Element(usize),
+}
+
 #[derive(Debug, Clone)]
 pub struct Value {
     kind: ValueKind,
@@ -19,13 +38,31 @@ pub struct Value {
 }
 
 impl Value {
-    pub fn new_int(value: i64) -> Self {
+    pub fn new_int(value: i128) -> Self {
         Value {
             kind: ValueKind::Int(value),
             ty: None,
         }
     }
 
+    pub fn new_float(value: f64) -> Self {
+        Value {
+            kind: ValueKind::Float(value),
+            ty: None,
+        }
+    }
+
+    pub fn new_bool(value: bool) -> Self {
+        let ty = Type {
+            kind: TypeEnum::Bool,
+            span: Span::default(),
+        };
+        Value {
+            kind: ValueKind::Int(value as i128),
+            ty: Some(TypeKind::new(Arc::new(ty))),
+        }
+    }
+
     pub fn new_function(func: Arc<FunctionDef>) -> Self {
         Value {
             kind: ValueKind::Function(func),
@@ -49,7 +86,28 @@ impl Value {
 
     pub fn new_structure(ty: Arc<TypeKind>, structure: HashMap<String, Value>) -> Self {
         Value {
-            kind: ValueKind::Structure(ty, structure),
+            kind: ValueKind::Structure(ty.clone(), structure),
+            ty: Some(ty),
+        }
+    }
+
+    pub fn new_array(ty: Arc<TypeKind>, elements: Vec<Value>) -> Self {
+        Value {
+            kind: ValueKind::Array(ty.clone(), elements),
+            ty: Some(ty),
+        }
+    }
+
+    pub fn new_enum(ty: Arc<TypeKind>, tag: usize, payload: Option<Value>) -> Self {
+        Value {
+            kind: ValueKind::Enum(ty.clone(), tag, payload.map(Box::new)),
+            ty: Some(ty),
+        }
+    }
+
+    pub fn new_enum_ctor(ty: Arc<TypeKind>, tag: usize) -> Self {
+        Value {
+            kind: ValueKind::EnumCtor(ty, tag),
             ty: None,
         }
     }
@@ -60,16 +118,30 @@ impl Value {
             ty: None,
         }
     }
+
+    pub(crate) fn new_pointer(place: Place, pointee: Arc<TypeKind>) -> Self {
+        Value {
+            kind: ValueKind::Pointer(place),
+            ty: Some(pointee.new_ptr()),
+        }
+    }
 }
 
 impl Value {
-    pub fn as_int(&self) -> i64 {
+    pub fn as_int(&self) -> i128 {
         match &self.kind {
             ValueKind::Int(value) => *value,
             _ => unreachable!(),
         }
     }
 
+    pub fn as_float(&self) -> f64 {
+        match &self.kind {
+            ValueKind::Float(value) => *value,
+            _ => unreachable!(),
+        }
+    }
+
     pub fn as_function(&self) -> Arc<FunctionDef> {
         match &self.kind {
             ValueKind::Function(func) => func.clone(),
@@ -106,9 +178,10 @@ impl Value {
 
     pub fn ty(&self) -> Arc<TypeKind> {
         let span = Span::default();
-        self.ty.clone().unwrap_or({
+        self.ty.clone().unwrap_or_else(|| {
             let kind = match self.kind {
                 ValueKind::Int(_) => TypeEnum::Signed(32),
+                ValueKind::Float(_) => TypeEnum::Float(64),
                 ValueKind::Unit => TypeEnum::Unit,
                 _ => unreachable!(),
             };
@@ -119,12 +192,121 @@ impl Value {
     pub fn set_type(&mut self, ty: Arc<TypeKind>) {
         self.ty = Some(ty);
     }
+
+    pub(crate) fn kind_ref(&self) -> &ValueKind {
+        &self.kind
+    }
+
+    pub(crate) fn project(&self, projection: &Projection) -> Option<&Value> {
+        match (&self.kind, projection) {
+            (ValueKind::Structure(_, fields), Projection::Field(name)) => fields.get(name),
+            (ValueKind::Array(_, elements), Projection::Element(index)) => elements.get(*index),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn project_mut(&mut self, projection: &Projection) -> Option<&mut Value> {
+        match (&mut self.kind, projection) {
+            (ValueKind::Structure(_, fields), Projection::Field(name)) => fields.get_mut(name),
+            (ValueKind::Array(_, elements), Projection::Element(index)) => elements.get_mut(*index),
+            _ => None,
+        }
+    }
+
+    /// Whether a pointer to a compile-time local is part of `self`, which
+    /// then can't outlive the evaluation.
+    pub(crate) fn contains_pointer(&self) -> bool {
+        match &self.kind {
+            ValueKind::Pointer(_) => true,
+            ValueKind::Structure(_, fields) => fields.values().any(Value::contains_pointer),
+            ValueKind::Array(_, elements) => elements.iter().any(Value::contains_pointer),
+            ValueKind::Enum(_, _, payload) => {
+                payload.as_ref().is_some_and(|p| p.contains_pointer())
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ValueKind::Int(value) => match self.ty.as_deref() {
+                Some(TypeKind::Primary(ty)) if matches!(ty.kind, TypeEnum::Bool) => {
+                    write!(f, "{}", *value != 0)
+                }
+                _ => write!(f, "{}", value),
+            },
+            ValueKind::Float(value) => write!(f, "{:?}", value),
+            ValueKind::Function(func) => {
+                write!(f, "fn@{}:{}", func.span.file(), func.span.start())
+            }
+            ValueKind::Proto(proto) => {
+                write!(f, "proto@{}:{}", proto.span.file(), proto.span.start())
+            }
+            ValueKind::Structure(ty, fields) => {
+                let mut fields = fields
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name, value))
+                    .collect::<Vec<_>>();
+                fields.sort();
+                write!(f, "{} {{ {} }}", ty, fields.join(", "))
+            }
+            ValueKind::Array(_, elements) => {
+                let elements = elements.iter().map(Value::to_string).collect::<Vec<_>>();
+                write!(f, "[{}]", elements.join(", "))
+            }
+            ValueKind::Enum(ty, tag, payload) => match payload {
+                Some(payload) => write!(f, "{}.{}({})", ty, tag, payload),
+                None => write!(f, "{}.{}", ty, tag),
+            },
+            ValueKind::EnumCtor(ty, tag) => write!(f, "{}.{}", ty, tag),
+            ValueKind::Type(ty) => write!(f, "{}", ty),
+            ValueKind::Pointer(place) => write!(f, "ptr@{}", place.slot),
+            ValueKind::Unit => write!(f, "()"),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum TypeKind {
     Primary(Arc<Type>),
     Ptr(Arc<Self>),
+    Array(Arc<Self>, u32),
+    Slice(Arc<Self>),
+    /// A structure returned by a type-returning function, named after the
+    /// call that produced it and with its field types resolved. The last
+    /// item is the namespace of its members.
+    Structure(
+        String,
+        Vec<(String, Arc<Self>)>,
+        StructLayout,
+        Option<String>,
+    ),
+}
+
+/// Prints the type in the form used for instance symbols, so two equal types
+/// always print the same and anonymous structures are told apart by where
+/// they are declared.
+impl Display for TypeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeKind::Primary(ty) => match &ty.kind {
+                TypeEnum::Structure(_) => {
+                    write!(f, "struct@{}:{}", ty.span.file(), ty.span.start())
+                }
+                TypeEnum::Enum(_) => write!(f, "enum@{}:{}", ty.span.file(), ty.span.start()),
+                TypeEnum::Dyn(_) => {
+                    write!(f, "dyn@{}:{}", ty.span.file(), ty.span.start())
+                }
+                _ => write!(f, "{}", ty),
+            },
+            TypeKind::Ptr(ty) => write!(f, "*{}", ty),
+            TypeKind::Array(ty, len) => write!(f, "[{}; {}]", ty, len),
+            TypeKind::Slice(ty) => write!(f, "[]{}", ty),
+            TypeKind::Structure(name, ..) => write!(f, "{}", name),
+        }
+    }
 }
 
 impl TypeKind {
@@ -135,4 +317,36 @@ impl TypeKind {
     pub fn new_ptr(self: &Arc<Self>) -> Arc<Self> {
         Arc::new(TypeKind::Ptr(self.clone()))
     }
+
+    pub fn new_tuple(elements: Vec<Arc<Self>>) -> Arc<Self> {
+        let name = format!(
+            "({})",
+            elements
+                .iter()
+                .map(|element| element.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let fields = elements
+            .into_iter()
+            .enumerate()
+            .map(|(index, element)| (index.to_string(), element))
+            .collect();
+        Arc::new(TypeKind::Structure(
+            name,
+            fields,
+            StructLayout::default(),
+            None,
+        ))
+    }
+
+    pub fn float_width(&self) -> Option<u32> {
+        match self {
+            TypeKind::Primary(ty) => match ty.kind {
+                TypeEnum::Float(width) => Some(width),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,175 @@
+use std::fmt::Display;
+
+use ast::{BinaryOp, TypeEnum};
+
+use crate::{ConstEvalContext, TypeKind};
+
+/// The width and signedness of an integer type. Integers are kept in an
+/// `i128`, sign extended if signed and zero extended otherwise, so a `u128`
+/// above `i128::MAX` is kept as its bit pattern.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct IntType {
+    width: u32,
+    signed: bool,
+}
+
+/// The number an integer of some `IntType` stands for. Both operands of an
+/// operation have the same type, so they are computed in the same variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Int {
+    Signed(i128),
+    Unsigned(u128),
+}
+
+impl IntType {
+    pub(crate) fn value_of(self, int: i128) -> Int {
+        if self.signed {
+            Int::Signed(int)
+        } else {
+            Int::Unsigned(int as u128)
+        }
+    }
+
+    pub(crate) fn fits(self, value: Int) -> bool {
+        let width = self.width;
+        match (value, self.signed) {
+            (Int::Signed(_), true) | (Int::Unsigned(_), false) if width >= 128 => true,
+            (Int::Signed(value), true) => {
+                let max = (1i128 << (width - 1)) - 1;
+                (-max - 1..=max).contains(&value)
+            }
+            (Int::Signed(value), false) => value >= 0 && self.fits(Int::Unsigned(value as u128)),
+            (Int::Unsigned(value), true) => value < 1u128 << (width - 1),
+            (Int::Unsigned(value), false) => value < 1u128 << width,
+        }
+    }
+
+    /// Keeps the bits of `value` that fit in this type, as `as` does.
+    pub(crate) fn wrap(self, value: Int) -> i128 {
+        let bits = match value {
+            Int::Signed(value) => value as u128,
+            Int::Unsigned(value) => value,
+        };
+        if self.width >= 128 {
+            return bits as i128;
+        }
+        let mask = (1u128 << self.width) - 1;
+        let bits = bits & mask;
+        if self.signed && bits >> (self.width - 1) == 1 {
+            (bits | !mask) as i128
+        } else {
+            bits as i128
+        }
+    }
+
+    pub(crate) fn width(self) -> u32 {
+        self.width
+    }
+}
+
+/// Folds `op` on two integers of the same primitive type.
+macro_rules! fold {
+    ($op:expr, $lhs:expr, $rhs:expr) => {
+        match $op {
+            BinaryOp::Add => $lhs.checked_add($rhs),
+            BinaryOp::Sub => $lhs.checked_sub($rhs),
+            BinaryOp::Mul => $lhs.checked_mul($rhs),
+            BinaryOp::Div => $lhs.checked_div($rhs),
+            BinaryOp::Mod => $lhs.checked_rem($rhs),
+            BinaryOp::BitAnd => Some($lhs & $rhs),
+            BinaryOp::BitOr => Some($lhs | $rhs),
+            BinaryOp::BitXor => Some($lhs ^ $rhs),
+            _ => unreachable!(),
+        }
+    };
+}
+
+impl Int {
+    /// Folds an arithmetic or bitwise operation, or returns `None` if it
+    /// overflows the 128 bits it is computed in. The caller checks that a
+    /// shift is by less than the width of the type.
+    pub(crate) fn fold(op: &BinaryOp, lhs: Int, rhs: Int) -> Option<Int> {
+        if let BinaryOp::LShift | BinaryOp::RShift = op {
+            let amount = match rhs {
+                Int::Signed(rhs) => rhs as u32,
+                Int::Unsigned(rhs) => rhs as u32,
+            };
+            let left = matches!(op, BinaryOp::LShift);
+            return Some(match lhs {
+                Int::Signed(lhs) if left => Int::Signed(lhs << amount),
+                Int::Signed(lhs) => Int::Signed(lhs >> amount),
+                Int::Unsigned(lhs) if left => Int::Unsigned(lhs << amount),
+                Int::Unsigned(lhs) => Int::Unsigned(lhs >> amount),
+            });
+        }
+        match (lhs, rhs) {
+            (Int::Signed(lhs), Int::Signed(rhs)) => fold!(op, lhs, rhs).map(Int::Signed),
+            (Int::Unsigned(lhs), Int::Unsigned(rhs)) => fold!(op, lhs, rhs).map(Int::Unsigned),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn checked_neg(self) -> Option<Int> {
+        match self {
+            Int::Signed(value) => value.checked_neg().map(Int::Signed),
+            Int::Unsigned(0) => Some(self),
+            Int::Unsigned(_) => None,
+        }
+    }
+
+    /// Flips every bit, which `IntType::wrap` then cuts to the width of
+    /// the type.
+    pub(crate) fn not(self) -> Int {
+        match self {
+            Int::Signed(value) => Int::Signed(!value),
+            Int::Unsigned(value) => Int::Unsigned(!value),
+        }
+    }
+
+    pub(crate) fn is_zero(self) -> bool {
+        matches!(self, Int::Signed(0) | Int::Unsigned(0))
+    }
+
+    /// Whether `self` is in `0..bound`, as the amount of a shift has to be.
+    pub(crate) fn is_below(self, bound: u32) -> bool {
+        match self {
+            Int::Signed(value) => (0..bound as i128).contains(&value),
+            Int::Unsigned(value) => value < bound as u128,
+        }
+    }
+
+    pub(crate) fn as_f64(self) -> f64 {
+        match self {
+            Int::Signed(value) => value as f64,
+            Int::Unsigned(value) => value as f64,
+        }
+    }
+}
+
+impl Display for Int {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Int::Signed(value) => write!(f, "{}", value),
+            Int::Unsigned(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl ConstEvalContext {
+    /// The integer type `ty`, with `bool` as an unsigned integer of one bit.
+    pub(crate) fn int_type(&self, ty: &TypeKind) -> Option<IntType> {
+        let TypeKind::Primary(primary) = ty else {
+            return None;
+        };
+        let pointer_width = self.ctx.target().pointer_width();
+        let (width, signed) = match primary.kind {
+            TypeEnum::Signed(width) => (width, true),
+            TypeEnum::Unsigned(width) => (width, false),
+            TypeEnum::Isize => (pointer_width, true),
+            TypeEnum::Usize => (pointer_width, false),
+            TypeEnum::Bool => (1, false),
+            _ => return None,
+        };
+        Some(IntType { width, signed })
+    }
+}
@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use ast::{EnumType, StructLayout, StructType, TypeEnum, visitor::ExpVisitor};
+
+use crate::{ConstEvalContext, TypeKind};
+
+type Fields = Vec<(String, Arc<TypeKind>)>;
+
+/// Computes sizes, alignments and field offsets with the same rules as the
+/// types `codegen_llvm` emits, so the layout builtins agree with the code.
+impl ConstEvalContext {
+    pub(crate) fn size_of(&mut self, ty: &TypeKind) -> u64 {
+        self.layout_of(ty).0
+    }
+
+    pub(crate) fn align_of(&mut self, ty: &TypeKind) -> u64 {
+        self.layout_of(ty).1
+    }
+
+    pub(crate) fn offset_of(&mut self, ty: &TypeKind, field: &str) -> Option<u64> {
+        let (fields, layout) = self.struct_fields(ty)?;
+        let position = fields.iter().position(|(name, _)| name == field)?;
+        let (offsets, _, _) = self.place(&fields, layout);
+        Some(offsets[position])
+    }
+
+    fn layout_of(&mut self, ty: &TypeKind) -> (u64, u64) {
+        let pointer_width = self.ctx.target().pointer_width();
+        match ty {
+            TypeKind::Ptr(_) => (pointer_width as u64 / 8, pointer_width as u64 / 8),
+            TypeKind::Array(element, len) => {
+                let (size, align) = self.layout_of(element);
+                (size * *len as u64, align)
+            }
+            // A pointer and a `usize` length.
+            TypeKind::Slice(_) => (pointer_width as u64 / 4, pointer_width as u64 / 8),
+            TypeKind::Structure(..) => {
+                let (fields, layout) = self.struct_fields(ty).unwrap();
+                let (_, size, align) = self.place(&fields, layout);
+                (size, align)
+            }
+            TypeKind::Primary(primary) => match &primary.kind {
+                TypeEnum::Signed(width) | TypeEnum::Unsigned(width) => int_layout(*width),
+                TypeEnum::Usize | TypeEnum::Isize => int_layout(pointer_width),
+                TypeEnum::Bool => int_layout(1),
+                TypeEnum::Float(width) => (*width as u64 / 8, *width as u64 / 8),
+                TypeEnum::Unit | TypeEnum::Type | TypeEnum::Interface(_) | TypeEnum::SelfType => {
+                    (0, 1)
+                }
+                // A data pointer and a vtable pointer.
+                TypeEnum::Dyn(_) | TypeEnum::Slice(_) => {
+                    (pointer_width as u64 / 4, pointer_width as u64 / 8)
+                }
+                TypeEnum::Structure(_) => {
+                    let (fields, layout) = self.struct_fields(ty).unwrap();
+                    let (_, size, align) = self.place(&fields, layout);
+                    (size, align)
+                }
+                TypeEnum::Enum(EnumType { variants, .. }) => {
+                    let (size, unit) = variants
+                        .iter()
+                        .filter_map(|variant| variant.payload)
+                        .map(|payload| {
+                            let payload = self.visit_right_value(payload).as_type();
+                            self.layout_of(&payload)
+                        })
+                        .fold((0, 1), |(size, align), (payload_size, payload_align)| {
+                            (size.max(payload_size), align.max(payload_align))
+                        });
+                    // `{ i32 tag, [n x iN] payload }`, see `codegen_llvm`.
+                    let payload = (size.div_ceil(unit) * unit, unit);
+                    let (_, size, align) = StructLayout::default().place([(4, 4), payload]);
+                    (size, align)
+                }
+            },
+        }
+    }
+
+    fn struct_fields(&mut self, ty: &TypeKind) -> Option<(Fields, StructLayout)> {
+        match ty {
+            TypeKind::Structure(_, fields, layout, _) => Some((fields.clone(), *layout)),
+            TypeKind::Primary(primary) => match &primary.kind {
+                TypeEnum::Structure(StructType { fields, layout, .. }) => {
+                    let fields = fields
+                        .iter()
+                        .map(|(name, ty)| (name.clone(), self.visit_right_value(*ty).as_type()))
+                        .collect();
+                    Some((fields, *layout))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn place(
+        &mut self,
+        fields: &[(String, Arc<TypeKind>)],
+        layout: StructLayout,
+    ) -> (Vec<u64>, u64, u64) {
+        let fields = fields
+            .iter()
+            .map(|(_, ty)| self.layout_of(ty))
+            .collect::<Vec<_>>();
+        layout.place(fields)
+    }
+}
+
+/// Integers take the next power of two bytes, aligned to at most 16 bytes.
+fn int_layout(width: u32) -> (u64, u64) {
+    let size = (width as u64).div_ceil(8).next_power_of_two();
+    (size, size.min(16))
+}
@@ -1,14 +1,161 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
 
+use ast::{
+    Call, ConstInitialValue, Exp, ExpId, FunctionDef, Span, Type, TypeEnum, visitor::ExpVisitor,
+};
 use query::QueryContext;
 
+pub use error::EvalError;
 pub use info::*;
 
+mod call;
+mod error;
 mod expr;
 mod info;
+mod int;
+mod layout;
+mod place;
 pub mod queries;
 mod stmt;
+#[cfg(test)]
+mod tests;
+
+pub type Bindings = HashMap<String, Value>;
+
+pub type EvalResult = Result<Value, (EvalError, Span)>;
+
+/// Keeps a const that never finishes from hanging the compiler.
+const STEP_LIMIT: u64 = 1_000_000;
+const DEPTH_LIMIT: usize = 64;
 
 struct ConstEvalContext {
     ctx: Arc<QueryContext>,
+    bindings: Bindings,
+    // Slots are never reused, so a pointer to a local that is gone finds
+    // nothing.
+    slots: BTreeMap<usize, Value>,
+    next_slot: usize,
+    scopes: Vec<HashMap<String, usize>>,
+    flow: Option<Flow>,
+    steps: u64,
+    depth: usize,
+}
+
+enum Flow {
+    Break(Option<String>, Value),
+    Continue(Option<String>),
+    Return(Value),
+    Error(EvalError, Span),
+}
+
+impl ConstEvalContext {
+    fn new(ctx: Arc<QueryContext>, bindings: Bindings) -> Self {
+        ConstEvalContext {
+            ctx,
+            bindings,
+            slots: BTreeMap::new(),
+            next_slot: 0,
+            scopes: vec![HashMap::new()],
+            flow: None,
+            steps: 0,
+            depth: 0,
+        }
+    }
+
+    /// Errors raised without a span of their own are given that of `exp`.
+    fn eval(&mut self, exp: ExpId) -> Option<Value> {
+        let value = self.visit_right_value(exp);
+        if let Some(Flow::Error(_, span)) = &mut self.flow
+            && *span == Span::default()
+        {
+            *span = exp.span();
+        }
+        self.flow.is_none().then_some(value)
+    }
+
+    fn fail(&mut self, error: EvalError, span: Span) -> Value {
+        if !matches!(self.flow, Some(Flow::Error(..))) {
+            self.flow = Some(Flow::Error(error, span));
+        }
+        Value::new_unit()
+    }
+
+    fn step(&mut self, span: Span) -> bool {
+        self.steps += 1;
+        if self.steps > STEP_LIMIT {
+            self.fail(EvalError::StepLimit(STEP_LIMIT), span);
+            return false;
+        }
+        true
+    }
+
+    fn finish(mut self, value: Value, exp: ExpId) -> EvalResult {
+        match self.flow.take() {
+            Some(Flow::Error(error, span)) if span == Span::default() => Err((error, exp.span())),
+            Some(Flow::Error(error, span)) => Err((error, span)),
+            _ if value.contains_pointer() => Err((EvalError::PointerInConst, exp.span())),
+            _ => Ok(value),
+        }
+    }
+}
+
+pub fn eval_exp(ctx: Arc<QueryContext>, exp: ExpId, bindings: &Bindings) -> Value {
+    try_eval_exp(ctx, exp, bindings).unwrap_or_else(|(error, _)| unreachable!("{}", error))
+}
+
+pub fn try_eval_exp(ctx: Arc<QueryContext>, exp: ExpId, bindings: &Bindings) -> EvalResult {
+    let mut eval_ctx = ConstEvalContext::new(ctx, bindings.clone());
+    let value = eval_ctx.visit_right_value(exp);
+    eval_ctx.finish(value, exp)
+}
+
+pub fn eval_call(ctx: Arc<QueryContext>, call: &Call, bindings: &Bindings) -> Value {
+    let mut eval_ctx = ConstEvalContext::new(ctx, bindings.clone());
+    let value = eval_ctx.visit_call(call);
+    eval_ctx
+        .finish(value, call.func)
+        .unwrap_or_else(|(error, _)| unreachable!("{}", error))
+}
+
+pub fn literal_scope(bindings: &Bindings) -> type_infer::Scope {
+    type_infer::Scope::new(
+        bindings
+            .iter()
+            .map(|(name, value)| (name.clone(), value.to_string())),
+    )
+}
+
+pub fn is_type_function(ctx: &QueryContext, func: &FunctionDef) -> bool {
+    func.return_type.is_some_and(|ty| {
+        matches!(
+            ctx.ast_ctx().exp(ty),
+            Exp::Type(Type {
+                kind: TypeEnum::Type,
+                ..
+            })
+        )
+    })
+}
+
+/// Finds the name of the const a function is declared in, so that anything
+/// derived from it gets the same name however the function is reached.
+pub fn defining_name(ctx: &QueryContext, func: &FunctionDef) -> String {
+    let ast_ctx = ctx.ast_ctx();
+    let mut names = ctx
+        .def_ids()
+        .into_iter()
+        .filter_map(|def_id| {
+            let const_def = ctx.get_def(def_id)?;
+            let ConstInitialValue::Exp(exp) = &const_def.initial_value;
+            match ast_ctx.exp(exp.exp) {
+                Exp::Function(def) if def.span == func.span => Some(const_def.name.clone()),
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+    names.into_iter().next().unwrap_or_default()
 }
@@ -0,0 +1,143 @@
+use ast::{Exp, ExpId, Index, Span};
+
+use crate::{ConstEvalContext, EvalError, Place, Projection, Value, ValueKind};
+
+impl ConstEvalContext {
+    pub(crate) fn declare(&mut self, name: &str, value: Value) {
+        let slot = self.allocate(value);
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.to_string(), slot);
+    }
+
+    pub(crate) fn local(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    /// Where the value of `exp` is stored. Values that aren't stored
+    /// anywhere, like the `5` in `&5`, are put in a temporary.
+    pub(crate) fn locate(&mut self, exp: ExpId) -> Option<Place> {
+        let ast_ctx = self.ctx.ast_ctx();
+        match ast_ctx.exp(exp) {
+            Exp::Exp(inner, _) => self.locate(*inner),
+            Exp::Var(var) => match self.local(&var.path.path.join("::")) {
+                Some(slot) => Some(Place {
+                    slot,
+                    projections: Vec::new(),
+                }),
+                None => self.temporary(exp),
+            },
+            Exp::Deref(deref) => match self.eval(deref.exp)?.kind() {
+                ValueKind::Pointer(place) => Some(place),
+                _ => unreachable!(),
+            },
+            Exp::FieldAccess(field_access) => {
+                let mut place = self.locate(field_access.lhs)?;
+                place
+                    .projections
+                    .push(Projection::Field(field_access.field.clone()));
+                Some(place)
+            }
+            Exp::Index(index) => self.index_place(index),
+            _ => self.temporary(exp),
+        }
+    }
+
+    /// Where `exp[index]` is stored. Indexing a pointer indexes what it
+    /// points to, counting from the element it points to if that is in an
+    /// array.
+    pub(crate) fn index_place(&mut self, index: &Index) -> Option<Place> {
+        let mut place = self.locate(index.exp)?;
+        let mut position = self.eval(index.index)?.as_int();
+        let pointer = self.inspect(&place, index.span, |value| match value.kind_ref() {
+            ValueKind::Pointer(target) => Some(target.clone()),
+            _ => None,
+        })?;
+        if let Some(target) = pointer {
+            place = target;
+            if !self.inspect(&place, index.span, |value| {
+                matches!(value.kind_ref(), ValueKind::Array(..))
+            })? {
+                match place.projections.last() {
+                    Some(Projection::Element(first)) => {
+                        position += *first as i128;
+                        place.projections.pop();
+                    }
+                    // A pointer to a single value is like an array of one.
+                    _ if position == 0 => return Some(place),
+                    _ => {
+                        self.fail(EvalError::IndexOutOfBounds(position, 1), index.span);
+                        return None;
+                    }
+                }
+            }
+        }
+
+        let len = self.inspect(&place, index.span, |value| match value.kind_ref() {
+            ValueKind::Array(_, elements) => elements.len(),
+            _ => unreachable!(),
+        })?;
+        if position < 0 || position as usize >= len {
+            self.fail(EvalError::IndexOutOfBounds(position, len), index.span);
+            return None;
+        }
+        place
+            .projections
+            .push(Projection::Element(position as usize));
+        Some(place)
+    }
+
+    pub(crate) fn read(&mut self, place: &Place, span: Span) -> Option<Value> {
+        self.inspect(place, span, Value::clone)
+    }
+
+    pub(crate) fn write(&mut self, place: &Place, value: Value, span: Span) {
+        let target = self.slots.get_mut(&place.slot).and_then(|mut target| {
+            for projection in &place.projections {
+                target = target.project_mut(projection)?;
+            }
+            Some(target)
+        });
+        match target {
+            Some(target) => *target = value,
+            None => {
+                self.fail(EvalError::DanglingPointer, span);
+            }
+        }
+    }
+
+    fn inspect<R>(&mut self, place: &Place, span: Span, f: impl FnOnce(&Value) -> R) -> Option<R> {
+        let target = self.slots.get(&place.slot).and_then(|mut target| {
+            for projection in &place.projections {
+                target = target.project(projection)?;
+            }
+            Some(target)
+        });
+        match target {
+            Some(target) => Some(f(target)),
+            None => {
+                self.fail(EvalError::DanglingPointer, span);
+                None
+            }
+        }
+    }
+
+    fn temporary(&mut self, exp: ExpId) -> Option<Place> {
+        let value = self.eval(exp)?;
+        Some(Place {
+            slot: self.allocate(value),
+            projections: Vec::new(),
+        })
+    }
+
+    fn allocate(&mut self, value: Value) -> usize {
+        let slot = self.next_slot;
+        self.next_slot += 1;
+        self.slots.insert(slot, value);
+        slot
+    }
+}
@@ -1,18 +1,30 @@
 use std::sync::{Arc, LazyLock};
 
-use ast::{ConstDef, ConstExp, ConstInitialValue, visitor::ExpVisitor};
+use ast::{ConstDef, ConstExp, ConstInitialValue};
 use query::{DefId, Provider, QueryContext};
 
-use crate::{ConstEvalContext, info::Value};
+use crate::{Bindings, EvalResult, info::Value, try_eval_exp};
 
 pub static CONST_EVAL_PROVIDER: LazyLock<Provider<DefId, Value>> =
     LazyLock::new(|| Provider::new(const_eval_provider));
 
+/// The value of a const, or why it can't be evaluated. The analyzer reports
+/// the error, so [`CONST_EVAL_PROVIDER`] can assume there is none.
+pub static TRY_CONST_EVAL_PROVIDER: LazyLock<Provider<DefId, EvalResult>> =
+    LazyLock::new(|| Provider::new(try_const_eval_provider));
+
 fn const_eval_provider(ctx: Arc<QueryContext>, def_id: DefId) -> Value {
-    let mut eval_ctx = ConstEvalContext { ctx: ctx.clone() };
+    ctx.query_cached(&TRY_CONST_EVAL_PROVIDER, def_id)
+        .unwrap()
+        .unwrap_or_else(|(error, _)| unreachable!("{}", error))
+}
+
+fn try_const_eval_provider(ctx: Arc<QueryContext>, def_id: DefId) -> EvalResult {
     let ConstDef { initial_value, .. } = ctx.get_def(def_id).unwrap();
 
     match initial_value {
-        ConstInitialValue::Exp(ConstExp { exp }) => eval_ctx.visit_right_value(*exp),
+        ConstInitialValue::Exp(ConstExp { exp }) => {
+            try_eval_exp(ctx.clone(), *exp, &Bindings::new())
+        }
     }
 }
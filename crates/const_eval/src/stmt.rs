@@ -1,37 +1,254 @@
-use ast::visitor::StatementVisitor;
+use std::collections::HashMap;
 
-use crate::{ConstEvalContext, Value};
+use ast::{
+    Block, EnumType, Pattern, Statement, TypeEnum, VarBinding,
+    visitor::{BlockVisitor, ExpVisitor, StatementVisitor},
+};
+
+use crate::{ConstEvalContext, EvalError, Flow, TypeKind, Value, ValueKind, int::Int};
 
 impl StatementVisitor<Value> for ConstEvalContext {
-    fn visit_assign(&mut self, _assign: &ast::Assign) -> Value {
+    fn visit_assign(&mut self, assign: &ast::Assign) -> Value {
+        let Some(value) = self.eval(assign.rhs) else {
+            return Value::new_unit();
+        };
+        let Some(place) = self.locate(assign.lhs) else {
+            return Value::new_unit();
+        };
+        let value = match &assign.op {
+            Some(op) => match self.read(&place, assign.span) {
+                Some(current) => self.visit_binary(op, current, value, &assign.span),
+                None => return Value::new_unit(),
+            },
+            None => value,
+        };
+        if self.flow.is_none() {
+            self.write(&place, value, assign.span);
+        }
+        Value::new_unit()
+    }
+
+    fn visit_return(&mut self, return_stmt: &ast::Return) -> Value {
+        let value = match return_stmt.value {
+            Some(value) => match self.eval(value) {
+                Some(value) => value,
+                None => return Value::new_unit(),
+            },
+            None => Value::new_unit(),
+        };
+        self.flow = Some(Flow::Return(value));
+        Value::new_unit()
+    }
+
+    fn visit_if_exp(&mut self, if_exp: &ast::IfExp) -> Value {
+        let Some(condition) = self.eval(if_exp.condition) else {
+            return Value::new_unit();
+        };
+        if condition.as_int() != 0 {
+            self.eval_block(&if_exp.then_branch)
+        } else if let Some(else_if) = &if_exp.else_if {
+            self.visit_if_exp(else_if)
+        } else if let Some(else_branch) = &if_exp.else_branch {
+            self.eval_block(else_branch)
+        } else {
+            Value::new_unit()
+        }
+    }
+
+    fn visit_match(&mut self, match_: &ast::Match) -> Value {
+        let Some(value) = self.eval(match_.exp) else {
+            return Value::new_unit();
+        };
+        let (ty, tag, payload) = match value.kind() {
+            ValueKind::Enum(ty, tag, payload) => (Some(ty), tag as i128, payload),
+            ValueKind::Int(value) => (None, value, None),
+            _ => unreachable!(),
+        };
+
+        for arm in &match_.arms {
+            let matched = match &arm.pattern {
+                Pattern::Wildcard(_) => true,
+                Pattern::Number(number, negative) => {
+                    let int = if *negative {
+                        Int::Signed(0i128.wrapping_sub_unsigned(number.num))
+                    } else {
+                        Int::Unsigned(number.num)
+                    };
+                    self.int_type(&value.ty()).unwrap().wrap(int) == tag
+                }
+                Pattern::Variant(path, _, _) => {
+                    variant_tag(ty.as_deref().unwrap(), path.path.last().unwrap()) == tag
+                }
+            };
+            if !matched {
+                continue;
+            }
+            self.scopes.push(HashMap::new());
+            if let Pattern::Variant(_, Some(binding), _) = &arm.pattern {
+                self.declare(binding, payload.as_deref().unwrap().clone());
+            }
+            let value = self.visit_right_value(arm.body);
+            self.scopes.pop();
+            return value;
+        }
+        Value::new_unit()
+    }
+
+    fn visit_for(&mut self, for_: &ast::For) -> Value {
+        let (Some(start), Some(end)) = (self.eval(for_.start), self.eval(for_.end)) else {
+            return Value::new_unit();
+        };
+        let step = match for_.step {
+            Some(step) => match self.eval(step) {
+                Some(step) => step.as_int(),
+                None => return Value::new_unit(),
+            },
+            None => 1,
+        };
+
+        self.scopes.push(HashMap::new());
+        self.declare(&for_.var, start.clone());
+        let slot = self.local(&for_.var).unwrap();
+        loop {
+            let current = self.slots[&slot].as_int();
+            if current >= end.as_int() || !self.step(for_.span) {
+                break;
+            }
+            self.eval_block(&for_.body);
+            if self.end_iteration(&for_.label).is_some() {
+                break;
+            }
+            let mut next = Value::new_int(self.slots[&slot].as_int() + step);
+            next.set_type(start.ty());
+            self.slots.insert(slot, next);
+        }
+        self.scopes.pop();
+        Value::new_unit()
+    }
+
+    fn visit_loop(&mut self, loop_: &ast::Loop) -> Value {
+        while self.step(loop_.span) {
+            self.eval_block(&loop_.body);
+            if let Some(value) = self.end_iteration(&loop_.label) {
+                return value;
+            }
+        }
+        Value::new_unit()
+    }
+
+    fn visit_while(&mut self, while_: &ast::While) -> Value {
+        while let Some(condition) = self.eval(while_.condition) {
+            if condition.as_int() == 0 || !self.step(while_.span) {
+                break;
+            }
+            self.eval_block(&while_.body);
+            if self.end_iteration(&while_.label).is_some() {
+                break;
+            }
+        }
         Value::new_unit()
     }
 
-    fn visit_return(&mut self, _return_stmt: &ast::Return) -> Value {
-        unimplemented!()
+    fn visit_break(&mut self, break_: &ast::Break) -> Value {
+        let value = match break_.value {
+            Some(value) => match self.eval(value) {
+                Some(value) => value,
+                None => return Value::new_unit(),
+            },
+            None => Value::new_unit(),
+        };
+        self.flow = Some(Flow::Break(break_.label.clone(), value));
+        Value::new_unit()
     }
 
-    fn visit_if_exp(&mut self, _if_exp: &ast::IfExp) -> Value {
-        unimplemented!()
+    fn visit_continue(&mut self, continue_: &ast::Continue) -> Value {
+        self.flow = Some(Flow::Continue(continue_.label.clone()));
+        Value::new_unit()
     }
+}
 
-    fn visit_for(&mut self, _for_: &ast::For) -> Value {
-        unimplemented!()
+impl BlockVisitor<Value> for ConstEvalContext {
+    fn on_enter_block(&mut self) {
+        self.scopes.push(HashMap::new());
     }
 
-    fn visit_loop(&mut self, _loop_: &ast::Loop) -> Value {
-        unimplemented!()
+    fn on_leave_block(&mut self) {
+        self.scopes.pop();
     }
 
-    fn visit_while(&mut self, _while_: &ast::While) -> Value {
-        unimplemented!()
+    fn visit_statement(&mut self, stmt: &Statement) -> Option<Value> {
+        if self.flow.is_none() {
+            match stmt {
+                Statement::Exp(exp) => {
+                    self.visit_right_value(*exp);
+                }
+                Statement::InlineAsm(inline_asm) => self.visit_inline_asm(inline_asm),
+            }
+        }
+        self.flow.is_some().then(Value::new_unit)
     }
 
-    fn visit_break(&mut self, _span: ast::Span) -> Value {
-        unimplemented!()
+    /// Declares the names even if the value couldn't be evaluated, so the
+    /// rest of the block can still look them up on its way out.
+    fn visit_var_def(&mut self, var_def: &ast::VarDef) {
+        // `let x: T;` is given its value by a later assignment.
+        let value = var_def
+            .initial_value
+            .and_then(|value| self.eval(value))
+            .unwrap_or_else(Value::new_unit);
+        match &var_def.binding {
+            VarBinding::Name(name) => self.declare(name, value),
+            VarBinding::Tuple(names) => {
+                for (index, name) in names.iter().enumerate() {
+                    let element = match value.kind() {
+                        ValueKind::Structure(_, mut fields) => {
+                            fields.remove(&index.to_string()).unwrap()
+                        }
+                        _ => Value::new_unit(),
+                    };
+                    self.declare(name, element);
+                }
+            }
+        }
     }
 
-    fn visit_continue(&mut self, _span: ast::Span) -> Value {
-        unimplemented!()
+    fn visit_inline_asm(&mut self, inline_asm: &ast::InlineAsm) {
+        self.fail(EvalError::InlineAsm, inline_asm.span);
     }
 }
+
+impl ConstEvalContext {
+    pub(crate) fn eval_block(&mut self, block: &Block) -> Value {
+        <Self as BlockVisitor<Value>>::visit_block(self, block).unwrap_or_else(Value::new_unit)
+    }
+
+    /// Handles `break` and `continue` at the end of an iteration of the loop
+    /// labeled `label`. Once the loop is over, returns what it evaluates to.
+    fn end_iteration(&mut self, label: &Option<String>) -> Option<Value> {
+        let aimed_here = |target: &Option<String>| target.is_none() || target == label;
+        match &self.flow {
+            Some(Flow::Break(target, _)) if aimed_here(target) => {
+                let Some(Flow::Break(_, value)) = self.flow.take() else {
+                    unreachable!()
+                };
+                Some(value)
+            }
+            Some(Flow::Continue(target)) if aimed_here(target) => {
+                self.flow = None;
+                None
+            }
+            Some(_) => Some(Value::new_unit()),
+            None => None,
+        }
+    }
+}
+
+fn variant_tag(ty: &TypeKind, name: &str) -> i128 {
+    let TypeKind::Primary(primary) = ty else {
+        unreachable!()
+    };
+    let TypeEnum::Enum(EnumType { variants, .. }) = &primary.kind else {
+        unreachable!()
+    };
+    variants.iter().position(|v| v.name == name).unwrap() as i128
+}
@@ -0,0 +1,140 @@
+use std::{io::Write, sync::Arc};
+
+use ast::{FileTable, ParseContext};
+use parser::CaraParser;
+use query::QueryContext;
+use targets::spec::Target;
+
+use crate::{EvalError, EvalResult, queries::TRY_CONST_EVAL_PROVIDER};
+
+/// Evaluates the const `name` declared in `source`. Query results are cached
+/// per process, so `name` is evaluated uncached and may not refer to other
+/// consts.
+fn eval(source: &str, name: &str) -> EvalResult {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(source.as_bytes()).unwrap();
+    let file_table = FileTable::new();
+    let file_id = file_table
+        .register_file(file.path().to_string_lossy().into_owned())
+        .unwrap();
+    let ast = ParseContext::new(&file_table)
+        .parse(&CaraParser, file_id)
+        .unwrap();
+    let ctx = QueryContext::builder()
+        .crate_name("main".into())
+        .ast(Arc::new(simplifier::simplify("main".into(), ast)))
+        .target(Target::default())
+        .build();
+    let def_id = ctx.lookup_def_id(format!("::main::{}", name)).unwrap();
+    ctx.query(&TRY_CONST_EVAL_PROVIDER, def_id).unwrap()
+}
+
+/// The start and end of the first occurrence of `text` in `source`.
+fn span_of(source: &str, text: &str) -> (usize, usize) {
+    let start = source.find(text).unwrap();
+    (start, start + text.len())
+}
+
+fn expect_error(source: &str, name: &str, text: &str) -> EvalError {
+    let (error, span) = eval(source, name).unwrap_err();
+    assert_eq!((span.start(), span.end()), span_of(source, text));
+    error
+}
+
+#[test]
+fn u128_is_computed_in_128_bits() {
+    let source = "const A = 18446744073709551615u128 * 18446744073709551615u128;";
+    let value = eval(source, "A").unwrap();
+    assert_eq!(
+        value.as_int() as u128,
+        18446744073709551615u128 * 18446744073709551615
+    );
+
+    let source = "const A = 340282366920938463463374607431768211455u128 / 3u128;";
+    let value = eval(source, "A").unwrap();
+    assert_eq!(value.as_int() as u128, u128::MAX / 3);
+}
+
+#[test]
+fn i128_is_computed_in_128_bits() {
+    let source = "const A = 9223372036854775807i128 * 4i128;";
+    assert_eq!(eval(source, "A").unwrap().as_int(), i64::MAX as i128 * 4);
+
+    let source = "const A = 0i128 - 9223372036854775807i128 * 9223372036854775807i128;";
+    assert_eq!(
+        eval(source, "A").unwrap().as_int(),
+        -(i64::MAX as i128 * i64::MAX as i128)
+    );
+}
+
+#[test]
+fn u128_overflow_is_an_error() {
+    let text = "340282366920938463463374607431768211455u128 + 1u128";
+    let source = format!("const A = {};", text);
+    let error = expect_error(&source, "A", text);
+    assert!(matches!(error, EvalError::Overflow(_, ty) if ty == "u128"));
+}
+
+#[test]
+fn narrow_overflow_is_an_error() {
+    let source = "const A = 200u8 + 56u8;";
+    let error = expect_error(source, "A", "200u8 + 56u8");
+    assert!(
+        matches!(error, EvalError::Overflow(operation, ty) if operation == "200 + 56" && ty == "u8")
+    );
+
+    let source = "const A = 0u64 - 1u64;";
+    let error = expect_error(source, "A", "0u64 - 1u64");
+    assert!(matches!(error, EvalError::Overflow(_, ty) if ty == "u64"));
+}
+
+#[test]
+fn shifts_check_the_width() {
+    let source = "const A = 1u128 << 127u128;";
+    assert_eq!(eval(source, "A").unwrap().as_int() as u128, 1 << 127);
+
+    let source = "const A = 1u128 << 128u128;";
+    let error = expect_error(source, "A", "1u128 << 128u128");
+    assert!(matches!(error, EvalError::ShiftOutOfRange(amount, _) if amount == "128"));
+}
+
+#[test]
+fn division_by_zero_is_an_error() {
+    let source = "const A = 1i128 / 0i128;";
+    let error = expect_error(source, "A", "1i128 / 0i128");
+    assert!(matches!(error, EvalError::DivisionByZero));
+}
+
+#[test]
+fn literals_are_checked_at_the_u64_and_i64_boundary() {
+    let source = "const A = 18446744073709551615u64;";
+    assert_eq!(eval(source, "A").unwrap().as_int() as u64, u64::MAX);
+
+    let source = "const A = 18446744073709551616u128;";
+    assert_eq!(eval(source, "A").unwrap().as_int(), 1 << 64);
+
+    let source = "const A = -9223372036854775808i64;";
+    assert_eq!(eval(source, "A").unwrap().as_int(), i64::MIN as i128);
+
+    let source = "const A = 9223372036854775808i64;";
+    let error = expect_error(source, "A", "9223372036854775808i64");
+    assert!(
+        matches!(error, EvalError::Overflow(literal, ty) if literal == "9223372036854775808" && ty == "i64")
+    );
+
+    let source = "const A = -9223372036854775809i64;";
+    let error = expect_error(source, "A", "-9223372036854775809i64");
+    assert!(matches!(error, EvalError::Overflow(literal, _) if literal == "-9223372036854775809"));
+}
+
+#[test]
+fn negative_patterns_match_in_the_scrutinee_type() {
+    let source = "const A = match -1i32 { 1 => 1i32, -1 => 2i32, _ => 3i32 };";
+    assert_eq!(eval(source, "A").unwrap().as_int(), 2);
+
+    let source = "const A = match 18446744073709551615i128 { -1 => 1i32, _ => 2i32 };";
+    assert_eq!(eval(source, "A").unwrap().as_int(), 2);
+
+    let source = "const A = match -18446744073709551617i128 { -1 => 1i32, -18446744073709551617 => 2i32, _ => 3i32 };";
+    assert_eq!(eval(source, "A").unwrap().as_int(), 2);
+}
@@ -1,6 +1,8 @@
 use std::fmt::Display;
 
-use annotate_snippets::{AnnotationKind, Group, Level, Renderer, Snippet, renderer::DecorStyle};
+use annotate_snippets::{
+    Annotation, AnnotationKind, Group, Level, Renderer, Snippet, renderer::DecorStyle,
+};
 use ast::{FileTable, Span};
 
 pub struct LintDumper<'d> {
@@ -23,25 +25,57 @@ impl LintDumper<'_> {
         I: Iterator<Item = &'a (T, Span)>,
     {
         for (error, span) in lints {
-            let file = span.file();
-            let path = self.file_table.get_path(file).unwrap();
-            let source_code = (*self.file_table.read_source(file).unwrap()).clone();
-
-            let error = format!("{}", error);
-
-            self.report.push(
-                Level::ERROR.primary_title(error.clone()).element(
-                    Snippet::source(source_code).path(path).annotation(
-                        AnnotationKind::Primary
-                            .span(span.start()..span.end())
-                            .label(error),
-                    ),
-                ),
-            );
+            self.lint(error, *span, None);
         }
         self
     }
 
+    pub fn lint(
+        &mut self,
+        error: impl Display,
+        span: Span,
+        related: Option<(String, Span)>,
+    ) -> &mut Self {
+        let error = format!("{}", error);
+        let primary = AnnotationKind::Primary
+            .span(span.start()..span.end())
+            .label(error.clone());
+
+        let title = Level::ERROR.primary_title(error);
+        let group = match related {
+            Some((label, related)) => {
+                let context = AnnotationKind::Context
+                    .span(related.start()..related.end())
+                    .label(label);
+                if related.file() == span.file() {
+                    title.element(self.snippet(span).annotation(primary).annotation(context))
+                } else {
+                    title
+                        .element(self.snippet(span).annotation(primary))
+                        .element(self.snippet(related).annotation(context))
+                }
+            }
+            None => title.element(self.snippet(span).annotation(primary)),
+        };
+        self.report.push(group);
+        self
+    }
+
+    pub fn help(&mut self, help: impl Display) -> &mut Self {
+        if let Some(group) = self.report.pop() {
+            self.report
+                .push(group.element(Level::HELP.message(format!("{}", help))));
+        }
+        self
+    }
+
+    fn snippet<'a>(&self, span: Span) -> Snippet<'a, Annotation<'a>> {
+        let file = span.file();
+        let path = self.file_table.get_path(file).unwrap();
+        let source_code = (*self.file_table.read_source(file).unwrap()).clone();
+        Snippet::source(source_code).path(path)
+    }
+
     pub fn dump(&self) {
         if self.report.is_empty() {
             return;
@@ -14,7 +14,11 @@ impl BlockVisitor<()> for MonomorphizeContext {
     fn visit_inline_asm(&mut self, _inline_asm: &ast::InlineAsm) {}
 
     fn visit_var_def(&mut self, var_def: &ast::VarDef) {
-        self.visit_right_value(var_def.initial_value);
-        self.locals.push(var_def.name.clone());
+        if let Some(value) = var_def.initial_value {
+            self.visit_right_value(value);
+        }
+        for name in var_def.binding.names() {
+            self.locals.push(name.clone());
+        }
     }
 }
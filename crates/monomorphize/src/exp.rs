@@ -1,9 +1,9 @@
 use std::sync::Arc;
 
-use ast::{Array, Span, StructType, TypeEnum, visitor::ExpVisitor};
-use const_eval::{ValueKind, queries::CONST_EVAL_PROVIDER};
+use ast::{Array, Exp, InterfaceType, Span, StructType, TypeEnum, visitor::ExpVisitor};
+use const_eval::{Bindings, TypeKind, ValueKind, queries::CONST_EVAL_PROVIDER};
 
-use crate::{CodegenItem, MonomorphizeContext};
+use crate::{CodegenItem, MonomorphizeContext, callee_function, generic_callee, method_candidates};
 
 impl ExpVisitor<()> for MonomorphizeContext {
     fn ast_ctx(&self) -> std::sync::Arc<ast::AstContext> {
@@ -19,7 +19,7 @@ impl ExpVisitor<()> for MonomorphizeContext {
                     self.visit_right_value(*element);
                 }
             }
-            _ => unimplemented!(),
+            Array::Template(value, _, _) => self.visit_right_value(*value),
         }
     }
 
@@ -33,6 +33,27 @@ impl ExpVisitor<()> for MonomorphizeContext {
         for &arg in &call.args {
             self.visit_right_value(arg);
         }
+        if let Some(func) = callee_function(&self.ctx, call.func)
+            && const_eval::is_type_function(&self.ctx, &func)
+        {
+            return;
+        }
+        if let Some(func) = generic_callee(&self.ctx, call.func) {
+            self.required_items.insert(CodegenItem::instantiate(
+                self.ctx.clone(),
+                func,
+                &call.args,
+                &self.bindings,
+            ));
+            return;
+        }
+        if let Exp::FieldAccess(field_access) = self.ctx.ast_ctx().exp(call.func) {
+            self.required_items.extend(
+                method_candidates(&self.ctx, &field_access.field)
+                    .into_iter()
+                    .map(CodegenItem::Func),
+            );
+        }
         self.visit_right_value(call.func)
     }
 
@@ -55,9 +76,20 @@ impl ExpVisitor<()> for MonomorphizeContext {
         self.visit_right_value(index.index);
     }
 
+    fn visit_slice(&mut self, slice: &ast::Slice) {
+        self.visit_left_value(slice.exp);
+        for bound in slice.start.iter().chain(&slice.end) {
+            self.visit_right_value(*bound);
+        }
+    }
+
     fn visit_number(&mut self, _number: &ast::Number) {}
 
-    fn visit_str(&mut self, _string: &str) {}
+    fn visit_float(&mut self, _float: &ast::Float) {}
+
+    fn visit_bool(&mut self, _value: bool) {}
+
+    fn visit_str(&mut self, _string: &[u8]) {}
 
     fn visit_unary(&mut self, _op: &ast::UnaryOp, _value: (), _: &Span) {}
 
@@ -69,7 +101,7 @@ impl ExpVisitor<()> for MonomorphizeContext {
             let def_id = self.ctx.lookup_def_id(name).unwrap();
             let result = self.ctx.query_cached(&CONST_EVAL_PROVIDER, def_id).unwrap();
             match result.kind() {
-                ValueKind::Function(func) => {
+                ValueKind::Function(func) if !func.is_generic() => {
                     self.required_items.insert(CodegenItem::Func(func.clone()));
                 }
                 ValueKind::Proto(proto) => {
@@ -83,6 +115,24 @@ impl ExpVisitor<()> for MonomorphizeContext {
 
     fn visit_type_cast(&mut self, type_cast: &ast::TypeCast) {
         self.visit_right_value(type_cast.exp);
+        // The vtable built by `ptr as dyn I` refers to the methods of `I`.
+        let target = const_eval::eval_exp(self.ctx.clone(), type_cast.ty, &self.bindings).as_type();
+        if let TypeKind::Primary(target) = target.as_ref()
+            && let TypeEnum::Dyn(interface) = &target.kind
+            && let TypeKind::Primary(interface) =
+                const_eval::eval_exp(self.ctx.clone(), *interface, &Bindings::new())
+                    .as_type()
+                    .as_ref()
+            && let TypeEnum::Interface(InterfaceType { methods, .. }) = &interface.kind
+        {
+            for (name, _) in methods {
+                self.required_items.extend(
+                    method_candidates(&self.ctx, name)
+                        .into_iter()
+                        .map(CodegenItem::Func),
+                );
+            }
+        }
     }
 
     fn visit_structure(&mut self, structure: &ast::Structure) {
@@ -92,15 +142,25 @@ impl ExpVisitor<()> for MonomorphizeContext {
         }
     }
 
+    fn visit_tuple(&mut self, tuple: &ast::Tuple) {
+        for &element in &tuple.elements {
+            self.visit_right_value(element);
+        }
+    }
+
     fn visit_field_access(&mut self, field_access: &ast::FieldAccess) {
         self.visit_right_value(field_access.lhs);
     }
 
     fn visit_type(&mut self, type_: &ast::Type) {
         if let TypeEnum::Structure(StructType { fields, .. }) = &type_.kind {
-            for (_, &ty) in fields.iter() {
-                self.visit_right_value(ty);
+            for (_, ty) in fields.iter() {
+                self.visit_right_value(*ty);
             }
         }
     }
+
+    fn visit_variant(&mut self, _variant: &ast::EnumVariant) {}
+
+    fn visit_builtin(&mut self, _builtin: &ast::BuiltinCall) {}
 }
@@ -4,7 +4,8 @@ use std::{
     sync::Arc,
 };
 
-use ast::{FunctionDef, ProtoDef};
+use ast::{Exp, ExpId, FunctionDef, ProtoDef};
+use const_eval::{Bindings, Value, ValueKind, queries::CONST_EVAL_PROVIDER};
 use query::QueryContext;
 use symbol_table::SymbolTable;
 
@@ -16,13 +17,72 @@ mod stmt;
 #[derive(Clone)]
 pub enum CodegenItem {
     Func(Arc<FunctionDef>),
+    Instance(Instance),
     Proto(Arc<ProtoDef>),
 }
 
+/// A generic function together with the values its `comptime` parameters are
+/// bound to.
+#[derive(Clone)]
+pub struct Instance {
+    pub func: Arc<FunctionDef>,
+    pub comptime_args: Vec<(String, Value)>,
+    pub symbol: String,
+}
+
+impl Instance {
+    pub fn bindings(&self) -> Bindings {
+        self.comptime_args.iter().cloned().collect()
+    }
+}
+
+impl CodegenItem {
+    pub fn instantiate(
+        ctx: Arc<QueryContext>,
+        func: Arc<FunctionDef>,
+        args: &[ExpId],
+        bindings: &Bindings,
+    ) -> Self {
+        let comptime_args = func
+            .params
+            .iter()
+            .zip(args)
+            .filter(|(param, _)| param.comptime)
+            .map(|(param, &arg)| {
+                let mut value = const_eval::eval_exp(ctx.clone(), arg, bindings);
+                if !matches!(value.kind(), ValueKind::Type(_)) {
+                    let ty = const_eval::eval_exp(ctx.clone(), param.param_type, bindings);
+                    value.set_type(ty.as_type());
+                }
+                (param.name.clone(), value)
+            })
+            .collect::<Vec<_>>();
+
+        let symbol = format!(
+            "{}<{}>",
+            const_eval::defining_name(&ctx, &func),
+            comptime_args
+                .iter()
+                .map(|(_, value)| value.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        CodegenItem::Instance(Instance {
+            func,
+            comptime_args,
+            symbol,
+        })
+    }
+}
+
 impl PartialEq for CodegenItem {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (CodegenItem::Func(func1), CodegenItem::Func(func2)) => Arc::ptr_eq(func1, func2),
+            (CodegenItem::Instance(inst1), CodegenItem::Instance(inst2)) => {
+                Arc::ptr_eq(&inst1.func, &inst2.func) && inst1.symbol == inst2.symbol
+            }
             (CodegenItem::Proto(proto1), CodegenItem::Proto(proto2)) => Arc::ptr_eq(proto1, proto2),
             _ => false,
         }
@@ -35,6 +95,10 @@ impl Hash for CodegenItem {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match self {
             CodegenItem::Func(func) => Arc::as_ptr(func).hash(state),
+            CodegenItem::Instance(inst) => {
+                Arc::as_ptr(&inst.func).hash(state);
+                inst.symbol.hash(state);
+            }
             CodegenItem::Proto(proto) => Arc::as_ptr(proto).hash(state),
         }
     }
@@ -43,5 +107,50 @@ impl Hash for CodegenItem {
 struct MonomorphizeContext {
     ctx: Arc<QueryContext>,
     locals: SymbolTable<String>,
+    bindings: Bindings,
     required_items: HashSet<CodegenItem>,
 }
+
+pub fn callee_function(ctx: &Arc<QueryContext>, callee: ExpId) -> Option<Arc<FunctionDef>> {
+    let Exp::Var(var) = ctx.ast_ctx().exp(callee).clone() else {
+        return None;
+    };
+    let def_id = ctx.lookup_def_id(var.path.path.join("::"))?;
+    match ctx.query_cached(&CONST_EVAL_PROVIDER, def_id)?.kind() {
+        ValueKind::Function(func) => Some(func),
+        _ => None,
+    }
+}
+
+pub fn generic_callee(ctx: &Arc<QueryContext>, callee: ExpId) -> Option<Arc<FunctionDef>> {
+    callee_function(ctx, callee)
+        .filter(|func| func.is_generic() && !const_eval::is_type_function(ctx, func))
+}
+
+/// Returns every method named `name`. Which one a call like `value.name()`
+/// picks depends on the type of `value`, which isn't known here, so all of
+/// them are generated.
+pub fn method_candidates(ctx: &Arc<QueryContext>, name: &str) -> Vec<Arc<FunctionDef>> {
+    let suffix = format!("::{}", name);
+    ctx.def_ids()
+        .into_iter()
+        .filter(|&def_id| {
+            ctx.get_def(def_id)
+                .is_some_and(|const_def| const_def.name.ends_with(&suffix))
+        })
+        .filter_map(
+            |def_id| match ctx.query_cached(&CONST_EVAL_PROVIDER, def_id)?.kind() {
+                ValueKind::Function(func)
+                    if !func.is_generic()
+                        && func
+                            .params
+                            .first()
+                            .is_some_and(|param| param.name == "self") =>
+                {
+                    Some(func)
+                }
+                _ => None,
+            },
+        )
+        .collect()
+}
@@ -4,7 +4,7 @@ use std::{
 };
 
 use ast::{FunctionDef, visitor::ExpVisitor};
-use const_eval::queries::CONST_EVAL_PROVIDER;
+use const_eval::{Bindings, queries::CONST_EVAL_PROVIDER};
 use query::{Provider, QueryContext};
 use symbol_table::SymbolTable;
 
@@ -33,10 +33,15 @@ fn collect_codegen_units(ctx: Arc<QueryContext>, (): ()) -> Vec<CodegenItem> {
     while !new_ones.is_empty() {
         let mut new_new_ones = Vec::new();
         for new_one in new_ones.iter() {
-            let CodegenItem::Func(new_one) = new_one else {
-                continue;
+            let collected = match new_one {
+                CodegenItem::Func(func) => {
+                    collect_required_items(ctx.clone(), func.clone(), Bindings::new())
+                }
+                CodegenItem::Instance(inst) => {
+                    collect_required_items(ctx.clone(), inst.func.clone(), inst.bindings())
+                }
+                CodegenItem::Proto(_) => continue,
             };
-            let collected = collect_required_items(ctx.clone(), new_one.clone());
             for item in collected {
                 if !required.contains(&item) {
                     required.insert(item.clone());
@@ -54,10 +59,12 @@ fn collect_codegen_units(ctx: Arc<QueryContext>, (): ()) -> Vec<CodegenItem> {
 fn collect_required_items(
     ctx: Arc<QueryContext>,
     func_def: Arc<FunctionDef>,
+    bindings: Bindings,
 ) -> HashSet<CodegenItem> {
     let mut visitor_ctx = MonomorphizeContext {
         ctx,
         locals: SymbolTable::new(),
+        bindings,
         required_items: HashSet::new(),
     };
 
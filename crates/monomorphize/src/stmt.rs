@@ -34,6 +34,7 @@ impl StatementVisitor<()> for MonomorphizeContext {
     }
 
     fn visit_if_exp(&mut self, if_exp: &ast::IfExp) {
+        self.visit_right_value(if_exp.condition);
         self.visit_block(&if_exp.then_branch);
         if let Some(else_block) = if_exp.else_branch.as_ref() {
             self.visit_block(else_block);
@@ -43,6 +44,23 @@ impl StatementVisitor<()> for MonomorphizeContext {
         }
     }
 
-    fn visit_break(&mut self, _span: ast::Span) {}
-    fn visit_continue(&mut self, _span: ast::Span) {}
+    fn visit_match(&mut self, match_: &ast::Match) {
+        self.visit_right_value(match_.exp);
+        for arm in &match_.arms {
+            self.locals.push_scope();
+            if let ast::Pattern::Variant(_, Some(binding), _) = &arm.pattern {
+                self.locals.push(binding.clone());
+            }
+            self.visit_right_value(arm.body);
+            self.locals.pop_scope();
+        }
+    }
+
+    fn visit_break(&mut self, break_: &ast::Break) {
+        if let Some(value) = break_.value {
+            self.visit_right_value(value);
+        }
+    }
+
+    fn visit_continue(&mut self, _continue: &ast::Continue) {}
 }
@@ -0,0 +1,78 @@
+use ast::{FileTable, Span};
+use lint::LintDumper;
+use thiserror::Error;
+
+#[derive(Debug, Clone, Error)]
+pub enum Error {
+    #[error("{0}")]
+    Lexing(String),
+    #[error("Expected {expected}, found {found}.")]
+    Unexpected {
+        expected: String,
+        found: String,
+        unclosed: Option<(String, Span)>,
+        help: Option<&'static str>,
+    },
+    #[error("Expected `;` after this.")]
+    MissingSemi { after_const: bool },
+    #[error("Module '{0}' not found.")]
+    ModuleNotFound(String),
+    #[error("Failed to read '{0}': {1}.")]
+    UnreadableModule(String, String),
+    #[error("Circular import: {}.", .0.join(" -> "))]
+    CircularImport(Vec<String>),
+}
+
+impl Error {
+    pub fn related(&self) -> Option<(String, Span)> {
+        match self {
+            Error::Unexpected {
+                unclosed: Some((bracket, span)),
+                ..
+            } => Some((format!("this `{}` is never closed", bracket), *span)),
+            _ => None,
+        }
+    }
+
+    pub fn help(&self) -> Option<&'static str> {
+        match self {
+            Error::Unexpected { help, .. } => *help,
+            Error::MissingSemi { after_const: true } => {
+                Some("Every `const` definition ends with `;`, even when its value ends with `}`.")
+            }
+            Error::CircularImport(_) => {
+                Some("A module can't import a file that imports it, directly or not.")
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ParseErrors {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("could not parse the file due to {} error(s)", .0.len())]
+    Errors(Vec<(Error, Span)>),
+}
+
+impl ParseErrors {
+    pub fn dump(&self, file_table: &FileTable) {
+        let errors = match self {
+            ParseErrors::Io(error) => {
+                eprintln!("{}", error);
+                return;
+            }
+            ParseErrors::Errors(errors) => errors,
+        };
+
+        let mut dumper = LintDumper::new(file_table);
+        for (error, span) in errors {
+            dumper.lint(error, *span, error.related());
+            if let Some(help) = error.help() {
+                dumper.help(help);
+            }
+        }
+        dumper.dump();
+    }
+}
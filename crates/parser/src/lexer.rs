@@ -1,13 +1,14 @@
-use std::{fmt::Display, ops::Deref, sync::Arc};
+use std::{collections::HashMap, fmt::Display, ops::Deref, sync::Arc};
 
 use line_column::line_column;
-use logos::{Logos, Source, Span};
+use logos::{Filter, Logos, Source, Span};
 use peg::{Parse, ParseLiteral, RuleResult};
-use unescaper::unescape;
 
 pub struct TokenStream {
     input: Arc<String>,
     tokens: Vec<(Token, Span)>,
+    docs: HashMap<usize, String>,
+    inner_docs: HashMap<usize, String>,
 }
 
 impl Deref for TokenStream {
@@ -20,27 +21,68 @@ impl Deref for TokenStream {
 impl TokenStream {
     pub fn new(input: Arc<String>) -> Result<Self, LexingErrors> {
         let mut errors = Vec::new();
-        let tokens = Token::lexer(&input)
-            .spanned()
-            .filter_map(|(token, span)| match token {
-                Ok(token) => Some((token, span)),
+        let mut tokens = Vec::new();
+        let mut docs = HashMap::new();
+        let mut inner_docs = HashMap::new();
+        for (token, span) in Token::lexer(&input).spanned() {
+            match token {
+                Ok(Token::Doc(line)) => push_doc_line(&mut docs, tokens.len(), line),
+                Ok(Token::InnerDoc(line)) => push_doc_line(&mut inner_docs, tokens.len(), line),
+                Ok(token) => tokens.push((token, span)),
                 Err(err) => {
-                    errors.push(err);
-                    None
+                    let span = err.span().unwrap_or(span);
+                    errors.push((err, span));
                 }
-            })
-            .collect();
+            }
+        }
 
         if !errors.is_empty() {
-            return Err(LexingErrors(errors));
+            return Err(LexingErrors { input, errors });
         }
 
-        Ok(Self { input, tokens })
+        Ok(Self {
+            input,
+            tokens,
+            docs,
+            inner_docs,
+        })
+    }
+
+    pub fn doc(&self, index: usize) -> Option<String> {
+        self.docs.get(&index).cloned()
+    }
+
+    pub fn inner_doc(&self, index: usize) -> Option<String> {
+        self.inner_docs.get(&index).cloned()
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub(crate) fn tokens_mut(&mut self) -> &mut Vec<(Token, Span)> {
+        &mut self.tokens
     }
 }
 
+fn push_doc_line(docs: &mut HashMap<usize, String>, index: usize, line: String) {
+    docs.entry(index)
+        .and_modify(|doc| {
+            doc.push('\n');
+            doc.push_str(&line);
+        })
+        .or_insert(line);
+}
+
 #[derive(Debug, Clone)]
-pub struct Sp(Span, Arc<String>);
+pub struct Sp(Span, Arc<String>, usize);
+
+impl Sp {
+    /// The index of the token, which is the length of the stream at its end.
+    pub fn index(&self) -> usize {
+        self.2
+    }
+}
 
 impl Display for Sp {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -68,6 +110,7 @@ impl Parse for TokenStream {
                 .get(p)
                 .map_or_else(|| 0..0, |(_, span)| span.clone()),
             Arc::clone(&self.input),
+            p,
         )
     }
 }
@@ -79,6 +122,10 @@ impl ParseLiteral for TokenStream {
         };
         match token {
             Token::RawIdent(ident) if ident == literal => RuleResult::Matched(pos + 1, ()),
+            _ if span.is_empty() => match token.inserted_text() == literal {
+                true => RuleResult::Matched(pos + 1, ()),
+                false => RuleResult::Failed,
+            },
             _ if self.input.slice(span.clone()).unwrap() == literal => {
                 RuleResult::Matched(pos + 1, ())
             }
@@ -88,20 +135,51 @@ impl ParseLiteral for TokenStream {
 }
 
 #[derive(Debug)]
-pub struct LexingErrors(Vec<LexingError>);
+pub struct LexingErrors {
+    input: Arc<String>,
+    errors: Vec<(LexingError, Span)>,
+}
+
+impl LexingErrors {
+    pub fn messages(&self) -> impl Iterator<Item = (String, Span)> + '_ {
+        self.errors.iter().map(|(error, span)| {
+            let text = &self.input[span.clone()];
+            let message = match error {
+                LexingError::Unexpected(c) => format!("Unexpected character {c:?}."),
+                LexingError::InvalidEscape(_) => format!("Invalid escape `{text}`."),
+                LexingError::InvalidChar(_) => {
+                    format!("`{text}` is not a single ASCII character or byte escape.")
+                }
+                LexingError::InvalidByte(_) => {
+                    format!("`{text}` may only hold ASCII characters and byte escapes.")
+                }
+                LexingError::UnterminatedString(_) => "Unterminated raw string.".to_string(),
+                LexingError::UnterminatedComment(_) => "Unterminated block comment.".to_string(),
+                LexingError::Other => format!("Invalid token `{text}`."),
+            };
+            (message, span.clone())
+        })
+    }
+}
 
 impl Display for LexingErrors {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for error in &self.0 {
-            writeln!(f, "{:?}", error)?;
+        for (message, span) in self.messages() {
+            let (line, column) = line_column(&self.input, span.start);
+            writeln!(f, "{line}:{column}: {message}")?;
         }
         Ok(())
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub enum LexingError {
     Unexpected(char),
+    InvalidEscape(Span),
+    InvalidChar(Span),
+    InvalidByte(Span),
+    UnterminatedString(Span),
+    UnterminatedComment(Span),
     #[default]
     Other,
 }
@@ -110,29 +188,215 @@ impl LexingError {
     fn from_lexer(lex: &mut logos::Lexer<'_, Token>) -> Self {
         LexingError::Unexpected(lex.slice().chars().next().unwrap())
     }
+
+    fn span(&self) -> Option<Span> {
+        match self {
+            LexingError::InvalidEscape(span)
+            | LexingError::InvalidChar(span)
+            | LexingError::InvalidByte(span)
+            | LexingError::UnterminatedString(span)
+            | LexingError::UnterminatedComment(span) => Some(span.clone()),
+            LexingError::Unexpected(_) | LexingError::Other => None,
+        }
+    }
+}
+
+/// `\x` escapes go up to `\x7F` in strings, and up to `\xFF` in `byte`
+/// literals, where `\u` escapes are ASCII only.
+fn unescape(body: &str, offset: usize, byte: bool) -> Result<String, LexingError> {
+    let mut result = String::new();
+    let mut chars = body.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        let invalid = |end: usize| LexingError::InvalidEscape(offset + start..offset + end);
+        let Some((index, escape)) = chars.next() else {
+            return Err(invalid(body.len()));
+        };
+        let mut end = index + escape.len_utf8();
+        let c = match escape {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            '\\' | '\'' | '"' => escape,
+            // A line break and the indentation after it are skipped.
+            '\n' => {
+                while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+                continue;
+            }
+            'x' => {
+                let digits = (0..2)
+                    .map_while(|_| chars.next_if(|(_, c)| c.is_ascii_hexdigit()))
+                    .map(|(_, c)| c)
+                    .collect::<String>();
+                end += digits.len();
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(value) if digits.len() == 2 && (byte || value.is_ascii()) => {
+                        char::from(value)
+                    }
+                    _ => return Err(invalid(end)),
+                }
+            }
+            'u' => {
+                let mut digits = String::new();
+                let mut closed = false;
+                if chars.next_if(|(_, c)| *c == '{').is_some() {
+                    end += 1;
+                    for (index, c) in chars.by_ref() {
+                        end = index + c.len_utf8();
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        digits.push(c);
+                    }
+                }
+                match u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                {
+                    Some(c) if closed && digits.len() <= 6 && (!byte || c.is_ascii()) => c,
+                    _ => return Err(invalid(end)),
+                }
+            }
+            _ => return Err(invalid(end)),
+        };
+        result.push(c);
+    }
+    Ok(result)
+}
+
+fn block_comment(lex: &mut logos::Lexer<'_, Token>) -> Result<(), LexingError> {
+    let rest = lex.remainder();
+    let mut depth = 1;
+    let mut index = 0;
+    while index < rest.len() {
+        if rest[index..].starts_with("/*") {
+            depth += 1;
+            index += 2;
+        } else if rest[index..].starts_with("*/") {
+            depth -= 1;
+            index += 2;
+            if depth == 0 {
+                lex.bump(index);
+                return Ok(());
+            }
+        } else {
+            index += 1;
+        }
+    }
+    let opening = lex.span();
+    lex.bump(rest.len());
+    Err(LexingError::UnterminatedComment(opening))
+}
+
+fn doc_line(lex: &mut logos::Lexer<'_, Token>) -> Filter<String> {
+    let slice = lex.slice();
+    // `////` starts an ordinary comment.
+    if slice.starts_with("////") {
+        return Filter::Skip;
+    }
+    let text = slice[3..].trim_end_matches('\r');
+    Filter::Emit(text.strip_prefix(' ').unwrap_or(text).to_string())
+}
+
+fn string(lex: &mut logos::Lexer<'_, Token>) -> Result<String, LexingError> {
+    let slice = lex.slice();
+    unescape(&slice[1..slice.len() - 1], lex.span().start + 1, false)
+}
+
+fn byte_string(lex: &mut logos::Lexer<'_, Token>) -> Result<Vec<u8>, LexingError> {
+    let slice = lex.slice();
+    let body = &slice[2..slice.len() - 1];
+    let text = unescape(body, lex.span().start + 2, true)?;
+    if !body.is_ascii() {
+        return Err(LexingError::InvalidByte(lex.span()));
+    }
+    text.chars()
+        .map(|c| u8::try_from(c).map_err(|_| LexingError::InvalidByte(lex.span())))
+        .collect()
+}
+
+fn raw_string(lex: &mut logos::Lexer<'_, Token>) -> Result<String, LexingError> {
+    let closing = format!("\"{}", &lex.slice()[1..lex.slice().len() - 1]);
+    match lex.remainder().find(&closing) {
+        Some(len) => {
+            let body = lex.remainder()[..len].to_string();
+            lex.bump(len + closing.len());
+            Ok(body)
+        }
+        None => {
+            let opening = lex.span();
+            lex.bump(lex.remainder().len());
+            Err(LexingError::UnterminatedString(opening))
+        }
+    }
+}
+
+fn char_literal(lex: &mut logos::Lexer<'_, Token>) -> Result<u8, LexingError> {
+    let slice = lex.slice();
+    let body = &slice[1..slice.len() - 1];
+    let text = unescape(body, lex.span().start + 1, true)?;
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if body.starts_with('\\') || c.is_ascii() => {
+            u8::try_from(c).map_err(|_| LexingError::InvalidChar(lex.span()))
+        }
+        _ => Err(LexingError::InvalidChar(lex.span())),
+    }
 }
 
 impl std::error::Error for LexingErrors {}
 
+impl Token {
+    pub(crate) fn inserted_text(&self) -> &'static str {
+        match self {
+            Token::Semi => ";",
+            Token::RightParen => ")",
+            Token::RightBrace => "}",
+            Token::RightBracket => "]",
+            _ => "",
+        }
+    }
+}
+
 #[derive(Logos, Debug, PartialEq, Eq)]
 #[logos(skip(r"[ \t\r\n\f]+"))]
 #[logos(skip(r"\/\/[^\n]*"))]
-#[logos(skip(r"\/\* [^\*\/]* \*\/"))]
+#[logos(skip(r"\/\*", block_comment))]
 #[logos(error(LexingError, LexingError::from_lexer))]
 pub enum Token {
-    #[regex(r"\d+[iu]size", |lex| lex.slice().to_string())]
-    #[regex(r"\d+[iu][1-9]\d*", |lex| lex.slice().to_string())]
-    #[regex(r"\d+", |lex| lex.slice().to_string())]
+    #[regex(
+        r"(\d[\d_]*|0x_*[0-9a-fA-F][0-9a-fA-F_]*|0o_*[0-7][0-7_]*|0b_*[01][01_]*)([iu](size|[1-9]\d*))?",
+        |lex| lex.slice().to_string()
+    )]
     Number(String),
+    #[regex(r"\d[\d_]*\.\d[\d_]*([eE][+-]?_*\d[\d_]*)?(f32|f64)?", |lex| lex.slice().to_string())]
+    #[regex(r"\d[\d_]*[eE][+-]?_*\d[\d_]*(f32|f64)?", |lex| lex.slice().to_string())]
+    #[regex(r"\d[\d_]*(f32|f64)", |lex| lex.slice().to_string())]
+    Float(String),
     #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*", |lex| lex.slice().to_string())]
     Ident(String),
     #[regex(r#"\@\"[^\"]*\""#, |lex| lex.slice().to_string())]
     RawIdent(String),
-    #[regex(r#"\"[^\"]*\""#, |lex| {
-        let s = lex.slice().trim_start_matches('"').trim_end_matches('"');
-        unescape(s).unwrap()
-    })]
+    #[regex(r"@[a-zA-Z_][a-zA-Z0-9_]*", |lex| lex.slice().to_string())]
+    Builtin(String),
+    #[regex(r"'[a-zA-Z_][a-zA-Z0-9_]*", |lex| lex.slice()[1..].to_string())]
+    Label(String),
+    #[regex(r"\/\/\/[^\n]*", doc_line, allow_greedy = true)]
+    Doc(String),
+    #[regex(r"\/\/![^\n]*", doc_line, allow_greedy = true)]
+    InnerDoc(String),
+    #[regex(r#""([^"\\]|\\[\s\S])*""#, string)]
+    #[regex(r##"r#*""##, raw_string)]
     String(String),
+    #[regex(r#"b"([^"\\]|\\[\s\S])*""#, byte_string)]
+    ByteString(Vec<u8>),
+    #[regex(r"'([^'\\\n]|\\[^\n][^'\n]*)'", char_literal)]
+    Char(u8),
 
     #[token(";")]
     Semi,
@@ -176,4 +440,90 @@ pub enum Token {
     And,
     #[token("|")]
     Or,
+    #[token("^")]
+    Caret,
+    #[token("~")]
+    Tilde,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{LexingError, TokenStream};
+
+    /// The errors lexing `input` gives, with the text each one points at.
+    fn errors(input: &str) -> Vec<(LexingError, &str)> {
+        let Err(errors) = TokenStream::new(Arc::new(input.to_string())) else {
+            panic!("{input:?} lexed without errors");
+        };
+        errors
+            .errors
+            .into_iter()
+            .map(|(error, span)| (error, &input[span]))
+            .collect()
+    }
+
+    #[test]
+    fn strings_reject_invalid_escapes() {
+        let input = r#"let a = "ok \q"; let b = "\x80"; let c = "\u{110000}";"#;
+        assert_eq!(
+            errors(input),
+            [
+                (LexingError::InvalidEscape(12..14), r"\q"),
+                (LexingError::InvalidEscape(26..30), r"\x80"),
+                (LexingError::InvalidEscape(42..52), r"\u{110000}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn escapes_must_be_complete() {
+        let errors = errors(r#""\x4" "\u{41" "\u41""#);
+        let texts = errors.iter().map(|(_, text)| *text).collect::<Vec<_>>();
+        assert_eq!(texts, [r"\x4", r"\u{41", r"\u"]);
+        assert!(
+            errors
+                .iter()
+                .all(|(error, _)| matches!(error, LexingError::InvalidEscape(_)))
+        );
+    }
+
+    #[test]
+    fn chars_hold_a_single_byte() {
+        assert_eq!(errors("'é'"), [(LexingError::InvalidChar(0..4), "'é'")]);
+        assert_eq!(
+            errors(r"'\nx'"),
+            [(LexingError::InvalidChar(0..5), r"'\nx'")]
+        );
+        assert_eq!(
+            errors(r"'\u{e9}'"),
+            [(LexingError::InvalidEscape(1..7), r"\u{e9}")]
+        );
+    }
+
+    #[test]
+    fn byte_strings_hold_ascii_and_byte_escapes() {
+        let errors = errors(r#"b"\xFF" b"é" b"\u{e9}""#);
+        assert_eq!(
+            errors,
+            [
+                (LexingError::InvalidByte(8..13), "b\"é\""),
+                (LexingError::InvalidEscape(16..22), r"\u{e9}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn block_comments_nest() {
+        let input = "/* a /* b */ c */ const";
+        let tokens = TokenStream::new(Arc::new(input.to_string())).unwrap();
+        assert_eq!(tokens.len(), 1);
+
+        let input = "const /* a /* b */ c";
+        assert_eq!(
+            errors(input),
+            [(LexingError::UnterminatedComment(6..8), "/*")]
+        );
+    }
 }
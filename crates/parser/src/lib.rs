@@ -1,4 +1,7 @@
+pub use diagnostic::*;
 pub use parser::*;
 
+mod diagnostic;
 mod lexer;
 mod parser;
+mod recovery;
@@ -1,10 +1,13 @@
-use std::{process::exit, sync::Arc};
+use std::{cell::RefCell, collections::HashMap, sync::Arc};
 
 use ast::*;
-use lint::LintDumper;
 use peg::RuleResult;
 
-use crate::lexer::{Token, TokenStream};
+use crate::{
+    Error, ParseErrors,
+    lexer::{Token, TokenStream},
+    recovery::{MAX_SYNTAX_ERRORS, Recovery},
+};
 
 #[derive(Default)]
 pub struct CaraParser;
@@ -15,16 +18,127 @@ impl CaraParser {
     }
 }
 
+impl CaraParser {
+    fn load_module(&self, ctx: &ParseContext<'_>, path: &str, span: Span) -> LoadedModule {
+        let Some(found) = ctx.find_module(path) else {
+            return Err(vec![(Error::ModuleNotFound(path.to_string()), span)]);
+        };
+        let unreadable = |error: std::io::Error| {
+            vec![(
+                Error::UnreadableModule(found.clone(), error.to_string()),
+                span,
+            )]
+        };
+        let file = ctx
+            .file_table()
+            .register_file(found.clone())
+            .map_err(unreadable)?;
+        if let Some(cycle) = ctx.import_cycle(file) {
+            let chain = cycle
+                .into_iter()
+                .chain([file])
+                .filter_map(|file| ctx.file_table().get_path(file))
+                .collect();
+            return Err(vec![(Error::CircularImport(chain), span)]);
+        }
+        match ctx.parse_module(self, file) {
+            Ok(module) => Ok(module),
+            Err(ParseErrors::Errors(errors)) => Err(errors),
+            Err(ParseErrors::Io(error)) => Err(unreadable(error)),
+        }
+    }
+}
+
+/// The modules a file imports, by the span of their `mod`. Each is loaded
+/// once, though the file is parsed again after every syntax error, and peg
+/// parses it again to report one.
+#[derive(Default)]
+struct Modules(RefCell<Vec<(Span, LoadedModule)>>);
+
+type LoadedModule = Result<StructType, Vec<(Error, Span)>>;
+
+impl Modules {
+    /// The module imported by the `mod` at `span`. One that failed to load
+    /// is empty, so that the file importing it can still be parsed.
+    fn load(&self, ctx: &ParseContext<'_>, path: &str, span: Span) -> StructType {
+        let loaded = self
+            .0
+            .borrow()
+            .iter()
+            .find(|(at, _)| *at == span)
+            .map(|(_, module)| module.clone());
+        let module = loaded.unwrap_or_else(|| {
+            let module = CaraParser.load_module(ctx, path, span);
+            self.0.borrow_mut().push((span, module.clone()));
+            module
+        });
+        module.unwrap_or_else(|_| empty_module(span))
+    }
+
+    fn has_errors(&self) -> bool {
+        self.0.borrow().iter().any(|(_, module)| module.is_err())
+    }
+
+    fn into_errors(self) -> impl Iterator<Item = (Error, Span)> {
+        self.0
+            .into_inner()
+            .into_iter()
+            .filter_map(|(_, module)| module.err())
+            .flatten()
+    }
+}
+
 impl Parser for CaraParser {
-    type Error = anyhow::Error;
+    type Error = ParseErrors;
 
     fn parse_content(
         &self,
         ctx: &ParseContext<'_>,
         content: Arc<String>,
-    ) -> anyhow::Result<StructType> {
-        let token_stream = TokenStream::new(content)?;
-        cara_parser::module_root(&token_stream, &token_stream, self, ctx).map_err(|e| e.into())
+    ) -> Result<StructType, ParseErrors> {
+        let mut tokens = match TokenStream::new(content) {
+            Ok(tokens) => tokens,
+            Err(errors) => {
+                let errors = errors
+                    .messages()
+                    .map(|(message, span)| (Error::Lexing(message), ctx.span(span.start, span.end)))
+                    .collect();
+                return Err(ParseErrors::Errors(errors));
+            }
+        };
+
+        let mut recovery = Recovery::default();
+        let modules = Modules::default();
+        loop {
+            let stop = match cara_parser::module_root(&tokens, &tokens, ctx, &modules) {
+                Ok(root) if recovery.errors.is_empty() && !modules.has_errors() => {
+                    return Ok(root);
+                }
+                Ok(_) => true,
+                Err(error) => {
+                    !recovery.recover(ctx, &mut tokens, &error)
+                        || recovery.errors.len() >= MAX_SYNTAX_ERRORS
+                }
+            };
+            if stop {
+                let mut errors = recovery.errors;
+                errors.extend(modules.into_errors());
+                return Err(ParseErrors::Errors(errors));
+            }
+        }
+    }
+}
+
+fn empty_module(span: Span) -> StructType {
+    StructType {
+        fields: Vec::new(),
+        field_docs: HashMap::new(),
+        members: Vec::new(),
+        namespace: None,
+        implements: Vec::new(),
+        layout: StructLayout::default(),
+        doc: None,
+        span,
     }
 }
 
@@ -35,8 +149,42 @@ macro_rules! binary_op_rule {
     }};
 }
 
+/// The value and suffix type of an integer literal such as `0xFF_FFu16`.
+/// Values too large for `u128` saturate, since no type can hold them
+/// anyway.
+fn parse_integer(literal: &str) -> Option<(u128, Option<TypeEnum>)> {
+    let (digits, radix) = match literal.get(..2) {
+        Some("0x") => (&literal[2..], 16),
+        Some("0o") => (&literal[2..], 8),
+        Some("0b") => (&literal[2..], 2),
+        _ => (literal, 10),
+    };
+    let (digits, ty) = match digits.find(['i', 'u']) {
+        Some(index) => {
+            let (digits, suffix) = digits.split_at(index);
+            let signed = suffix.starts_with('i');
+            let ty = match &suffix[1..] {
+                "size" if signed => TypeEnum::Isize,
+                "size" => TypeEnum::Usize,
+                width if signed => TypeEnum::Signed(width.parse().ok()?),
+                width => TypeEnum::Unsigned(width.parse().ok()?),
+            };
+            (digits, Some(ty))
+        }
+        None => (digits, None),
+    };
+    let num = digits
+        .chars()
+        .filter(|&c| c != '_')
+        .try_fold(0u128, |num, c| {
+            let digit = c.to_digit(radix)? as u128;
+            Some(num.saturating_mul(radix as u128).saturating_add(digit))
+        })?;
+    Some((num, ty))
+}
+
 peg::parser! {
-    grammar cara_parser<'s>(tokens: &TokenStream, pself: &CaraParser, parser: &ParseContext<'_>) for TokenStream {
+    grammar cara_parser<'s>(tokens: &TokenStream, parser: &ParseContext<'_>, modules: &Modules) for TokenStream {
         pub rule module_root() -> StructType
         = _ i: struct_inner() _ {
             i
@@ -78,8 +226,8 @@ peg::parser! {
         }
 
         rule const_def() -> ConstDef
-        = l: pos() "const" __ name: identifier() _ "=" _ value: const_initial_value() _ ";" r: pos() {
-            ConstDef { name, initial_value: value, span: parser.span(l, r) }
+        = doc: doc() l: pos() "const" __ name: identifier() _ "=" _ value: const_initial_value() _ ";" r: pos() {
+            ConstDef { name, initial_value: value, doc, span: parser.span(l, r) }
         }
 
         rule const_initial_value() -> ConstInitialValue
@@ -91,7 +239,9 @@ peg::parser! {
         = l: pos() _ "{" _ items: (block_item() ** _) _ return_value: expr()? _ "}" _ r: pos() {
             let mut items = items;
             let mut return_value = return_value;
-            if let Some((no_semi, item)) = items.pop() {
+            // A trailing block-like expression without `;` is the value of
+            // the block, unless another expression follows it.
+            if return_value.is_none() && let Some((no_semi, item)) = items.pop() {
                 if no_semi {
                     let BlockItem::Statement(Statement::Exp(exp)) = item else {
                         unreachable!()
@@ -113,10 +263,20 @@ peg::parser! {
         }
 
         rule var_def() -> VarDef
-        = l: pos() _ "let" __ mutable: ("mut"?) _ name: identifier() _
-                var_type: (":" _ t: expr() {t} )? _
-                "=" _ value: expr() _ ";" r: pos() {
-            VarDef { name, var_type, initial_value: value, mutable: mutable.is_some(), span: parser.span(l, r) }
+        = l: pos() _ "let" __ mutable: ("mut"?) _ binding: var_binding() _
+                var_type: (":" _ t: type_expr() {t} )? _
+                initial_value: ("=" _ v: expr() {v})? _ ";" r: pos() {?
+            if var_type.is_none() && initial_value.is_none() {
+                return Err("a type or an initial value");
+            }
+            Ok(VarDef { binding, var_type, initial_value, mutable: mutable.is_some(), span: parser.span(l, r) })
+        }
+
+        rule var_binding() -> VarBinding
+        = name: identifier() {
+            VarBinding::Name(name)
+        } / "(" _ names: (identifier() ** ("," _)) _ ","? _ ")" {
+            VarBinding::Tuple(names)
         }
 
         rule statement() -> (bool, Statement)
@@ -134,21 +294,25 @@ peg::parser! {
         }
 
         rule param() -> Param
-        = l: pos() name: identifier() _ ":" _ ty: expr() r: pos() {
-            Param { name, param_type: ty, span: parser.span(l, r) }
+        = l: pos() comptime: ("comptime" __)? name: identifier() _ ":" _ ty: expr() r: pos() {
+            Param { name, param_type: ty, comptime: comptime.is_some(), span: parser.span(l, r) }
         }
 
         rule expr() -> ExpId =
             p: proto_def() { parser.insert_exp(Exp::ProtoDef(p)) } /
             f: function_def() { parser.insert_exp(Exp::Function(f)) } /
             precedence!{
-                l: pos() "break" _ r: pos() {
+                l: pos() "break" _ label: label()? _ value: @ {
+                    let span = parser.span(l, value.span().end());
+                    parser.insert_exp(Exp::Break(Break { label, value: Some(value), span }))
+                }
+                l: pos() "break" _ label: label()? _ r: pos() {
                     let span = parser.span(l, r);
-                    parser.insert_exp(Exp::Break(span))
+                    parser.insert_exp(Exp::Break(Break { label, value: None, span }))
                 }
-                l: pos() "continue" _ r: pos() {
+                l: pos() "continue" _ label: label()? _ r: pos() {
                     let span = parser.span(l, r);
-                    parser.insert_exp(Exp::Continue(span))
+                    parser.insert_exp(Exp::Continue(Continue { label, span }))
                 }
                 l: pos() _ "return" __ rhs: @ {
                     let span = parser.span(l, rhs.span().end());
@@ -160,7 +324,19 @@ peg::parser! {
                 }
                 lhs: (@) _ "=" _ rhs: @ {
                     let span = parser.span(lhs.span().start(), rhs.span().end());
-                    parser.insert_exp(Exp::Assign(Assign { lhs, rhs, span }))
+                    parser.insert_exp(Exp::Assign(Assign { lhs, op: None, rhs, span }))
+                }
+                lhs: (@) _ op: compound_op() "=" _ rhs: @ {
+                    let span = parser.span(lhs.span().start(), rhs.span().end());
+                    parser.insert_exp(Exp::Assign(Assign { lhs, op: Some(op), rhs, span }))
+                }
+                --
+                l: (@) _ "|" "|" _ r: @ {
+                    binary_op_rule!(parser, l, r, Or)
+                }
+                --
+                l: (@) _ "&" "&" _ r: @ {
+                    binary_op_rule!(parser, l, r, And)
                 }
                 --
                 l: (@) _ "<" _ r: @ {
@@ -182,11 +358,16 @@ peg::parser! {
                     binary_op_rule!(parser, l, r, Ne)
                 }
                 --
-                l: (@) _ "&" "&" _ r: @ {
-                    binary_op_rule!(parser, l, r, And)
+                l: (@) _ "|" !"|" _ r: @ {
+                    binary_op_rule!(parser, l, r, BitOr)
                 }
-                l: (@) _ "|" "|" _ r: @ {
-                    binary_op_rule!(parser, l, r, Or)
+                --
+                l: (@) _ "^" _ r: @ {
+                    binary_op_rule!(parser, l, r, BitXor)
+                }
+                --
+                l: (@) _ "&" !"&" _ r: @ {
+                    binary_op_rule!(parser, l, r, BitAnd)
                 }
                 --
                 l: (@) _ "+" _ r: @ {
@@ -218,6 +399,10 @@ peg::parser! {
                     let span = parser.span(s, r.span().end());
                     parser.insert_exp(Exp::Unary(UnaryOp::Not, r, span))
                 }
+                s: pos() "~" _ r: (@) {
+                    let span = parser.span(s, r.span().end());
+                    parser.insert_exp(Exp::Unary(UnaryOp::BitNot, r, span))
+                }
                 s: pos() "*" _ r: (@) {
                     let span = parser.span(s, r.span().end());
                     parser.insert_exp(Exp::Unary(UnaryOp::Ptr, r, span))
@@ -235,6 +420,10 @@ peg::parser! {
                     parser.insert_exp(Exp::TypeCast(TypeCast { exp: l, ty: t, span }))
                 }
                 --
+                l: (@) _ "[" _ start: expr()? _ "." "." _ end: expr()? _ "]" r: pos() {
+                    let span = parser.span(l.span().start(), r);
+                    parser.insert_exp(Exp::Slice(Slice { exp: l, start, end, span }))
+                }
                 l: (@) _ "[" _ r: expr() _ "]" {
                     let span = parser.span(l.span().start(), r.span().end());
                     parser.insert_exp(Exp::Index(Index { exp: l, index: r, span }))
@@ -263,6 +452,16 @@ peg::parser! {
                         span
                     }))
                 }
+                l: @ "." indices: tuple_index() r: pos() {
+                    let span = parser.span(l.span().start(), r);
+                    indices.into_iter().fold(l, |lhs, index| {
+                        parser.insert_exp(Exp::FieldAccess(FieldAccess {
+                            lhs,
+                            field: index,
+                            span
+                        }))
+                    })
+                }
                 l: @ "." n: identifier() r: pos() {
                     let span = parser.span(l.span().start(), r);
                     parser.insert_exp(Exp::FieldAccess(FieldAccess {
@@ -291,13 +490,21 @@ peg::parser! {
                     let span = parser.span(l, r);
                     parser.insert_exp(Exp::Unit(span))
                 }
+                l: pos() "(" _ first: expr() _ "," _ rest: (expr() ** ("," _)) _ ","? _ ")" r: pos() {
+                    let elements = std::iter::once(first).chain(rest).collect();
+                    parser.insert_exp(Exp::Tuple(Tuple { elements, span: parser.span(l, r) }))
+                }
                 "(" _ e: expr() _ ")" {
                     e
                 }
                 n: number() { parser.insert_exp(n) }
+                f: float() { parser.insert_exp(Exp::Float(f)) }
                 s: string_wrapper() { parser.insert_exp(s) }
+                l: pos() "true" r: pos() { parser.insert_exp(Exp::Bool(true, parser.span(l, r))) }
+                l: pos() "false" r: pos() { parser.insert_exp(Exp::Bool(false, parser.span(l, r))) }
                 v: var() { parser.insert_exp(Exp::Var(v)) }
                 a: array() { parser.insert_exp(Exp::Array(a)) }
+                b: builtin() { parser.insert_exp(Exp::Builtin(b)) }
             }
 
         rule block_exp() -> ExpId
@@ -306,13 +513,30 @@ peg::parser! {
             l: loop_exp() { parser.insert_exp(Exp::Loop(l)) } /
             w: while_exp() { parser.insert_exp(Exp::While(w)) } /
             i: if_exp() { parser.insert_exp(Exp::IfExp(i)) } /
+            m: match_exp() { parser.insert_exp(Exp::Match(m)) } /
             b: block() { parser.insert_exp(Exp::Block(b)) }
 
+        rule compound_op() -> BinaryOp
+            = "+" { BinaryOp::Add } /
+            "-" { BinaryOp::Sub } /
+            "*" { BinaryOp::Mul } /
+            "/" { BinaryOp::Div } /
+            "%" { BinaryOp::Mod } /
+            "&" { BinaryOp::BitAnd } /
+            "|" { BinaryOp::BitOr } /
+            "^" { BinaryOp::BitXor } /
+            "<" "<" { BinaryOp::LShift } /
+            ">" ">" { BinaryOp::RShift }
+
+        rule loop_label() -> String
+            = label: label() _ ":" _ { label }
+
         rule for_exp() -> For
-            = l: pos() "for" __ v: identifier() _ "in" _
+            = l: pos() label: loop_label()? "for" __ v: identifier() _ "in" _
                 "(" _ s: expr() _ "," _ e: expr() _ step: ("," _ step: expr() {step})? _ ","? _ ")" _
                 b: block() r: pos() {
                 For {
+                    label,
                     var: v,
                     start: s,
                     end: e,
@@ -323,27 +547,51 @@ peg::parser! {
             }
 
         rule loop_exp() -> Loop
-            = l: pos() "loop" _ b: block() r: pos() {
+            = l: pos() label: loop_label()? "loop" _ b: block() r: pos() {
                 Loop {
+                    label,
                     body: b,
                     span: parser.span(l, r)
                 }
             }
 
         rule while_exp() -> While
-            = l: pos() "while" __ c: expr() _ b: block() r: pos() {
+            = l: pos() label: loop_label()? "while" __ c: expr() _ b: block() r: pos() {
                 While {
+                    label,
                     condition: c,
                     body: b,
                     span: parser.span(l, r)
                 }
             }
 
+        // `[T; N]` is also how array types are written; which one it is
+        // depends on whether `T` is a type.
         rule array() -> Array
-            = l: pos() "[" _ values: (expr() ** ("," _)) _ "]" r: pos() {
+            = l: pos() "[" _ value: expr() _ ";" _ len: expr() _ "]" r: pos() {
+                Array::Template(value, ConstExp { exp: len }, parser.span(l, r))
+            } / l: pos() "[" _ values: (expr() ** ("," _)) _ "]" r: pos() {
                 Array::List(values, parser.span(l, r))
             }
 
+        rule builtin() -> BuiltinCall
+            = l: pos() "@sizeOf" _ "(" _ ty: expr() _ ")" r: pos() {
+                BuiltinCall {
+                    builtin: Builtin::SizeOf(ty),
+                    span: parser.span(l, r)
+                }
+            } / l: pos() "@alignOf" _ "(" _ ty: expr() _ ")" r: pos() {
+                BuiltinCall {
+                    builtin: Builtin::AlignOf(ty),
+                    span: parser.span(l, r)
+                }
+            } / l: pos() "@offsetOf" _ "(" _ ty: expr() _ "," _ field: string() _ ")" r: pos() {
+                BuiltinCall {
+                    builtin: Builtin::OffsetOf(ty, field),
+                    span: parser.span(l, r)
+                }
+            }
+
         rule if_exp() -> IfExp
              = l: pos() "if" __ c: expr() _ t: block() _
                 e: ("else" _ b: block() {b})? r: pos() {
@@ -365,26 +613,49 @@ peg::parser! {
                 }
             }
 
+        rule match_exp() -> Match
+             = l: pos() "match" __ e: expr() _ "{" _ arms: (match_arm() ** (","? _)) _ ","? _ "}" r: pos() {
+                Match {
+                    exp: e,
+                    arms,
+                    span: parser.span(l, r)
+                }
+            }
+
+        rule match_arm() -> MatchArm
+             = l: pos() p: pattern() _ "=" ">" _ body: expr() r: pos() {
+                MatchArm {
+                    pattern: p,
+                    body,
+                    span: parser.span(l, r)
+                }
+            }
+
+        rule pattern() -> Pattern
+             = l: pos() "_" r: pos() {
+                Pattern::Wildcard(parser.span(l, r))
+            } / l: pos() "-" _ n: number() {
+                let Exp::Number(mut n) = n else { unreachable!() };
+                n.span = parser.span(l, n.span.end());
+                Pattern::Number(n, true)
+            } / n: number() {
+                let Exp::Number(n) = n else { unreachable!() };
+                Pattern::Number(n, false)
+            } / l: pos() p: path() _ binding: ("(" _ b: identifier() _ ")" {b})? r: pos() {
+                Pattern::Variant(p, binding, parser.span(l, r))
+            }
+
         rule module() -> (Span, StructType)
              = s: pos() "mod" __ path: string() e: pos() {
                  let span = parser.span(s, e);
-                 let Some(path) = parser.find_module(&path) else {
-                     LintDumper::new(parser.file_table()).lints([(format!("Module '{}' not found.", path), span)].iter()).dump();
-                     exit(-1);
-                 };
-                 let Ok(file) = parser.file_table().register_file(path.clone()) else {
-                     LintDumper::new(parser.file_table()).lints([(format!("Failed to read '{}'.", path), span)].iter()).dump();
-                     exit(-1);
-                 };
-                 (
-                     parser.span(s, e),
-                     parser.parse_module(pself, file).unwrap()
-                 )
+                 (span, modules.load(parser, &path, span))
              }
 
         rule string_wrapper() -> Exp
              = s: pos() string: string() e: pos() {
-            Exp::Str(string, parser.span(s, e))
+            Exp::Str(string.into_bytes(), parser.span(s, e))
+        } / s: pos() bytes: byte_string() e: pos() {
+            Exp::ByteStr(bytes, parser.span(s, e))
         }
 
         rule var() -> Var
@@ -395,48 +666,51 @@ peg::parser! {
         rule number() -> Exp
             = #{ |input, pos| match input.get(pos) {
                 Some((Token::Number(num_str), span)) => {
-                    let num_width_getter = |p| {
-                        let (num, width) = num_str.rsplit_once(p).unwrap();
-                        let Ok(num) = num.parse() else {
-                            return Err(());
-                        };
-                        let ty = if width == "size" {
-                            if p == "u" {
-                                TypeEnum::Usize
-                            } else {
-                                TypeEnum::Isize
-                            }
-                        } else {
-                            let Ok(width) = width.parse() else {
-                                return Err(());
-                            };
-                            if p == "u" {
-                                TypeEnum::Unsigned(width)
-                            } else {
-                                TypeEnum::Signed(width)
-                            }
-                        };
-                        Ok((num, ty))
+                    let Some((num, ty)) = parse_integer(num_str) else {
+                        return RuleResult::Failed;
                     };
+                    RuleResult::Matched(pos+1, Exp::Number(Number { num, ty, span: parser.span(span.start, span.end) }))
+                }
+                Some((Token::Char(byte), span)) => {
+                    let ty = Some(TypeEnum::Unsigned(8));
+                    RuleResult::Matched(pos+1, Exp::Number(Number { num: *byte as u128, ty, span: parser.span(span.start, span.end) }))
+                }
+                _ => RuleResult::Failed
+            }}
 
-                    let (num, ty) = if num_str.contains("u") {
-                        let Ok((num, ty)) = num_width_getter("u") else {
-                            return RuleResult::Failed;
-                        };
-                        (num, Some(ty))
-                    } else if num_str.contains("i") {
-                        let Ok((num, ty)) = num_width_getter("i") else {
-                            return RuleResult::Failed;
-                        };
-                        (num, Some(ty))
-                    } else {
-                        let Ok(num) = num_str.parse() else {
-                            return RuleResult::Failed;
-                        };
-                        (num, None)
+        // The `0` of `t.0`. `t.0.1` is lexed with `0.1` as one float, which
+        // stands for both indices.
+        rule tuple_index() -> Vec<String>
+            = #{ |input, pos| match input.get(pos) {
+                Some((Token::Number(index), _)) if index.bytes().all(|b| b.is_ascii_digit()) => {
+                    RuleResult::Matched(pos+1, vec![index.clone()])
+                }
+                Some((Token::Float(indices), _)) => match indices.split_once('.') {
+                    Some((first, second))
+                        if [first, second].iter().all(|index| index.bytes().all(|b| b.is_ascii_digit())) =>
+                    {
+                        RuleResult::Matched(pos+1, vec![first.into(), second.into()])
+                    }
+                    _ => RuleResult::Failed,
+                },
+                _ => RuleResult::Failed
+            }}
+
+        rule float() -> Float
+            = #{ |input, pos| match input.get(pos) {
+                Some((Token::Float(num_str), span)) => {
+                    let (num, ty) = match num_str.strip_suffix("f32") {
+                        Some(num) => (num, Some(TypeEnum::Float(32))),
+                        None => match num_str.strip_suffix("f64") {
+                            Some(num) => (num, Some(TypeEnum::Float(64))),
+                            None => (num_str.as_str(), None),
+                        },
+                    };
+                    let Ok(num) = num.replace('_', "").parse() else {
+                        return RuleResult::Failed;
                     };
 
-                    RuleResult::Matched(pos+1, Exp::Number(Number { num, ty, span: parser.span(span.start, span.end) }))
+                    RuleResult::Matched(pos+1, Float { num, ty, span: parser.span(span.start, span.end) })
                 }
                 _ => RuleResult::Failed
             }}
@@ -466,6 +740,14 @@ peg::parser! {
                     if ident.len() > 1 {
                         let (prefix, width) = ident.split_at(1);
 
+                        if prefix == "f" {
+                            return match width {
+                                "32" => RuleResult::Matched(pos+1, TypeEnum::Float(32)),
+                                "64" => RuleResult::Matched(pos+1, TypeEnum::Float(64)),
+                                _ => RuleResult::Failed,
+                            };
+                        }
+
                         let signed = if prefix == "i" {
                             true
                         } else if prefix == "u" {
@@ -499,20 +781,67 @@ peg::parser! {
                 _ => RuleResult::Failed
             }} / "(" _ ")" {
                 TypeEnum::Unit
-            } / "[" _ inner: expr() _ ";" _ len: digit() _ "]" {
-                TypeEnum::Array(inner, len as u32)
-            } / "struct" _ "{" _ i: struct_inner() _ "}" {
-                TypeEnum::Structure(i)
+            } / "bool" {
+                TypeEnum::Bool
+            } / "type" {
+                TypeEnum::Type
+            } / "[" _ "]" _ inner: type_expr() {
+                TypeEnum::Slice(inner)
+            } / "Self" {
+                TypeEnum::SelfType
+            } / "dyn" __ l: pos() path: path() r: pos() {
+                TypeEnum::Dyn(parser.insert_exp(Exp::Var(Var { path, span: parser.span(l, r) })))
+            } / layout: struct_layout() "struct" _ implements: struct_implements() "{" _ i: struct_inner() _ "}" {
+                TypeEnum::Structure(StructType { layout, implements, ..i })
+            } / l: pos() "interface" _ "{" _ methods: (interface_method() ** _) _ "}" r: pos() {
+                TypeEnum::Interface(InterfaceType { methods, name: None, span: parser.span(l, r) })
+            } / l: pos() "enum" _ "{" _ variants: (variant() ** ("," _)) _ ","? _ "}" r: pos() {
+                TypeEnum::Enum(EnumType { variants, span: parser.span(l, r) })
             }
 
+        rule variant() -> Variant
+             = l: pos() name: identifier() _ payload: (":" _ t: expr() {t})? r: pos() {
+                 Variant { name, payload, span: parser.span(l, r) }
+             }
+
+        rule struct_layout() -> StructLayout
+             = packed: ("packed" __)? align: ("align" _ "(" _ n: number() _ ")" _ {n})? {
+                 // Too large to be a power of two, which the analyzer checks.
+                 let align = align.map(|n| match n {
+                     Exp::Number(n) => u64::try_from(n.num).unwrap_or(u64::MAX),
+                     _ => unreachable!(),
+                 });
+                 StructLayout { packed: packed.is_some(), align }
+             }
+
+        rule struct_implements() -> Vec<ExpId>
+             = implements: ("impl" _ "(" _ i: (expr() ** ("," _)) _ ","? _ ")" _ {i})? {
+                 implements.unwrap_or_default()
+             }
+
+        rule interface_method() -> (String, ExpId)
+             = "const" __ name: identifier() _ "=" _ l: pos() "proto" __ "fn" _ "(" _ params: (param() ** ("," _)) _ ","? _ ")" return_type: (__ "-" ">" _ t: expr() {t})? r: pos() _ ";" {
+                 let proto = ProtoDef { abi: Abi::Cara, params, return_type, span: parser.span(l, r) };
+                 (name, parser.insert_exp(Exp::ProtoDef(proto)))
+             }
+
         rule struct_inner() -> StructType
-             = l: pos() _
-             fields: (name: identifier() _ ":" _ ty: expr() { (name, ty) }) ** ("," _) ","? _
+             = l: pos() _ doc: inner_doc()
+             fields: (doc: doc() name: identifier() _ ":" _ ty: expr() { (doc, name, ty) }) ** ("," _) ","? _
              items: (global_item() ** _) _
              r: pos() {
+                 let field_docs = fields
+                     .iter()
+                     .filter_map(|(doc, name, _)| Some((name.clone(), doc.clone()?)))
+                     .collect();
                  StructType {
-                     fields: fields.into_iter().collect(),
+                     fields: fields.into_iter().map(|(_, name, ty)| (name, ty)).collect(),
+                     field_docs,
                      members: items,
+                     namespace: None,
+                     implements: Vec::new(),
+                     layout: StructLayout::default(),
+                     doc,
                      span: parser.span(l, r),
                  }
              }
@@ -525,19 +854,76 @@ peg::parser! {
             }
         }
 
+        // The expressions that can stand for a type. Unlike `expr()`, it stops
+        // before a `=`, so it can be followed by a value.
+        rule type_expr() -> ExpId =
+            p: proto_def() { parser.insert_exp(Exp::ProtoDef(p)) } /
+            precedence!{
+                s: pos() "*" _ r: (@) {
+                    let span = parser.span(s, r.span().end());
+                    parser.insert_exp(Exp::Unary(UnaryOp::Ptr, r, span))
+                }
+                --
+                l: @ "." n: identifier() r: pos() {
+                    let span = parser.span(l.span().start(), r);
+                    parser.insert_exp(Exp::FieldAccess(FieldAccess {
+                        lhs: l,
+                        field: n,
+                        span
+                    }))
+                }
+                --
+                l: (@) _ "(" _ args: (expr() ** ("," _)) ","? _ ")" r: pos() {
+                    let span = parser.span(l.span().start(), r);
+                    parser.insert_exp(Exp::Call(Call {
+                        func: l,
+                        args,
+                        span
+                    }))
+                }
+                --
+                t: type_() { parser.insert_exp(Exp::Type(t)) }
+                l: pos() "(" _ first: type_expr() _ "," _ rest: (type_expr() ** ("," _)) _ ","? _ ")" r: pos() {
+                    let elements = std::iter::once(first).chain(rest).collect();
+                    parser.insert_exp(Exp::Tuple(Tuple { elements, span: parser.span(l, r) }))
+                }
+                "(" _ e: expr() _ ")" {
+                    e
+                }
+                v: var() { parser.insert_exp(Exp::Var(v)) }
+                a: array() { parser.insert_exp(Exp::Array(a)) }
+                b: builtin() { parser.insert_exp(Exp::Builtin(b)) }
+            }
+
         rule identifier() -> String
           = #{ |input, pos| match input.get(pos) {
               Some((Token::Ident(ident), span)) => {
                   RuleResult::Matched(pos+1, ident.into())
               }
               _ => RuleResult::Failed
-          }}
+          }} / expected!("an identifier")
 
         rule keyword()
           = ("const" / "fn" / "extern" / "mut" / "proto" / "let" / "struct" / "mod"
             / "if" / "while" / "loop" / "for" / "in" / "else" / "break" / "continue"
+            / "enum" / "match" / "comptime" / "type" / "bool" / "true" / "false"
+            / "interface" / "dyn" / "Self"
             / "i" n: digit() / "u" n: digit()) __
 
+        rule doc() -> Option<String>
+          = p: position!() { tokens.doc(p) }
+
+        rule inner_doc() -> Option<String>
+          = p: position!() { tokens.inner_doc(p) }
+
+        rule label() -> String
+          = #{ |input, pos| match input.get(pos) {
+              Some((Token::Label(label), _)) => {
+                  RuleResult::Matched(pos+1, label.into())
+              }
+              _ => RuleResult::Failed
+          }}
+
         rule string() -> String
         = #{ |input, pos| match input.get(pos) {
             Some((Token::String(string), span)) => {
@@ -546,7 +932,94 @@ peg::parser! {
             _ => RuleResult::Failed
         }}
 
+        rule byte_string() -> Vec<u8>
+        = #{ |input, pos| match input.get(pos) {
+            Some((Token::ByteString(bytes), _)) => RuleResult::Matched(pos+1, bytes.clone()),
+            _ => RuleResult::Failed
+        }}
+
         rule _() =
         rule __() =
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ast::{FileTable, ParseContext};
+
+    use crate::{CaraParser, Error, ParseErrors};
+
+    /// Parses `main.cara` from a directory holding `files`, and returns the
+    /// errors with the file and text each one points at.
+    fn module_errors(files: &[(&str, &str)]) -> Vec<(Error, String, String)> {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, source) in files {
+            std::fs::write(dir.path().join(name), source).unwrap();
+        }
+        let file_table = FileTable::new();
+        let main = dir.path().join("main.cara").to_string_lossy().into_owned();
+        let file_id = file_table.register_file(main).unwrap();
+        let Err(ParseErrors::Errors(errors)) =
+            ParseContext::new(&file_table).parse(&CaraParser, file_id)
+        else {
+            panic!("expected the modules to fail to load");
+        };
+        errors
+            .into_iter()
+            .map(|(error, span)| {
+                let path = file_table.get_path(span.file()).unwrap();
+                let source = std::fs::read_to_string(&path).unwrap();
+                let name = std::path::Path::new(&path).file_name().unwrap();
+                let text = source[span.start()..span.end()].trim_end().to_string();
+                (error, name.to_string_lossy().into_owned(), text)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn missing_module_is_reported_at_its_mod() {
+        let errors = module_errors(&[("main.cara", "const a = mod \"missing.cara\";")]);
+        let [(Error::ModuleNotFound(path), file, text)] = &errors[..] else {
+            panic!("expected one error, found {errors:?}");
+        };
+        assert_eq!(path, "missing.cara");
+        assert_eq!(
+            (file.as_str(), text.as_str()),
+            ("main.cara", "mod \"missing.cara\"")
+        );
+    }
+
+    #[test]
+    fn circular_import_is_reported() {
+        let errors = module_errors(&[
+            ("main.cara", "const a = mod \"a.cara\";"),
+            ("a.cara", "const b = mod \"b.cara\";"),
+            ("b.cara", "const a = mod \"a.cara\";"),
+        ]);
+        let [(Error::CircularImport(chain), file, text)] = &errors[..] else {
+            panic!("expected one error, found {errors:?}");
+        };
+        let names = chain
+            .iter()
+            .map(|path| path.rsplit('/').next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["main.cara", "a.cara", "b.cara", "a.cara"]);
+        assert_eq!((file.as_str(), text.as_str()), ("b.cara", "mod \"a.cara\""));
+    }
+
+    #[test]
+    fn module_syntax_errors_point_into_the_module() {
+        let errors = module_errors(&[
+            ("main.cara", "const a = mod \"a.cara\";\nconst b = 1 +;"),
+            ("a.cara", "const c = 2 +;"),
+        ]);
+        let places = errors
+            .iter()
+            .map(|(error, file, text)| {
+                assert!(matches!(error, Error::Unexpected { .. }), "{error:?}");
+                (file.as_str(), text.as_str())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(places, [("main.cara", ";"), ("a.cara", ";")]);
+    }
+}
@@ -0,0 +1,453 @@
+//! Recovery from syntax errors. After each error the tokens around it are
+//! fixed up, by inserting a missing `;` or bracket or by dropping the broken
+//! statement, and the file is parsed again to find the errors after it.
+//!
+//! The tokens are lexed once, but every error costs two parses of the whole
+//! file, as peg parses it again to report the error, so a file with errors
+//! is parsed up to `2 * MAX_SYNTAX_ERRORS + 1` times.
+
+use ast::ParseContext;
+use logos::Span;
+use peg::error::{ExpectedSet, ParseError};
+
+use crate::{
+    Error,
+    lexer::{Sp, Token, TokenStream},
+};
+
+/// Past this many, most errors come from recovering badly from earlier ones.
+pub const MAX_SYNTAX_ERRORS: usize = 20;
+
+#[derive(Default)]
+pub struct Recovery {
+    pub errors: Vec<(Error, ast::Span)>,
+    inserted: Option<usize>,
+}
+
+impl Recovery {
+    /// Records `error`, then edits `tokens` so that parsing them again gets
+    /// past it. Returns `false` when parsing again is no use.
+    pub fn recover(
+        &mut self,
+        ctx: &ParseContext<'_>,
+        tokens: &mut TokenStream,
+        error: &ParseError<Sp>,
+    ) -> bool {
+        let index = error.location.index();
+        let expected = &error.expected;
+        let span = |span: Span| ctx.span(span.start, span.end);
+
+        let start = statement_start(tokens, index);
+        let open = open_brackets(tokens, index);
+        let unclosed = |index: usize| {
+            let bracket = tokens[index].1.clone();
+            (tokens.input()[bracket.clone()].to_string(), span(bracket))
+        };
+        if index >= tokens.len() {
+            let (error, at) = if expects(expected, ";") && open.is_empty() && index > 0 {
+                (missing_semi(tokens, start), tokens[index - 1].1.clone())
+            } else {
+                let unexpected = Error::Unexpected {
+                    expected: readable(expected),
+                    found: "the end of the file".to_string(),
+                    unclosed: open.last().map(|&last| unclosed(last)),
+                    help: None,
+                };
+                let end = tokens.last().map_or(0..0, |(_, span)| span.end..span.end);
+                (unexpected, end)
+            };
+            self.errors.push((error, span(at)));
+            return false;
+        }
+
+        if expects(expected, ";") && index > 0 && self.inserted != Some(index) {
+            let previous = tokens[index - 1].1.clone();
+            let (token, next) = &tokens[index];
+            let line_break = tokens.input()[previous.end..next.start].contains('\n');
+            if line_break
+                || is_keyword(tokens, index, &["const", "let"])
+                || *token == Token::RightBrace
+            {
+                self.errors
+                    .push((missing_semi(tokens, start), span(previous.clone())));
+                self.inserted = Some(index);
+                tokens
+                    .tokens_mut()
+                    .insert(index, (Token::Semi, previous.end..previous.end));
+                return true;
+            }
+        }
+
+        // Brackets left open by a `;`, which ends the statement they are
+        // in, or by a bracket closing one opened before them get closed
+        // right there. A closing bracket with nothing to close is dropped.
+        let (token, at) = &tokens[index];
+        let mut stray = false;
+        let first_unclosed = if *token == Token::Semi {
+            open.iter()
+                .rposition(|&opening| tokens[opening].0 == Token::LeftBrace)
+                .map_or(0, |block| block + 1)
+        } else if is_closing(token)
+            && open
+                .last()
+                .is_none_or(|&last| !pairs(&tokens[last].0, token))
+        {
+            match open
+                .iter()
+                .rposition(|&opening| pairs(&tokens[opening].0, token))
+            {
+                Some(own) => own + 1,
+                None => {
+                    stray = true;
+                    open.len()
+                }
+            }
+        } else {
+            open.len()
+        };
+
+        let error = Error::Unexpected {
+            expected: readable(expected),
+            found: describe(tokens, index),
+            unclosed: open[first_unclosed..].last().map(|&last| unclosed(last)),
+            help: help(tokens, index, expected),
+        };
+        let at = span(at.clone());
+        // The same error again means the edits below got nowhere.
+        if self
+            .errors
+            .iter()
+            .any(|(seen, span)| *span == at && seen.to_string() == error.to_string())
+        {
+            return false;
+        }
+        self.errors.push((error, at));
+        self.inserted = None;
+
+        if stray {
+            tokens.tokens_mut().remove(index);
+            return true;
+        }
+        if first_unclosed < open.len() {
+            let at = tokens[index].1.start..tokens[index].1.start;
+            let closers = open[first_unclosed..]
+                .iter()
+                .rev()
+                .map(|&opening| (closer(&tokens[opening].0), at.clone()))
+                .collect::<Vec<_>>();
+            tokens.tokens_mut().splice(index..index, closers);
+            return true;
+        }
+
+        let item = start < index && is_keyword(tokens, start, &["const", "let"]);
+        let end = statement_end(tokens, index, item);
+        let end = if end > start { end } else { index + 1 };
+        tokens.tokens_mut().drain(start..end);
+        true
+    }
+}
+
+fn missing_semi(tokens: &TokenStream, start: usize) -> Error {
+    Error::MissingSemi {
+        after_const: is_keyword(tokens, start, &["const"]),
+    }
+}
+
+fn is_keyword(tokens: &TokenStream, index: usize, keywords: &[&str]) -> bool {
+    matches!(tokens.get(index), Some((Token::Ident(ident), _)) if keywords.contains(&ident.as_str()))
+}
+
+fn is_opening(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::LeftParen | Token::LeftBrace | Token::LeftBracket
+    )
+}
+
+fn is_closing(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::RightParen | Token::RightBrace | Token::RightBracket
+    )
+}
+
+fn closer(opening: &Token) -> Token {
+    match opening {
+        Token::LeftParen => Token::RightParen,
+        Token::LeftBracket => Token::RightBracket,
+        _ => Token::RightBrace,
+    }
+}
+
+fn pairs(opening: &Token, closing: &Token) -> bool {
+    matches!(
+        (opening, closing),
+        (Token::LeftParen, Token::RightParen)
+            | (Token::LeftBrace, Token::RightBrace)
+            | (Token::LeftBracket, Token::RightBracket)
+    )
+}
+
+/// The statement holding the token at `index` follows a `;` or an open
+/// bracket, or starts with `const` or `let`.
+fn statement_start(tokens: &TokenStream, index: usize) -> usize {
+    let mut depth = 0usize;
+    for start in (0..index).rev() {
+        let token = &tokens[start].0;
+        if is_closing(token) {
+            depth += 1;
+        } else if is_opening(token) {
+            if depth == 0 {
+                return start + 1;
+            }
+            depth -= 1;
+        } else if depth == 0 {
+            if *token == Token::Semi {
+                return start + 1;
+            }
+            if is_keyword(tokens, start, &["const", "let"]) {
+                return start;
+            }
+        }
+    }
+    0
+}
+
+/// A `const` or `let` at `index` itself ends the statement only if `item`
+/// says that it began with one.
+fn statement_end(tokens: &TokenStream, index: usize, item: bool) -> usize {
+    let mut depth = 0usize;
+    for end in index..tokens.len() {
+        let token = &tokens[end].0;
+        if is_opening(token) {
+            depth += 1;
+        } else if is_closing(token) {
+            if depth == 0 {
+                return end;
+            }
+            depth -= 1;
+        } else if depth == 0 {
+            if *token == Token::Semi {
+                return end + 1;
+            }
+            if (end > index || item) && is_keyword(tokens, end, &["const", "let"]) {
+                return end;
+            }
+        }
+    }
+    tokens.len()
+}
+
+fn open_brackets(tokens: &TokenStream, index: usize) -> Vec<usize> {
+    let mut open = Vec::new();
+    for (i, (token, _)) in tokens[..index].iter().enumerate() {
+        if is_opening(token) {
+            open.push(i);
+        } else if is_closing(token) {
+            open.pop();
+        }
+    }
+    open
+}
+
+fn expects(expected: &ExpectedSet, literal: &str) -> bool {
+    expected
+        .tokens()
+        .any(|token| token.trim_matches('"') == literal)
+}
+
+fn describe(tokens: &TokenStream, index: usize) -> String {
+    let (token, span) = &tokens[index];
+    match token {
+        Token::String(_) | Token::ByteString(_) => "a string".to_string(),
+        Token::Char(_) => "a character".to_string(),
+        Token::Number(_) | Token::Float(_) => {
+            format!("the number `{}`", &tokens.input()[span.clone()])
+        }
+        _ if span.is_empty() => format!("`{}`", token.inserted_text()),
+        _ => format!("`{}`", &tokens.input()[span.clone()]),
+    }
+}
+
+/// Named in error messages even among the many tokens that can start or
+/// continue an expression.
+const SEPARATORS: [&str; 5] = [";", ",", ")", "]", "}"];
+
+fn readable(expected: &ExpectedSet) -> String {
+    let mut literals = Vec::new();
+    let mut names = Vec::new();
+    for token in expected.tokens() {
+        match token.strip_prefix('"') {
+            Some(literal) => literals.push(literal.trim_end_matches('"')),
+            None if token == "EOF" => names.push("the end of the file"),
+            None => names.push(token),
+        }
+    }
+    let expression = literals.contains(&"true");
+    let operator = literals.contains(&"%");
+    if expression || operator {
+        literals.retain(|literal| SEPARATORS.contains(literal));
+        names.clear();
+        if operator {
+            names.push("an operator");
+        }
+        if expression {
+            names.push("an expression");
+        }
+    }
+    literals.sort();
+    literals.dedup();
+    let names = literals
+        .into_iter()
+        .map(|literal| format!("`{literal}`"))
+        .chain(names.into_iter().map(str::to_string))
+        .collect::<Vec<_>>();
+    match names.split_last() {
+        None => "something else".to_string(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+    }
+}
+
+fn help(tokens: &TokenStream, index: usize, expected: &ExpectedSet) -> Option<&'static str> {
+    if !expects(expected, "const") {
+        return None;
+    }
+    if is_keyword(tokens, index, &["let"]) {
+        Some("`let` declares variables in functions only. Use `const` for definitions.")
+    } else if is_keyword(tokens, index, &["fn"]) {
+        Some("Functions are defined as constants: `const name = fn() { ... };`.")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ops::Range, sync::Arc};
+
+    use ast::{FileTable, ParseContext, Parser};
+
+    use super::MAX_SYNTAX_ERRORS;
+    use crate::{CaraParser, Error, ParseErrors};
+
+    fn parse_errors(input: &str) -> Vec<(Error, Range<usize>)> {
+        let file_table = FileTable::new();
+        let ctx = ParseContext::new(&file_table);
+        match CaraParser.parse_content(&ctx, Arc::new(input.to_string())) {
+            Err(ParseErrors::Errors(errors)) => errors
+                .into_iter()
+                .map(|(error, span)| (error, span.start()..span.end()))
+                .collect(),
+            _ => panic!("{input:?} parsed without errors"),
+        }
+    }
+
+    /// Where each occurrence of `text` in `input` is.
+    fn spans(input: &str, text: &str) -> Vec<Range<usize>> {
+        input
+            .match_indices(text)
+            .map(|(start, _)| start..start + text.len())
+            .collect()
+    }
+
+    /// What each error found instead of what it expected, and where.
+    fn found(errors: Vec<(Error, Range<usize>)>) -> Vec<(String, Range<usize>)> {
+        errors
+            .into_iter()
+            .map(|(error, span)| match error {
+                Error::Unexpected { found, .. } => (found, span),
+                _ => panic!("expected an unexpected token, found {error:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn missing_semi_is_inserted() {
+        let input = "const a = 1\nconst b = 2\nconst c = 3;";
+        let errors = parse_errors(input);
+        assert_eq!(errors.len(), 2);
+        for ((error, span), digit) in errors.into_iter().zip(["1", "2"]) {
+            assert!(matches!(error, Error::MissingSemi { after_const: true }));
+            assert_eq!(span, spans(input, digit)[0]);
+        }
+
+        let input = "const f = fn() { let a = 1\n let b = 2; };";
+        let [(error, span)] = &parse_errors(input)[..] else {
+            panic!("expected one error");
+        };
+        assert!(matches!(error, Error::MissingSemi { after_const: false }));
+        assert_eq!(*span, spans(input, "1")[0]);
+    }
+
+    #[test]
+    fn unclosed_bracket_is_closed() {
+        let input = "const f = fn() { let a = (1 + 2; let b = g(3; };";
+        let errors = parse_errors(input);
+        let unclosed = errors
+            .iter()
+            .map(|(error, _)| match error {
+                Error::Unexpected {
+                    unclosed: Some((bracket, span)),
+                    ..
+                } => (bracket.as_str(), span.start()..span.end()),
+                _ => panic!("expected an unclosed bracket in {error:?}"),
+            })
+            .collect::<Vec<_>>();
+        let opening = spans(input, "(");
+        assert_eq!(
+            unclosed,
+            [("(", opening[1].clone()), ("(", opening[2].clone())]
+        );
+        let semis = spans(input, ";");
+        let expected = [
+            ("`;`".to_string(), semis[0].clone()),
+            ("`;`".to_string(), semis[1].clone()),
+        ];
+        assert_eq!(found(errors), expected);
+    }
+
+    #[test]
+    fn stray_closing_bracket_is_dropped() {
+        let input = "const a = 1);\nconst b = fn() { (2)) };";
+        let closing = spans(input, ")");
+        let expected = [
+            ("`)`".to_string(), closing[0].clone()),
+            ("`)`".to_string(), closing[3].clone()),
+        ];
+        assert_eq!(found(parse_errors(input)), expected);
+    }
+
+    #[test]
+    fn broken_statement_is_dropped() {
+        let input = "const f = fn() { let a = 1 +; let b = 2 2; let c = 3 +; };";
+        let semis = spans(input, ";");
+        let expected = [
+            ("`;`".to_string(), semis[0].clone()),
+            ("the number `2`".to_string(), spans(input, "2")[1].clone()),
+            ("`;`".to_string(), semis[2].clone()),
+        ];
+        assert_eq!(found(parse_errors(input)), expected);
+    }
+
+    #[test]
+    fn errors_are_capped() {
+        let input = "const a = 1 +;\n".repeat(MAX_SYNTAX_ERRORS * 2);
+        assert_eq!(parse_errors(&input).len(), MAX_SYNTAX_ERRORS);
+    }
+
+    #[test]
+    fn recovery_terminates() {
+        for input in [
+            ")))(((}}}{{{;;;",
+            "const",
+            "const a = ",
+            "const f = fn() { let a = (((; };",
+            "} const a = 1; {",
+            "const f = fn() { ] ) } ; let ; let",
+        ] {
+            let errors = parse_errors(input);
+            assert!((1..=MAX_SYNTAX_ERRORS).contains(&errors.len()), "{input:?}");
+        }
+    }
+}
@@ -1,11 +1,12 @@
 use std::{collections::HashMap, hash::Hash, sync::Arc};
 
-use ast::{AstContext, ConstDef, GlobalItem};
+use ast::{AstContext, ConstDef, GlobalItem, SourceMap};
 use bon::bon;
 pub use defs::*;
 pub use provider::*;
 use rayon::{ThreadPool, ThreadPoolBuilder};
 use targets::spec::Target;
+use type_infer::LiteralTypes;
 
 mod defs;
 mod provider;
@@ -14,14 +15,21 @@ pub struct QueryContext {
     crate_name: String,
     target: Target,
     ast_ctx: Arc<AstContext>,
+    source_map: SourceMap,
     consts: HashMap<DefId, Arc<ConstDef>>,
     thread_pool: ThreadPool,
+    literal_types: LiteralTypes,
 }
 
 #[bon]
 impl QueryContext {
     #[builder]
-    pub fn new(crate_name: String, target: Target, ast: Arc<AstContext>) -> Arc<Self> {
+    pub fn new(
+        crate_name: String,
+        target: Target,
+        ast: Arc<AstContext>,
+        #[builder(default)] source_map: SourceMap,
+    ) -> Arc<Self> {
         let mut consts = HashMap::new();
         for GlobalItem::ConstDef(const_def) in ast.root.members.iter() {
             let id = DefId(consts.len());
@@ -31,8 +39,10 @@ impl QueryContext {
             crate_name,
             target,
             ast_ctx: ast,
+            source_map,
             consts,
             thread_pool: ThreadPoolBuilder::new().build().unwrap(),
+            literal_types: LiteralTypes::default(),
         })
     }
 }
@@ -54,6 +64,14 @@ impl QueryContext {
     pub fn target(&self) -> &Target {
         &self.target
     }
+
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
+    }
+
+    pub fn literal_types(&self) -> &LiteralTypes {
+        &self.literal_types
+    }
 }
 
 impl QueryContext {
@@ -1,7 +1,7 @@
 use ast::{
-    Array, Assign, BinaryOp, Call, Deref, Exp, ExpId, FieldAccess, For, FunctionDef, GetAddr,
-    IfExp, Index, Loop, Param, Path, ProtoDef, Return, Span, Structure, TypeCast, UnaryOp, Var,
-    While,
+    Array, Assign, BinaryOp, Break, Builtin, BuiltinCall, Call, ConstExp, Deref, Exp, ExpId,
+    FieldAccess, For, FunctionDef, GetAddr, IfExp, Index, Loop, Match, MatchArm, Param, Path,
+    Pattern, ProtoDef, Return, Slice, Span, Structure, Tuple, TypeCast, UnaryOp, Var, While,
 };
 
 use crate::SimplifierContext;
@@ -21,15 +21,20 @@ impl SimplifierContext {
             Exp::Call(call) => self.simp_call(call),
             Exp::Deref(deref) => self.simp_deref(deref),
             Exp::FieldAccess(field_access) => self.simp_field_access(field_access),
+            Exp::Builtin(builtin) => self.simp_builtin(builtin),
             Exp::For(for_loop) => self.simp_for(for_loop),
             Exp::IfExp(if_exp) => self.simp_if(if_exp),
+            Exp::Match(match_) => self.simp_match(match_),
             Exp::Function(func) => self.simp_function(func),
             Exp::GetAddr(get_addr) => self.simp_get_addr(get_addr),
             Exp::Index(index) => self.simp_index(index),
+            Exp::Slice(slice) => self.simp_slice(slice),
             Exp::Loop(loop_exp) => self.simp_loop(loop_exp),
             Exp::ProtoDef(proto_def) => self.simp_proto(proto_def),
             Exp::Return(ret) => self.simp_return(ret),
+            Exp::Break(break_exp) => self.simp_break(break_exp),
             Exp::Structure(structure) => self.simp_structure(structure),
+            Exp::Tuple(tuple) => self.simp_tuple(tuple),
             Exp::TypeCast(type_cast) => self.simp_type_cast(type_cast),
             Exp::Var(var) => self.simp_var(var),
             Exp::While(while_exp) => self.simp_while(while_exp),
@@ -49,7 +54,11 @@ impl SimplifierContext {
                     .collect(),
                 span,
             )),
-            _ => unreachable!(),
+            Array::Template(value, ConstExp { exp: len }, span) => {
+                let value = self.simp_exp(value);
+                let len = self.simp_exp(len);
+                Exp::Array(Array::Template(value, ConstExp { exp: len }, span))
+            }
         }
     }
 
@@ -69,6 +78,7 @@ impl SimplifierContext {
         let rhs = self.simp_exp(assign.rhs);
         Exp::Assign(Assign {
             lhs,
+            op: assign.op,
             rhs,
             span: assign.span,
         })
@@ -107,6 +117,7 @@ impl SimplifierContext {
 
     fn simp_for(&mut self, for_exp: For) -> Exp {
         let For {
+            label,
             var,
             start,
             end,
@@ -122,6 +133,7 @@ impl SimplifierContext {
         let body = self.simp_block(body);
 
         Exp::For(For {
+            label,
             var,
             start,
             end,
@@ -133,6 +145,7 @@ impl SimplifierContext {
 
     fn simp_while(&mut self, while_exp: While) -> Exp {
         let While {
+            label,
             condition,
             body,
             span,
@@ -142,6 +155,7 @@ impl SimplifierContext {
         let body = self.simp_block(body);
 
         Exp::While(While {
+            label,
             condition,
             body,
             span,
@@ -174,6 +188,43 @@ impl SimplifierContext {
         })
     }
 
+    fn simp_match(&mut self, match_: Match) -> Exp {
+        let Match { exp, arms, span } = match_;
+
+        let exp = self.simp_exp(exp);
+        let arms = arms
+            .into_iter()
+            .map(
+                |MatchArm {
+                     pattern,
+                     body,
+                     span,
+                 }| {
+                    self.locals.push_scope();
+                    let pattern = match pattern {
+                        Pattern::Variant(path, binding, span) => {
+                            let path = self.simp_path(path);
+                            if let Some(binding) = &binding {
+                                self.locals.push(binding.clone());
+                            }
+                            Pattern::Variant(path, binding, span)
+                        }
+                        pattern => pattern,
+                    };
+                    let body = self.simp_exp(body);
+                    self.locals.pop_scope();
+                    MatchArm {
+                        pattern,
+                        body,
+                        span,
+                    }
+                },
+            )
+            .collect();
+
+        Exp::Match(Match { exp, arms, span })
+    }
+
     fn simp_function(&mut self, func: FunctionDef) -> Exp {
         let FunctionDef {
             abi,
@@ -182,16 +233,20 @@ impl SimplifierContext {
             block,
             span,
         } = func;
+        // Parameters are visible to the types of later parameters and the
+        // return type, which is what lets `comptime T: type` be used as `x: T`.
+        self.locals.push_scope();
         let params = params
             .into_iter()
             .map(|param| {
                 let param = self.simp_param(param);
-                self.locals.pre_push(param.name.clone());
+                self.locals.push(param.name.clone());
                 param
             })
             .collect();
         let return_type = return_type.map(|ty| self.simp_exp(ty));
         let block = self.simp_block(block);
+        self.locals.pop_scope();
         Exp::Function(FunctionDef {
             abi,
             params,
@@ -225,12 +280,14 @@ impl SimplifierContext {
         let Param {
             name,
             param_type,
+            comptime,
             span,
         } = param;
         let param_type = self.simp_exp(param_type);
         Param {
             name,
             param_type,
+            comptime,
             span,
         }
     }
@@ -248,10 +305,34 @@ impl SimplifierContext {
         Exp::Index(Index { exp, index, span })
     }
 
+    fn simp_slice(&mut self, slice: Slice) -> Exp {
+        let Slice {
+            exp,
+            start,
+            end,
+            span,
+        } = slice;
+        let exp = self.simp_exp(exp);
+        let start = start.map(|start| self.simp_exp(start));
+        let end = end.map(|end| self.simp_exp(end));
+        Exp::Slice(Slice {
+            exp,
+            start,
+            end,
+            span,
+        })
+    }
+
     fn simp_loop(&mut self, loop_exp: Loop) -> Exp {
-        let Loop { body, span } = loop_exp;
+        let Loop { label, body, span } = loop_exp;
         let body = self.simp_block(body);
-        Exp::Loop(Loop { body, span })
+        Exp::Loop(Loop { label, body, span })
+    }
+
+    fn simp_break(&mut self, break_exp: Break) -> Exp {
+        let Break { label, value, span } = break_exp;
+        let value = value.map(|value| self.simp_exp(value));
+        Exp::Break(Break { label, value, span })
     }
 
     fn simp_return(&mut self, return_exp: Return) -> Exp {
@@ -273,6 +354,25 @@ impl SimplifierContext {
         Exp::Structure(Structure { ty, fields, span })
     }
 
+    fn simp_tuple(&mut self, tuple: Tuple) -> Exp {
+        let Tuple { elements, span } = tuple;
+        let elements = elements
+            .into_iter()
+            .map(|element| self.simp_exp(element))
+            .collect();
+        Exp::Tuple(Tuple { elements, span })
+    }
+
+    fn simp_builtin(&mut self, builtin: BuiltinCall) -> Exp {
+        let BuiltinCall { builtin, span } = builtin;
+        let builtin = match builtin {
+            Builtin::SizeOf(ty) => Builtin::SizeOf(self.simp_exp(ty)),
+            Builtin::AlignOf(ty) => Builtin::AlignOf(self.simp_exp(ty)),
+            Builtin::OffsetOf(ty, field) => Builtin::OffsetOf(self.simp_exp(ty), field),
+        };
+        Exp::Builtin(BuiltinCall { builtin, span })
+    }
+
     fn simp_type_cast(&mut self, type_cast: TypeCast) -> Exp {
         let TypeCast { exp, ty, span } = type_cast;
         let exp = self.simp_exp(exp);
@@ -285,6 +385,13 @@ impl SimplifierContext {
             path,
             span: var_span,
         } = var;
+        Exp::Var(Var {
+            path: self.simp_path(path),
+            span: var_span,
+        })
+    }
+
+    fn simp_path(&mut self, path: Path) -> Path {
         let Path {
             mut path,
             span: path_span,
@@ -307,12 +414,9 @@ impl SimplifierContext {
             }
         };
         new_path.extend(path);
-        Exp::Var(Var {
-            path: Path {
-                path: new_path,
-                span: path_span,
-            },
-            span: var_span,
-        })
+        Path {
+            path: new_path,
+            span: path_span,
+        }
     }
 }
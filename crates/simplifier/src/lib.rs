@@ -1,8 +1,8 @@
 use std::{collections::HashMap, sync::Arc};
 
 use ast::{
-    AstContext, ConstDef, ConstExp, ConstInitialValue, Exp, ExpId, GlobalItem, StructType, Type,
-    TypeEnum,
+    AstContext, ConstDef, ConstExp, ConstInitialValue, EnumType, EnumVariant, Exp, ExpId,
+    GlobalItem, InterfaceType, Path, StructLayout, StructType, Type, TypeEnum, Var, Variant,
 };
 use symbol_table::SymbolTable;
 
@@ -25,8 +25,13 @@ pub fn simplify(crate_name: String, ast: AstContext) -> AstContext {
     AstContext::new(
         exps,
         StructType {
-            fields: HashMap::new(),
+            fields: Vec::new(),
+            field_docs: HashMap::new(),
             members: extra_items,
+            namespace: None,
+            implements: Vec::new(),
+            layout: StructLayout::default(),
+            doc: None,
             span,
         },
     )
@@ -84,16 +89,27 @@ impl SimplifierContext {
         let ConstDef {
             name: raw_name,
             initial_value,
+            doc,
             span,
         } = const_def.as_ref();
         let name = self.globals.prefixed_name(raw_name);
         self.globals.set_name_cache(raw_name.clone());
+        let ConstInitialValue::Exp(ConstExp { exp }) = initial_value;
+        if let Some(Exp::Type(Type {
+            kind: TypeEnum::Enum(enum_ty),
+            ..
+        })) = self.origin_exps.get(exp)
+        {
+            let enum_ty = enum_ty.clone();
+            self.simp_enum_variants(&enum_ty);
+        }
         match initial_value {
             ConstInitialValue::Exp(exp) => {
                 let exp = self.simp_exp(exp.exp);
                 GlobalItem::ConstDef(Arc::new(ConstDef {
                     name,
                     initial_value: ConstInitialValue::Exp(ConstExp { exp }),
+                    doc: doc.clone(),
                     span: *span,
                 }))
             }
@@ -101,22 +117,86 @@ impl SimplifierContext {
     }
 
     fn simp_type(&mut self, ty: Type) -> Type {
-        if let TypeEnum::Structure(struct_ty) = ty.kind {
-            let struct_ty = self.simp_struct_ty(struct_ty);
-            Type {
-                kind: TypeEnum::Structure(struct_ty),
-                span: ty.span,
+        let kind = match ty.kind {
+            TypeEnum::Structure(struct_ty) => TypeEnum::Structure(self.simp_struct_ty(struct_ty)),
+            TypeEnum::Slice(element) => TypeEnum::Slice(self.simp_exp(element)),
+            TypeEnum::Enum(EnumType { variants, span }) => {
+                let variants = variants
+                    .into_iter()
+                    .map(
+                        |Variant {
+                             name,
+                             payload,
+                             span,
+                         }| Variant {
+                            name,
+                            payload: payload.map(|ty| self.simp_exp(ty)),
+                            span,
+                        },
+                    )
+                    .collect();
+                TypeEnum::Enum(EnumType { variants, span })
+            }
+            TypeEnum::Interface(InterfaceType { methods, span, .. }) => {
+                let methods = methods
+                    .into_iter()
+                    .map(|(name, proto)| (name, self.simp_exp(proto)))
+                    .collect();
+                self.globals.push_layer();
+                let name = self.globals.prefixes().join("::");
+                self.globals.pop_layer();
+                TypeEnum::Interface(InterfaceType {
+                    methods,
+                    name: Some(name),
+                    span,
+                })
             }
-        } else {
-            ty
+            TypeEnum::Dyn(interface) => TypeEnum::Dyn(self.simp_exp(interface)),
+            kind => kind,
+        };
+        Type {
+            kind,
+            span: ty.span,
+        }
+    }
+
+    fn simp_enum_variants(&mut self, enum_ty: &EnumType) {
+        self.globals.push_layer();
+        let enum_path = self.globals.prefixes();
+        for variant in enum_ty.variants.iter() {
+            let ty = self.insert_exp(Exp::Var(Var {
+                path: Path {
+                    path: enum_path.clone(),
+                    span: variant.span,
+                },
+                span: variant.span,
+            }));
+            let exp = self.insert_exp(Exp::Variant(EnumVariant {
+                ty,
+                name: variant.name.clone(),
+                span: variant.span,
+            }));
+            self.extra_items
+                .push(GlobalItem::ConstDef(Arc::new(ConstDef {
+                    name: self.globals.prefixed_name(&variant.name),
+                    initial_value: ConstInitialValue::Exp(ConstExp { exp }),
+                    doc: None,
+                    span: variant.span,
+                })));
         }
+        self.globals.pop_layer();
     }
 
     fn simp_struct_ty(&mut self, struct_ty: StructType) -> StructType {
         let StructType {
             fields,
+            field_docs,
             members,
+            implements,
+            layout,
+            doc,
             span,
+            ..
         } = struct_ty;
         let fields = fields
             .into_iter()
@@ -125,7 +205,12 @@ impl SimplifierContext {
                 (name, ty)
             })
             .collect();
+        let implements = implements
+            .into_iter()
+            .map(|interface| self.simp_exp(interface))
+            .collect();
         self.globals.push_layer();
+        let namespace = self.globals.prefixes().join("::");
         for item in &members {
             match item {
                 GlobalItem::ConstDef(const_def) => {
@@ -140,7 +225,12 @@ impl SimplifierContext {
         self.globals.pop_layer();
         StructType {
             fields,
+            field_docs,
             members: vec![],
+            namespace: Some(namespace),
+            implements,
+            layout,
+            doc,
             span,
         }
     }
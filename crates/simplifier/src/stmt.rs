@@ -23,18 +23,20 @@ impl SimplifierContext {
         match item {
             BlockItem::VarDef(var_def) => {
                 let VarDef {
-                    name,
+                    binding,
                     var_type,
                     initial_value,
                     mutable,
                     span,
                 } = var_def;
                 let var_type = var_type.map(|ty| self.simp_exp(ty));
-                let initial_value = self.simp_exp(initial_value);
+                let initial_value = initial_value.map(|value| self.simp_exp(value));
 
-                self.locals.push(name.clone());
+                for name in binding.names() {
+                    self.locals.push(name.clone());
+                }
                 BlockItem::VarDef(VarDef {
-                    name,
+                    binding,
                     var_type,
                     initial_value,
                     mutable,
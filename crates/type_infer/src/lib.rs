@@ -1 +1,13 @@
+//! Inference of the types of integer literals written without a suffix.
+//!
+//! The analyzer gives every such literal a [`TypeVar`] and unifies it with
+//! the types its context expects in an [`InferTable`]. Once a body is checked
+//! the types the literals ended up with are recorded in the [`LiteralTypes`]
+//! of the query context, which is where codegen and `const_eval` look them
+//! up.
 
+pub use literal::*;
+pub use table::*;
+
+mod literal;
+mod table;
@@ -0,0 +1,41 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use ast::{Span, TypeEnum};
+
+/// Which instance of a generic function a literal was checked in, as the
+/// values its `comptime` parameters are bound to, sorted by name. The same
+/// literal may have a different type in every instance. Code outside of
+/// generic functions is in the empty scope.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Scope(Vec<(String, String)>);
+
+impl Scope {
+    pub fn new(bindings: impl IntoIterator<Item = (String, String)>) -> Self {
+        let mut bindings = bindings.into_iter().collect::<Vec<_>>();
+        bindings.sort();
+        Scope(bindings)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LiteralTypes(RwLock<HashMap<Scope, HashMap<Span, TypeEnum>>>);
+
+impl LiteralTypes {
+    pub fn record(&self, span: Span, scope: Scope, ty: TypeEnum) {
+        self.0
+            .write()
+            .unwrap()
+            .entry(scope)
+            .or_default()
+            .insert(span, ty);
+    }
+
+    pub fn get(&self, span: Span, scope: &Scope) -> Option<TypeEnum> {
+        self.0
+            .read()
+            .unwrap()
+            .get(scope)
+            .and_then(|literals| literals.get(&span))
+            .cloned()
+    }
+}
@@ -0,0 +1,76 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TypeVar(usize);
+
+#[derive(Debug, Clone)]
+enum Node<T> {
+    Root(Option<T>),
+    Link(usize),
+}
+
+/// Type variables, grouped into sets of variables unified with each other.
+/// Each set is bound to at most one type.
+#[derive(Debug, Clone)]
+pub struct InferTable<T> {
+    nodes: Vec<Node<T>>,
+}
+
+impl<T> Default for InferTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> InferTable<T> {
+    pub fn new() -> Self {
+        InferTable { nodes: Vec::new() }
+    }
+
+    pub fn new_var(&mut self) -> TypeVar {
+        self.nodes.push(Node::Root(None));
+        TypeVar(self.nodes.len() - 1)
+    }
+
+    pub fn find(&self, var: TypeVar) -> TypeVar {
+        let mut index = var.0;
+        while let Node::Link(next) = self.nodes[index] {
+            index = next;
+        }
+        TypeVar(index)
+    }
+
+    pub fn probe(&self, var: TypeVar) -> Option<&T> {
+        match &self.nodes[self.find(var).0] {
+            Node::Root(ty) => ty.as_ref(),
+            Node::Link(_) => unreachable!(),
+        }
+    }
+
+    pub fn bind(&mut self, var: TypeVar, ty: T) {
+        let root = self.find(var);
+        self.nodes[root.0] = Node::Root(Some(ty));
+    }
+}
+
+impl<T: PartialEq> InferTable<T> {
+    pub fn union(&mut self, a: TypeVar, b: TypeVar) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return true;
+        }
+        let Node::Root(bound) = std::mem::replace(&mut self.nodes[a.0], Node::Link(b.0)) else {
+            unreachable!()
+        };
+        match (bound, &mut self.nodes[b.0]) {
+            (None, _) => true,
+            (Some(ty), Node::Root(other @ None)) => {
+                *other = Some(ty);
+                true
+            }
+            (Some(ty), Node::Root(Some(other))) if ty == *other => true,
+            (ty, _) => {
+                self.nodes[a.0] = Node::Root(ty);
+                false
+            }
+        }
+    }
+}